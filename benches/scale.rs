@@ -0,0 +1,157 @@
+//! Time genesis and whole epochs of `Simulator::run_one_slot` on a large chain.
+//!
+//! cargo bench --bench scale -- [validators] [tickets] [epochs]
+//!
+//! Defaults to 10,000 validators holding 1,000,000 tickets over the full
+//! 254-bucket layout. Every epoch a few validators are punished, sending
+//! their tickets to the MUTED bucket until the next transition restores
+//! them, and a few tickets are retired, so slots and transitions move
+//! tickets as well as scan validators.
+
+use std::time::{Duration, Instant};
+
+use eternix_sim::scenario::config::{Scenario, TicketConfig, ValidatorConfig};
+use eternix_sim::state::bucket_ops::{any_muted_bucket, move_all_validator_tickets_to_bucket};
+use eternix_sim::state::chain_state::ChainState;
+use eternix_sim::state::retirement_ops::request_ticket_retire;
+use eternix_sim::state::root::state_root;
+use eternix_sim::types::ticket::TicketState;
+use eternix_sim::types::validator::ValidatorState;
+
+/// Epochs the target run covers; the mean time of an epoch of slots is
+/// extrapolated to it.
+const TARGET_EPOCHS: u64 = 100_000;
+/// Validators punished in each benchmarked epoch.
+const PUNISHED_PER_EPOCH: u64 = 10;
+
+const BASE: &str = r#"
+name = "scale"
+validators = []
+
+[chain]
+epoch_seed = "0707070707070707070707070707070707070707070707070707070707070707"
+epoch_len_slots = 32
+retire_per_epoch_limit = 2
+
+[buckets]
+active = { first = 0, count = 254 }
+muted = [254]
+dead = 255
+
+[run]
+epochs = 1
+"#;
+
+fn scenario(validators: u64, tickets: u64) -> Scenario {
+    let mut scenario = Scenario::from_toml_str(BASE).expect("base scenario is valid");
+    scenario.validators = (1..=validators)
+        .map(|id| ValidatorConfig {
+            id,
+            vault_balance: 10_000_000,
+            initial_bond: 1_000_000,
+            state: ValidatorState::Active,
+            cooldown_until_epoch: None,
+            behavior: None,
+        })
+        .collect();
+    scenario.tickets = (1..=tickets)
+        .map(|id| TicketConfig { id, owner: id % validators + 1, bucket: None, creation_epoch: 0 })
+        .collect();
+    scenario
+}
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let value = f();
+    (value, start.elapsed())
+}
+
+/// The count `process_epoch_transition` used before the ticket index: one
+/// pass over every ticket per validator.
+fn scan_active_count(state: &ChainState, validator_id: u64) -> u64 {
+    state.tickets.values().filter(|t| t.owner == validator_id && t.state == TicketState::Active).count() as u64
+}
+
+/// Punish a few validators the way slashing does, with a one-epoch cooldown,
+/// and ask one of them to retire a ticket.
+fn churn(state: &mut ChainState, epoch: u64, validators: u64) {
+    let muted = any_muted_bucket(state).unwrap();
+    let mut events = Vec::new();
+    for i in 0..PUNISHED_PER_EPOCH {
+        let vid = (epoch * PUNISHED_PER_EPOCH + i) % validators + 1;
+        let v = state.validators.get_mut(&vid).unwrap();
+        if v.state != ValidatorState::Active {
+            continue;
+        }
+        v.state = ValidatorState::PunishedCooldown;
+        v.cooldown_until_epoch = Some(state.epoch_index + 1);
+        move_all_validator_tickets_to_bucket(state, vid, muted).unwrap();
+    }
+    let vid = epoch % validators + 1;
+    let active = state.ticket_index.owned_by(vid).find(|tid| state.tickets[tid].state == TicketState::Active);
+    if let Some(tid) = active {
+        request_ticket_retire(state, vid, vec![tid], &mut events).unwrap();
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1).filter(|a| a != "--bench");
+    let number = |arg: Option<String>, default: u64| arg.map(|s| s.parse().expect("expected a number")).unwrap_or(default);
+    let validators = number(args.next(), 10_000);
+    let tickets = number(args.next(), 1_000_000);
+    let epochs = number(args.next(), 50);
+
+    println!("{} validators, {} tickets, 254 ACTIVE buckets", validators, tickets);
+    let scenario = scenario(validators, tickets);
+
+    let (mut state, elapsed) = timed(|| scenario.build_state().expect("scale scenario is valid"));
+    println!("genesis state:             {:>10.3?}", elapsed);
+    let ((), elapsed) = timed(|| state.rebuild_ticket_index());
+    println!("ticket index build:        {:>10.3?}", elapsed);
+
+    let sample: Vec<u64> = (1..=validators).step_by((validators / 10).max(1) as usize).collect();
+    let (scanned, elapsed) = timed(|| sample.iter().map(|&vid| scan_active_count(&state, vid)).collect::<Vec<_>>());
+    let per_validator = elapsed / sample.len() as u32;
+    println!(
+        "active count by scan:      {:>10.3?} per validator, {:.1?} per epoch for all of them",
+        per_validator,
+        per_validator * validators as u32
+    );
+    let (indexed, elapsed) = timed(|| sample.iter().map(|&vid| state.ticket_index.active_count(vid)).collect::<Vec<_>>());
+    assert_eq!(scanned, indexed);
+    println!("active count from index:   {:>10.3?} per validator", elapsed / sample.len() as u32);
+
+    let (copy, elapsed) = timed(|| state.clone());
+    println!("state clone:               {:>10.3?}", elapsed);
+    drop(copy);
+    let (_, elapsed) = timed(|| state_root(&state).expect("state encodes"));
+    println!("state root, full rebuild:  {:>10.3?}", elapsed);
+    drop(state);
+
+    let mut sim = scenario.build_simulator().expect("scale scenario is valid");
+    let (_, elapsed) = timed(|| sim.run_one_slot().expect("slot succeeds"));
+    println!("first slot (builds trees): {:>10.3?}", elapsed);
+    while sim.clock.slot_index < sim.epoch_len_slots {
+        sim.run_one_slot().expect("slot succeeds");
+    }
+
+    // Each epoch: churn, then every slot up to and including the one that
+    // closes the epoch with its transition.
+    let (mut total, mut slowest) = (Duration::ZERO, Duration::ZERO);
+    for epoch in 0..epochs {
+        churn(&mut sim.state, epoch, validators);
+        for _ in 0..sim.epoch_len_slots {
+            let (result, elapsed) = timed(|| sim.run_one_slot());
+            result.expect("slot succeeds");
+            total += elapsed;
+            slowest = slowest.max(elapsed);
+        }
+    }
+    let slots = epochs * sim.epoch_len_slots;
+    let per_epoch = total / epochs.max(1) as u32;
+    println!("slot (run_one_slot):       {:>10.3?} mean over {} slots, slowest {:.3?}", total / slots.max(1) as u32, slots, slowest);
+    println!("epoch:                     {:>10.3?} mean over {} epochs of {} slots", per_epoch, epochs, sim.epoch_len_slots);
+    println!("  x {} epochs:         {:>10.1?}", TARGET_EPOCHS, per_epoch * TARGET_EPOCHS as u32);
+    let counts = sim.state.ticket_index.counts();
+    println!("  tickets now {} active, {} retiring, {} dead", counts.active, counts.retiring, counts.dead);
+}
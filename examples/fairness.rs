@@ -0,0 +1,43 @@
+//! Leader-selection fairness for a scenario's genesis ticket layout.
+//!
+//! cargo run --release --example fairness -- [scenario] [samples]
+
+use eternix_sim::analysis::fairness::{analyze, scaled_min_bucket_probabilities, FairnessLayout};
+use eternix_sim::consensus::leader_selection::BucketSelection;
+use eternix_sim::scenario::config::Scenario;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| "scenarios/full_layout.toml".to_string());
+    let samples: u64 = args.next().map(|s| s.parse().expect("samples must be a number")).unwrap_or(1_000_000);
+
+    let state = Scenario::load(&path)
+        .and_then(|s| s.build_state())
+        .unwrap_or_else(|e| {
+            eprintln!("invalid scenario {}: {}", path, e);
+            std::process::exit(1);
+        });
+
+    let layout = FairnessLayout::from_state(&state);
+
+    let bucket_counts = layout.buckets.iter().map(|(&id, h)| (id, h.values().sum())).collect();
+    let total: u64 = layout.ticket_counts().values().sum();
+    println!("Bucket selection probabilities (scaled-min exact vs proportional):");
+    for (id, p) in scaled_min_bucket_probabilities(&bucket_counts) {
+        let share = bucket_counts[&id] as f64 / total as f64;
+        if (p - share).abs() > 1e-4 {
+            println!("  bucket {:>3}: {:.6} vs {:.6}", id, p, share);
+        }
+    }
+    println!();
+
+    for algorithm in [BucketSelection::ScaledMin, BucketSelection::Proportional] {
+        match analyze(&layout, algorithm, state.epoch_seed, samples) {
+            Ok(report) => println!("{}\n", report),
+            Err(e) => {
+                eprintln!("cannot analyse {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Run seeded replicas of a scenario and print metric distributions.
+//!
+//! cargo run --release --example monte_carlo -- [scenario] [replicas] [base_seed] [threads] [replicas.csv]
+//!
+//! Every replica's seed is listed in the optional CSV; rerunning the scenario
+//! with `[run] seed` set to it reproduces that replica exactly.
+
+use std::fs::File;
+use std::io::BufWriter;
+
+use eternix_sim::scenario::config::Scenario;
+use eternix_sim::sweep::monte_carlo::run_monte_carlo;
+use eternix_sim::sweep::table::write_csv;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| "scenarios/full_layout.toml".to_string());
+    let number = |arg: Option<String>, default: u64| arg.map(|s| s.parse().expect("expected a number")).unwrap_or(default);
+    let replicas = number(args.next(), 100) as usize;
+    let base_seed = number(args.next(), 0);
+    let threads = number(args.next(), 0) as usize;
+    let out = args.next();
+
+    let scenario = Scenario::load(&path).unwrap_or_else(|e| {
+        eprintln!("invalid scenario {}: {}", path, e);
+        std::process::exit(1);
+    });
+
+    let report = run_monte_carlo(&scenario, replicas, base_seed, threads);
+    println!("{}", report);
+
+    if let Some(out) = out {
+        let written = File::create(&out).and_then(|file| write_csv(&report.results, BufWriter::new(file)));
+        if let Err(e) = written {
+            eprintln!("cannot write {}: {}", out, e);
+            std::process::exit(1);
+        }
+    }
+}
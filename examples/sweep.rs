@@ -0,0 +1,53 @@
+//! Run a parameter sweep and write one row per run.
+//!
+//! cargo run --release --example sweep -- <sweep.toml> [threads] [out.csv|out.json]
+//!
+//! `threads` 0 (the default) uses one thread per core. Without an output path
+//! the CSV table goes to stdout.
+
+use std::fs::File;
+use std::io::BufWriter;
+
+use eternix_sim::scenario::config::Scenario;
+use eternix_sim::sweep::runner::run_sweep;
+use eternix_sim::sweep::spec::SweepSpec;
+use eternix_sim::sweep::table::{write_csv, write_json};
+
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| "sweeps/punishment.toml".to_string());
+    let threads: usize = args.next().map(|s| s.parse().expect("threads must be a number")).unwrap_or(0);
+    let out = args.next();
+
+    let spec = SweepSpec::load(&path).unwrap_or_else(|e| fail(format!("invalid sweep {}: {}", path, e)));
+    let base = Scenario::load(&spec.scenario).unwrap_or_else(|e| fail(format!("invalid scenario {}: {}", spec.scenario, e)));
+    let runs = spec.plan(&base).unwrap_or_else(|e| fail(format!("invalid sweep {}: {}", path, e)));
+
+    eprintln!("running {} runs", runs.len());
+    let results = run_sweep(&runs, threads);
+
+    let written = match out.as_deref() {
+        None => write_csv(&results, std::io::stdout().lock()),
+        Some(out) => {
+            let file = File::create(out).unwrap_or_else(|e| fail(format!("cannot create {}: {}", out, e)));
+            if out.ends_with(".json") {
+                write_json(&results, BufWriter::new(file))
+            } else {
+                write_csv(&results, BufWriter::new(file))
+            }
+        }
+    };
+    if let Err(e) = written {
+        fail(format!("cannot write results: {}", e));
+    }
+
+    let failed = results.iter().filter(|r| r.summary.is_err()).count();
+    if failed > 0 {
+        eprintln!("{} of {} runs failed", failed, results.len());
+    }
+}
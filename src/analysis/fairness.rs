@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::error::SimError;
+use crate::consensus::leader_selection::{select_bucket_with, select_ticket, slot_seed, BucketSelection};
+use crate::state::chain_state::ChainState;
+use crate::types::ticket::TicketState;
+
+/// Who holds how many tickets in each ACTIVE bucket.
+#[derive(Debug, Clone, Default)]
+pub struct FairnessLayout {
+    /// bucket_id -> (validator_id -> ticket count)
+    pub buckets: BTreeMap<u64, BTreeMap<u64, u64>>,
+}
+
+impl FairnessLayout {
+    /// Layout of the Active tickets currently sitting in ACTIVE buckets.
+    pub fn from_state(state: &ChainState) -> Self {
+        let mut layout = FairnessLayout::default();
+        for t in state.tickets.values() {
+            if t.state == TicketState::Active && state.active_bucket_ids.contains(&t.bucket) {
+                layout.add(t.bucket, t.owner, 1);
+            }
+        }
+        layout
+    }
+
+    pub fn add(&mut self, bucket_id: u64, validator_id: u64, tickets: u64) {
+        *self.buckets.entry(bucket_id).or_default().entry(validator_id).or_default() += tickets;
+    }
+
+    pub fn ticket_counts(&self) -> BTreeMap<u64, u64> {
+        let mut counts = BTreeMap::new();
+        for holders in self.buckets.values() {
+            for (&vid, &n) in holders {
+                *counts.entry(vid).or_default() += n;
+            }
+        }
+        counts
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidatorFairness {
+    pub validator: u64,
+    pub tickets: u64,
+    /// Ticket share, i.e. the win rate a perfectly fair scheme would give.
+    pub expected_share: f64,
+    pub wins: u64,
+    pub empirical_share: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FairnessReport {
+    pub algorithm: BucketSelection,
+    pub samples: u64,
+    pub validators: Vec<ValidatorFairness>,
+    pub chi_square: f64,
+    pub degrees_of_freedom: u64,
+    /// Upper-tail p-value of `chi_square` (Wilson–Hilferty approximation).
+    pub p_value: f64,
+    /// KL(empirical || ticket share), in nats.
+    pub kl_divergence: f64,
+}
+
+/// Run two-stage leader selection over `samples` slot seeds derived from
+/// `base_seed` and compare each validator's win rate with its ticket share.
+/// Fails with `SimError::NoEligibleTickets` on an empty layout.
+pub fn analyze(
+    layout: &FairnessLayout,
+    algorithm: BucketSelection,
+    base_seed: [u8; 32],
+    samples: u64,
+) -> Result<FairnessReport, SimError> {
+    // Synthetic ticket ids, allocated bucket by bucket.
+    let mut owners: Vec<u64> = Vec::new();
+    let mut bucket_tickets: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for (&bucket_id, holders) in &layout.buckets {
+        let ids = bucket_tickets.entry(bucket_id).or_default();
+        for (&vid, &n) in holders {
+            for _ in 0..n {
+                ids.push(owners.len() as u64);
+                owners.push(vid);
+            }
+        }
+    }
+
+    let bucket_counts: BTreeMap<u64, usize> = bucket_tickets
+        .iter()
+        .filter(|(_, ids)| !ids.is_empty())
+        .map(|(&id, ids)| (id, ids.len()))
+        .collect();
+
+    let mut wins: BTreeMap<u64, u64> = BTreeMap::new();
+    for sample in 0..samples {
+        let seed = slot_seed(base_seed, sample);
+        let bucket_id = select_bucket_with(algorithm, seed, &bucket_counts)?;
+        let ticket_id = select_ticket(seed, &bucket_tickets[&bucket_id])?;
+        *wins.entry(owners[ticket_id as usize]).or_default() += 1;
+    }
+
+    let ticket_counts = layout.ticket_counts();
+    let total_tickets = owners.len() as f64;
+
+    let validators: Vec<ValidatorFairness> = ticket_counts
+        .iter()
+        .map(|(&validator, &tickets)| {
+            let w = wins.get(&validator).copied().unwrap_or(0);
+            ValidatorFairness {
+                validator,
+                tickets,
+                expected_share: tickets as f64 / total_tickets,
+                wins: w,
+                empirical_share: w as f64 / samples as f64,
+            }
+        })
+        .collect();
+
+    let chi_square: f64 = validators
+        .iter()
+        .map(|v| {
+            let expected = v.expected_share * samples as f64;
+            (v.wins as f64 - expected).powi(2) / expected
+        })
+        .sum();
+    let degrees_of_freedom = validators.len().saturating_sub(1) as u64;
+
+    let kl_divergence: f64 = validators
+        .iter()
+        .filter(|v| v.empirical_share > 0.0)
+        .map(|v| v.empirical_share * (v.empirical_share / v.expected_share).ln())
+        .sum();
+
+    Ok(FairnessReport {
+        algorithm,
+        samples,
+        validators,
+        chi_square,
+        degrees_of_freedom,
+        p_value: chi_square_upper_tail(chi_square, degrees_of_freedom),
+        kl_divergence,
+    })
+}
+
+/// Exact bucket selection probabilities of `BucketSelection::ScaledMin`.
+///
+/// Bucket `b` scores `U_b / n_b` with `U_b` uniform, so
+/// `P(b) = ∫ n_b · Π_{c≠b} (1 - n_c·t)⁺ dt` over `t ∈ [0, 1/n_max]`,
+/// integrated here with composite Simpson's rule.
+pub fn scaled_min_bucket_probabilities(ticket_counts: &BTreeMap<u64, u64>) -> BTreeMap<u64, f64> {
+    const STEPS: usize = 20_000;
+
+    let counts: Vec<(u64, f64)> = ticket_counts
+        .iter()
+        .filter(|&(_, &n)| n > 0)
+        .map(|(&id, &n)| (id, n as f64))
+        .collect();
+    let n_max = counts.iter().map(|&(_, n)| n).fold(0.0, f64::max);
+    if counts.is_empty() {
+        return BTreeMap::new();
+    }
+
+    let upper = 1.0 / n_max;
+    let h = upper / STEPS as f64;
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &(id, n_b))| {
+            let integrand = |t: f64| {
+                n_b * counts
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, &(_, n_c))| (1.0 - n_c * t).max(0.0))
+                    .product::<f64>()
+            };
+
+            let mut sum = integrand(0.0) + integrand(upper);
+            for k in 1..STEPS {
+                let weight = if k % 2 == 1 { 4.0 } else { 2.0 };
+                sum += weight * integrand(k as f64 * h);
+            }
+            (id, sum * h / 3.0)
+        })
+        .collect()
+}
+
+/// Upper-tail probability of a chi-square statistic with `df` degrees of
+/// freedom, via the Wilson–Hilferty normal approximation.
+pub fn chi_square_upper_tail(chi_square: f64, df: u64) -> f64 {
+    if df == 0 {
+        return 1.0;
+    }
+    let k = df as f64;
+    let z = ((chi_square / k).cbrt() - (1.0 - 2.0 / (9.0 * k))) / (2.0 / (9.0 * k)).sqrt();
+    0.5 * erfc(z / std::f64::consts::SQRT_2)
+}
+
+/// Complementary error function (Numerical Recipes `erfcc`, |error| < 1.2e-7).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t * (-z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+        .exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+impl fmt::Display for FairnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "algorithm: {:?}, samples: {}", self.algorithm, self.samples)?;
+        writeln!(f, "{:>10} {:>8} {:>12} {:>12} {:>10}", "validator", "tickets", "share", "win rate", "ratio")?;
+        for v in &self.validators {
+            writeln!(
+                f,
+                "{:>10} {:>8} {:>12.6} {:>12.6} {:>10.4}",
+                v.validator,
+                v.tickets,
+                v.expected_share,
+                v.empirical_share,
+                v.empirical_share / v.expected_share
+            )?;
+        }
+        write!(
+            f,
+            "chi-square = {:.3} (df {}, p ≈ {:.4}), KL = {:.3e} nats",
+            self.chi_square, self.degrees_of_freedom, self.p_value, self.kl_divergence
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Validator 1 alone in a 1-ticket bucket, validator 2 alone in a 2-ticket bucket.
+    fn skewed_layout() -> FairnessLayout {
+        let mut layout = FairnessLayout::default();
+        layout.add(0, 1, 1);
+        layout.add(1, 2, 2);
+        layout
+    }
+
+    #[test]
+    fn scaled_min_probabilities_match_closed_form() {
+        // P(bucket 0) = ∫_0^{1/2} (1 - 2t) dt = 1/4, not the proportional 1/3.
+        let counts = BTreeMap::from([(0, 1), (1, 2)]);
+        let p = scaled_min_bucket_probabilities(&counts);
+        assert!((p[&0] - 0.25).abs() < 1e-9);
+        assert!((p[&1] - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scaled_min_bias_is_detected_and_proportional_is_fair() {
+        let layout = skewed_layout();
+
+        let scaled = analyze(&layout, BucketSelection::ScaledMin, [1u8; 32], 20_000).unwrap();
+        assert!((scaled.validators[0].empirical_share - 0.25).abs() < 0.02);
+        assert!(scaled.p_value < 1e-6);
+
+        let proportional = analyze(&layout, BucketSelection::Proportional, [1u8; 32], 20_000).unwrap();
+        assert!((proportional.validators[0].empirical_share - 1.0 / 3.0).abs() < 0.02);
+        assert!(proportional.p_value > 1e-3);
+    }
+}
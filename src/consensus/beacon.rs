@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::state::chain_state::ChainState;
+use crate::types::block::Block;
+
+/// Source of per-epoch randomness.
+///
+/// The simulator calls `on_block` after every slot and `next_epoch_seed`
+/// right before each epoch transition.
+pub trait RandomnessBeacon {
+    fn on_block(&mut self, state: &mut ChainState, block: &Block);
+
+    fn next_epoch_seed(&mut self, state: &ChainState) -> [u8; 32];
+
+    /// The spec this beacon was built from, so a snapshot can rebuild it.
+    fn spec(&self) -> Option<BeaconSpec> {
+        None
+    }
+}
+
+/// Genesis seed forever; leader schedule depends only on slot and tickets.
+pub struct StaticBeacon;
+
+/// RANDAO-style: each block folds its proposer's reveal (or a deterministic
+/// protocol filler) into `randao_mix`, and the mix at the end of an epoch
+/// becomes the next epoch's seed.
+pub struct RandaoBeacon;
+
+impl RandomnessBeacon for StaticBeacon {
+    fn on_block(&mut self, _state: &mut ChainState, _block: &Block) {}
+
+    fn next_epoch_seed(&mut self, state: &ChainState) -> [u8; 32] {
+        state.epoch_seed
+    }
+
+    fn spec(&self) -> Option<BeaconSpec> {
+        Some(BeaconSpec::Static)
+    }
+}
+
+impl RandomnessBeacon for RandaoBeacon {
+    fn on_block(&mut self, state: &mut ChainState, block: &Block) {
+        state.randao_mix = randao_next_mix(state.randao_mix, block.slot_index, block.proposer);
+    }
+
+    fn next_epoch_seed(&mut self, state: &ChainState) -> [u8; 32] {
+        state.randao_mix
+    }
+
+    fn spec(&self) -> Option<BeaconSpec> {
+        Some(BeaconSpec::Randao)
+    }
+}
+
+/// A validator's reveal for a slot. Deterministic, so the only choice a
+/// proposer has is whether to publish it (i.e. whether to propose at all).
+pub fn reveal(validator_id: u64, slot_index: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"eternix/reveal");
+    hasher.update(validator_id.to_be_bytes());
+    hasher.update(slot_index.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// Contribution of a protocol-produced block.
+pub fn protocol_filler(mix: [u8; 32], slot_index: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"eternix/filler");
+    hasher.update(mix);
+    hasher.update(slot_index.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// Mix after a block at `slot_index` produced by `proposer` (`None` = protocol block).
+pub fn randao_next_mix(mix: [u8; 32], slot_index: u64, proposer: Option<u64>) -> [u8; 32] {
+    let contribution = match proposer {
+        Some(vid) => reveal(vid, slot_index),
+        None => protocol_filler(mix, slot_index),
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(mix);
+    hasher.update(contribution);
+    hasher.finalize().into()
+}
+
+/// Serialisable beacon choice, as used in scenarios.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BeaconSpec {
+    #[default]
+    Static,
+    Randao,
+}
+
+impl BeaconSpec {
+    pub fn build(&self) -> Box<dyn RandomnessBeacon> {
+        match self {
+            BeaconSpec::Static => Box::new(StaticBeacon),
+            BeaconSpec::Randao => Box::new(RandaoBeacon),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::sim::behavior::BehaviorSpec;
+    use crate::sim::sink::MemorySink;
+    use crate::types::event::SimEvent;
+
+    fn leader_share(withhold: bool) -> f64 {
+        let mut scenario = Scenario::load("scenarios/randao_bias.toml").unwrap();
+        if !withhold {
+            scenario.validators[0].behavior = Some(BehaviorSpec::Honest);
+        }
+        scenario.run.epochs = Some(300);
+
+        let mut sim = scenario.build_simulator().unwrap();
+        let sink = MemorySink::default();
+        sim.add_sink(Box::new(sink.clone()));
+        for _ in 0..scenario.run_slots().unwrap() {
+            sim.run_one_slot().unwrap();
+        }
+
+        let leaders: Vec<u64> = sink
+            .records()
+            .iter()
+            .filter_map(|r| match r.event {
+                SimEvent::LeaderSelected { leader } => Some(leader),
+                _ => None,
+            })
+            .collect();
+        leaders.iter().filter(|&&l| l == 1).count() as f64 / leaders.len() as f64
+    }
+
+    #[test]
+    fn randao_seed_evolves_deterministically() {
+        let mix = [7u8; 32];
+        assert_eq!(randao_next_mix(mix, 3, Some(1)), randao_next_mix(mix, 3, Some(1)));
+        assert_ne!(randao_next_mix(mix, 3, Some(1)), randao_next_mix(mix, 3, None));
+        assert_ne!(randao_next_mix(mix, 3, Some(1)), randao_next_mix(mix, 3, Some(2)));
+    }
+
+    #[test]
+    fn withholding_biases_leader_share_upwards() {
+        assert!(leader_share(true) > leader_share(false));
+    }
+}
@@ -1,24 +1,35 @@
+use crate::consensus::evidence::expire_evidence;
+use crate::economics::inflation::mint_epoch_inflation;
+use crate::economics::rewards::report_epoch_rewards;
+use crate::error::SimError;
 use crate::state::chain_state::ChainState;
-use crate::state::bucket_ops::{any_active_bucket, any_muted_bucket, move_all_validator_tickets_to_bucket};
-use crate::types::ticket::TicketState;
+use crate::state::bucket_ops::{any_muted_bucket, move_all_validator_tickets_to_bucket, restore_validator_tickets};
 use crate::types::validator::ValidatorState;
 use crate::state::retirement_ops::{begin_retire_for_epoch, finalize_retire_for_epoch};
+use crate::consensus::sub_epoch::reset_sub_epoch;
+use crate::types::event::SimEvent;
+
+pub fn process_epoch_transition(state: &mut ChainState, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    mint_epoch_inflation(state, state.epoch_index, events)?;
+    report_epoch_rewards(state, state.epoch_index, events);
 
-pub fn process_epoch_transition(state: &mut ChainState) {
     state.epoch_index += 1;
-    println!("=== EPOCH TRANSITION → {} ===", state.epoch_index);
-    
-    println!("Processing retire begin for epoch {}", state.epoch_index);
-    begin_retire_for_epoch(state, state.epoch_index);
-    finalize_retire_for_epoch(state, state.epoch_index);
-
-    for (validator_id, val) in state.validators.iter_mut() {
-        let active_ticket_count = state.tickets.values()
-            .filter(|t| t.owner == *validator_id && t.state == TicketState::Active)
-            .count();
-
-        if active_ticket_count == 0 && val.state == ValidatorState::Active {
+    events.push(SimEvent::EpochTransition { epoch: state.epoch_index });
+    expire_evidence(state, events);
+
+    begin_retire_for_epoch(state, state.epoch_index, events)?;
+    finalize_retire_for_epoch(state, state.epoch_index, events)?;
+
+    let idle: Vec<u64> = state
+        .validators
+        .values()
+        .filter(|v| v.state == ValidatorState::Active && state.ticket_index.active_count(v.id) == 0)
+        .map(|v| v.id)
+        .collect();
+    for validator_id in idle {
+        if let Some(val) = state.validators.get_mut(&validator_id) {
             val.state = ValidatorState::Inactive;
+            events.push(SimEvent::ValidatorInactive { validator: validator_id });
         }
     }
 
@@ -27,14 +38,13 @@ pub fn process_epoch_transition(state: &mut ChainState) {
         vault_balance >= initial_bond
     };
 
-    let active_bucket = any_active_bucket(state);
-    let muted_bucket = any_muted_bucket(state);
+    let muted_bucket = any_muted_bucket(state)?;
 
     let validator_ids: Vec<u64> = state.validators.keys().copied().collect();
 
     for vid in validator_ids {
         let (st, until, _vault, _bond) = {
-            let v = state.validators.get(&vid).unwrap();
+            let v = &state.validators[&vid];
             (v.state, v.cooldown_until_epoch, v.vault_balance, v.initial_bond)
         };
 
@@ -42,21 +52,25 @@ pub fn process_epoch_transition(state: &mut ChainState) {
             continue;
         }
         
-        if st == ValidatorState::PunishedCooldown {
-            if let Some(until_epoch) = until {
-                if state.epoch_index >= until_epoch {
-                    let v = state.validators.get_mut(&vid).unwrap();
-                    v.cooldown_until_epoch = None;
-
-                    if required_min(v.vault_balance, v.initial_bond) {
-                        v.state = ValidatorState::Active;
-                        move_all_validator_tickets_to_bucket(state, vid, active_bucket);
-                    } else {
-                        v.state = ValidatorState::PausedLowVault;
-                        move_all_validator_tickets_to_bucket(state, vid, muted_bucket);
-                    }
-                }
+        if st == ValidatorState::PunishedCooldown
+            && let Some(until_epoch) = until
+            && state.epoch_index >= until_epoch
+        {
+            let Some(v) = state.validators.get_mut(&vid) else { continue; };
+            v.cooldown_until_epoch = None;
+
+            if required_min(v.vault_balance, v.initial_bond) {
+                v.state = ValidatorState::Active;
+                events.push(SimEvent::CooldownEnded { validator: vid, new_state: ValidatorState::Active });
+                restore_validator_tickets(state, vid)?;
+            } else {
+                v.state = ValidatorState::PausedLowVault;
+                events.push(SimEvent::CooldownEnded { validator: vid, new_state: ValidatorState::PausedLowVault });
+                events.push(SimEvent::PausedLowVault { validator: vid });
+                move_all_validator_tickets_to_bucket(state, vid, muted_bucket)?;
             }
         }
     }
-}
\ No newline at end of file
+
+    reset_sub_epoch(state, events)
+}
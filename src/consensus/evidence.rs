@@ -0,0 +1,350 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::slot::apply_double_sign_punishment;
+use crate::error::SimError;
+use crate::state::chain_state::ChainState;
+use crate::types::event::SimEvent;
+use crate::types::validator::ValidatorState;
+
+/// Two conflicting blocks signed by `validator` for the same slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DoubleSignEvidence {
+    pub validator: u64,
+    pub slot: u64,
+    /// Epoch of the offence; the evidence window counts from here.
+    pub epoch: u64,
+    pub block_ids: [u64; 2],
+}
+
+impl DoubleSignEvidence {
+    /// The offence this evidence proves. Evidence is deduplicated on it.
+    pub fn offence(&self) -> (u64, u64) {
+        (self.validator, self.slot)
+    }
+}
+
+/// The `[evidence]` table of a scenario. By default every observed
+/// equivocation is reported and punished in the slot it happens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvidenceParams {
+    /// Evidence of an offence in epoch `e` can be included up to epoch `e + window_epochs`.
+    #[serde(default = "default_window_epochs")]
+    pub window_epochs: u64,
+    /// Slots after the offence at which validators submit what they observed.
+    #[serde(default)]
+    pub report_delay_slots: u64,
+    /// When false, evidence is only submitted by scenario events.
+    #[serde(default = "default_auto_report")]
+    pub auto_report: bool,
+}
+
+fn default_window_epochs() -> u64 {
+    2
+}
+
+fn default_auto_report() -> bool {
+    true
+}
+
+/// Longest accepted evidence window, far past any run.
+pub const MAX_WINDOW_EPOCHS: u64 = 1 << 32;
+
+impl EvidenceParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_epochs > MAX_WINDOW_EPOCHS {
+            return Err(format!("window_epochs {} exceeds {}", self.window_epochs, MAX_WINDOW_EPOCHS));
+        }
+        if !self.auto_report && self.report_delay_slots > 0 {
+            return Err("report_delay_slots only applies with auto_report".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for EvidenceParams {
+    fn default() -> Self {
+        EvidenceParams {
+            window_epochs: default_window_epochs(),
+            report_delay_slots: 0,
+            auto_report: default_auto_report(),
+        }
+    }
+}
+
+/// Evidence that has been submitted, waiting for inclusion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmittedEvidence {
+    pub evidence: DoubleSignEvidence,
+    /// `None` when submitted by a scenario event on nobody's behalf.
+    pub reporter: Option<u64>,
+}
+
+/// Double-sign evidence on its way from observation to inclusion, keyed by offence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidencePool {
+    /// Seen on the network, not yet submitted.
+    #[serde(with = "entries")]
+    pub observed: BTreeMap<(u64, u64), DoubleSignEvidence>,
+    /// Submitted; included (and punished) at the end of the slot.
+    #[serde(with = "entries")]
+    pub submitted: BTreeMap<(u64, u64), SubmittedEvidence>,
+    /// Offences already punished, with their epoch, kept until they leave the window.
+    #[serde(with = "entries")]
+    pub included: BTreeMap<(u64, u64), u64>,
+}
+
+/// Offence-keyed maps as lists of `[key, value]` pairs, since JSON object
+/// keys cannot be tuples.
+mod entries {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K: Serialize, V: Serialize, S: Serializer>(
+        map: &BTreeMap<K, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<BTreeMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl EvidencePool {
+    fn contains(&self, offence: (u64, u64)) -> bool {
+        self.observed.contains_key(&offence)
+            || self.submitted.contains_key(&offence)
+            || self.included.contains_key(&offence)
+    }
+}
+
+fn is_expired(state: &ChainState, evidence_epoch: u64) -> bool {
+    evidence_epoch.saturating_add(state.evidence.window_epochs) < state.epoch_index
+}
+
+/// Record an equivocation seen in the current slot. Nothing is punished yet.
+pub fn observe_double_sign(state: &mut ChainState, evidence: DoubleSignEvidence, events: &mut Vec<SimEvent>) {
+    if state.evidence_pool.contains(evidence.offence()) {
+        return;
+    }
+    state.evidence_pool.observed.insert(evidence.offence(), evidence);
+    events.push(SimEvent::EvidenceObserved { validator: evidence.validator, slot: evidence.slot });
+}
+
+/// Submit the observed evidence of `validator` double-signing at `slot`.
+///
+/// Rejects offences nobody observed, offences already submitted or punished,
+/// evidence outside the window, and offenders reporting themselves.
+pub fn submit_evidence(
+    state: &mut ChainState,
+    validator: u64,
+    slot: u64,
+    reporter: Option<u64>,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    if let Some(reporter) = reporter
+        && !state.validators.contains_key(&reporter)
+    {
+        return Err(SimError::UnknownValidator(reporter));
+    }
+    if reporter == Some(validator) {
+        return Err(SimError::SelfReport(validator));
+    }
+
+    let pool = &state.evidence_pool;
+    if pool.submitted.contains_key(&(validator, slot)) || pool.included.contains_key(&(validator, slot)) {
+        return Err(SimError::DuplicateEvidence { validator, slot });
+    }
+    let evidence = *pool.observed.get(&(validator, slot)).ok_or(SimError::UnknownEvidence { validator, slot })?;
+    if is_expired(state, evidence.epoch) {
+        return Err(SimError::EvidenceExpired { validator, slot });
+    }
+
+    state.evidence_pool.observed.remove(&(validator, slot));
+    state.evidence_pool.submitted.insert((validator, slot), SubmittedEvidence { evidence, reporter });
+    events.push(SimEvent::EvidenceSubmitted { validator, slot, reporter });
+    Ok(())
+}
+
+/// Include every submitted piece of evidence, in offence order, and punish
+/// the offenders. Jailed offenders have nothing left to lose and are only
+/// marked as punished.
+pub fn include_evidence(state: &mut ChainState, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    for (offence, submitted) in std::mem::take(&mut state.evidence_pool.submitted) {
+        include(state, offence, submitted, events)?;
+    }
+    Ok(())
+}
+
+/// Submit `evidence` on `reporter`'s behalf and include it at once, punishing
+/// the offender now rather than at the end of the slot.
+pub fn report_and_include(
+    state: &mut ChainState,
+    evidence: &DoubleSignEvidence,
+    reporter: u64,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    let (validator, slot) = evidence.offence();
+    submit_evidence(state, validator, slot, Some(reporter), events)?;
+    let submitted = state
+        .evidence_pool
+        .submitted
+        .remove(&evidence.offence())
+        .ok_or(SimError::UnknownEvidence { validator, slot })?;
+    include(state, evidence.offence(), submitted, events)
+}
+
+fn include(
+    state: &mut ChainState,
+    offence: (u64, u64),
+    SubmittedEvidence { evidence, reporter }: SubmittedEvidence,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    let (validator, slot) = offence;
+    let offender = state.validators.get(&validator).ok_or(SimError::UnknownValidator(validator))?;
+    let jailed = offender.state == ValidatorState::Jailed;
+
+    state.evidence_pool.included.insert(offence, evidence.epoch);
+    events.push(SimEvent::EvidenceIncluded { validator, slot, reporter });
+    if !jailed {
+        apply_double_sign_punishment(state, validator, reporter, events)?;
+    }
+    Ok(())
+}
+
+/// Drop evidence that has left the window. Run after the epoch index advances.
+pub fn expire_evidence(state: &mut ChainState, events: &mut Vec<SimEvent>) {
+    let epoch = state.epoch_index;
+    let window = state.evidence.window_epochs;
+    let pool = &mut state.evidence_pool;
+
+    let expired: Vec<(u64, u64)> = pool
+        .observed
+        .values()
+        .filter(|e| e.epoch.saturating_add(window) < epoch)
+        .map(|e| e.offence())
+        .chain(pool.submitted.values().filter(|s| s.evidence.epoch.saturating_add(window) < epoch).map(|s| s.evidence.offence()))
+        .collect();
+    for (validator, slot) in expired {
+        pool.observed.remove(&(validator, slot));
+        pool.submitted.remove(&(validator, slot));
+        events.push(SimEvent::EvidenceExpired { validator, slot });
+    }
+
+    pool.included.retain(|_, &mut e| e.saturating_add(window) >= epoch);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::sim::simulator::Simulator;
+    use crate::sim::sink::MemorySink;
+    use crate::state::bucket_ops::move_all_validator_tickets_to_bucket;
+    use crate::sim::timeline::{Action, Trigger, TimelineEvent};
+
+    /// Validator 1 of the default scenario double-signs whenever it leads.
+    fn unreported(window_epochs: u64) -> Simulator {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        sim.state.evidence = EvidenceParams { window_epochs, report_delay_slots: 0, auto_report: false };
+        sim
+    }
+
+    fn run_until_observed(sim: &mut Simulator) -> (u64, u64) {
+        while sim.state.evidence_pool.observed.is_empty() {
+            sim.run_one_slot().unwrap();
+        }
+        *sim.state.evidence_pool.observed.keys().next().unwrap()
+    }
+
+    fn submit(sim: &mut Simulator, (validator, slot): (u64, u64)) -> Option<String> {
+        let at = sim.clock.slot_index;
+        let action = Action::SubmitEvidence { validator, slot, reporter: Some(2) };
+        sim.timeline.push(TimelineEvent { at: Trigger::Slot(at), action });
+        sim.run_one_slot().unwrap();
+        sim.applied_events.last().unwrap().error.clone()
+    }
+
+    #[test]
+    fn punishment_waits_for_inclusion_and_counts_once() {
+        let mut sim = unreported(2);
+        let offence = run_until_observed(&mut sim);
+        assert_eq!(sim.state.validators[&1].double_sign_offenses, 0);
+
+        assert_eq!(submit(&mut sim, offence), None);
+        assert_eq!(sim.state.validators[&1].double_sign_offenses, 1);
+
+        let duplicate = SimError::DuplicateEvidence { validator: 1, slot: offence.1 };
+        assert_eq!(submit(&mut sim, offence), Some(duplicate.to_string()));
+        assert_eq!(sim.state.validators[&1].double_sign_offenses, 1);
+    }
+
+    #[test]
+    fn undelayed_reports_punish_before_the_protocol_block() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        let sink = MemorySink::default();
+        sim.add_sink(Box::new(sink.clone()));
+        while sim.state.evidence_pool.included.is_empty() {
+            sim.run_one_slot().unwrap();
+        }
+        let (validator, slot) = *sim.state.evidence_pool.included.keys().next().unwrap();
+
+        let events: Vec<SimEvent> = sink.records().into_iter().filter(|r| r.slot == slot).map(|r| r.event).collect();
+        let included = events.iter().position(|e| *e == SimEvent::EvidenceIncluded { validator, slot, reporter: Some(2) });
+        let protocol_block = events.iter().position(|e| matches!(e, SimEvent::ProtocolBlock { .. }));
+        assert!(included.unwrap() < protocol_block.unwrap(), "{:?}", events);
+        assert_eq!(sim.state.validators[&validator].double_sign_offenses, 1);
+    }
+
+    #[test]
+    fn only_active_validators_report() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        let dead = sim.state.dead_bucket_id;
+        sim.state.validators.get_mut(&2).unwrap().state = ValidatorState::Jailed;
+        move_all_validator_tickets_to_bucket(&mut sim.state, 2, dead).unwrap();
+        run_until_observed(&mut sim);
+        sim.run_one_slot().unwrap();
+
+        assert!(sim.state.evidence_pool.submitted.is_empty());
+        assert!(sim.state.evidence_pool.included.is_empty());
+        assert_eq!(sim.state.validators[&1].double_sign_offenses, 0);
+    }
+
+    #[test]
+    fn unbounded_windows_never_expire() {
+        let mut sim = unreported(u64::MAX);
+        let offence = run_until_observed(&mut sim);
+        sim.state.epoch_index = u64::MAX - 1;
+        sim.run_one_slot().unwrap();
+        assert!(sim.state.evidence_pool.observed.contains_key(&offence));
+
+        assert!(sim.state.evidence.validate().is_err());
+        assert!(EvidenceParams { report_delay_slots: 3, auto_report: false, ..Default::default() }.validate().is_err());
+        assert!(EvidenceParams::default().validate().is_ok());
+    }
+
+    #[test]
+    fn evidence_outside_the_window_is_dropped() {
+        let mut sim = unreported(0);
+        let offence = run_until_observed(&mut sim);
+
+        let next_epoch = sim.state.epoch_index + 1;
+        while sim.state.epoch_index < next_epoch {
+            sim.run_one_slot().unwrap();
+        }
+        assert!(!sim.state.evidence_pool.observed.contains_key(&offence));
+        let unknown = SimError::UnknownEvidence { validator: 1, slot: offence.1 };
+        assert_eq!(submit(&mut sim, offence), Some(unknown.to_string()));
+        assert_eq!(sim.state.validators[&1].double_sign_offenses, 0);
+    }
+}
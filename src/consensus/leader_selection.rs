@@ -1,7 +1,24 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use crate::consensus::slot::has_eligible_tickets;
+use crate::consensus::sub_epoch::sub_epoch_seed;
+use crate::error::SimError;
 use crate::state::chain_state::ChainState;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+/// How the first stage of leader selection picks an ACTIVE bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BucketSelection {
+    /// Lowest `hash / ticket_count` wins. Cheap, but selection probabilities are
+    /// only approximately proportional to ticket count.
+    #[default]
+    ScaledMin,
+    /// Uniform draw over all eligible tickets, mapped to buckets by cumulative
+    /// ticket count in bucket-id order. Exactly proportional.
+    Proportional,
+}
+
 fn hash_bytes(data: &[u8]) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(data);
@@ -18,8 +35,8 @@ pub fn slot_seed(epoch_seed: [u8; 32], slot_index: u64) -> [u8; 32] {
 
 pub fn select_bucket(
     slot_seed: [u8; 32],
-    buckets: &HashMap<u64, usize>, // bucket_id -> ticket_count
-) -> u64 {
+    buckets: &BTreeMap<u64, usize>, // bucket_id -> ticket_count
+) -> Result<u64, SimError> {
     let mut best_bucket: Option<u64> = None;
     let mut best_score: Option<u128> = None;
 
@@ -54,13 +71,71 @@ pub fn select_bucket(
         }
     }
 
-    best_bucket.expect("No active buckets available")
+    best_bucket.ok_or(SimError::NoEligibleTickets)
+}
+
+pub fn select_bucket_with(
+    algorithm: BucketSelection,
+    slot_seed: [u8; 32],
+    buckets: &BTreeMap<u64, usize>, // bucket_id -> ticket_count
+) -> Result<u64, SimError> {
+    match algorithm {
+        BucketSelection::ScaledMin => select_bucket(slot_seed, buckets),
+        BucketSelection::Proportional => select_bucket_proportional(slot_seed, buckets),
+    }
+}
+
+/// Pick a bucket with probability exactly `ticket_count / total_tickets`.
+///
+/// Draws `r` uniformly from `[0, total)` by rejection sampling on successive
+/// 128-bit hashes, then walks buckets in id order by cumulative count.
+pub fn select_bucket_proportional(
+    slot_seed: [u8; 32],
+    buckets: &BTreeMap<u64, usize>, // bucket_id -> ticket_count
+) -> Result<u64, SimError> {
+    let ordered: Vec<(u64, u128)> = buckets
+        .iter()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(&id, &count)| (id, count as u128))
+        .collect();
+
+    let total: u128 = ordered.iter().map(|&(_, count)| count).sum();
+    if total == 0 {
+        return Err(SimError::NoEligibleTickets);
+    }
+
+    // Largest multiple of `total` representable; draws at or above it are rejected.
+    let limit = u128::MAX - (u128::MAX % total);
+
+    let mut counter = 0u64;
+    let r = loop {
+        let mut data = Vec::new();
+        data.extend_from_slice(&slot_seed);
+        data.extend_from_slice(b"bucket");
+        data.extend_from_slice(&counter.to_be_bytes());
+
+        let hash = hash_bytes(&data);
+        let raw = u128::from_be_bytes(hash[0..16].try_into().unwrap());
+        if raw < limit {
+            break raw % total;
+        }
+        counter += 1;
+    };
+
+    let mut cumulative = 0u128;
+    for (bucket_id, count) in ordered {
+        cumulative += count;
+        if r < cumulative {
+            return Ok(bucket_id);
+        }
+    }
+    unreachable!("r < total")
 }
 
 pub fn select_ticket(
     slot_seed: [u8; 32],
     ticket_ids: &[u64],
-) -> u64 {
+) -> Result<u64, SimError> {
     let mut best_ticket: Option<u64> = None;
     let mut best_score: Option<[u8; 32]> = None;
 
@@ -85,18 +160,27 @@ pub fn select_ticket(
         }
     }
 
-    best_ticket.expect("Bucket contained no tickets")
+    best_ticket.ok_or(SimError::EmptyTicketSet)
 }
 
 pub fn select_leader(
     state: &ChainState,
     slot_index: u64,
-) -> u64 {
-    // panic!("select_leader CALLLED");
-    let seed = slot_seed(state.epoch_seed, slot_index);
+) -> Result<u64, SimError> {
+    select_leader_with_seed(state, state.sub_epoch_seed, slot_index)
+}
+
+/// Leader for `slot_index` as if the (sub-)epoch seed were `epoch_seed`.
+/// Used to evaluate hypothetical seeds without touching state.
+pub fn select_leader_with_seed(
+    state: &ChainState,
+    epoch_seed: [u8; 32],
+    slot_index: u64,
+) -> Result<u64, SimError> {
+    let seed = slot_seed(epoch_seed, slot_index);
 
     // Build bucket_id -> ticket_count map
-    let mut bucket_counts: HashMap<u64, usize> = HashMap::new();
+    let mut bucket_counts: BTreeMap<u64, usize> = BTreeMap::new();
 
     for &bucket_id in &state.active_bucket_ids {
         if let Some(bucket) = state.buckets.get(&bucket_id) {
@@ -107,27 +191,63 @@ pub fn select_leader(
         }
     }
 
-    let bucket_id = select_bucket(seed, &bucket_counts);
+    let bucket_id = select_bucket_with(state.bucket_selection, seed, &bucket_counts)?;
 
     let bucket = state
         .buckets
         .get(&bucket_id)
-        .expect("Selected bucket missing");
+        .ok_or(SimError::UnknownBucket(bucket_id))?;
 
-    let ticket_id = select_ticket(seed, &bucket.ticket_ids.iter().copied().collect::<Vec<_>>());
+    let ticket_ids: Vec<u64> = bucket.ticket_ids.iter().copied().collect();
+    let ticket_id = select_ticket(seed, &ticket_ids)?;
 
     let ticket = state
         .tickets
         .get(&ticket_id)
-        .expect("Selected ticket missing");
+        .ok_or(SimError::UnknownTicket(ticket_id))?;
+
+    Ok(ticket.owner)
+}
+
+/// Leaders of the epoch starting at `epoch_start_slot`, as drawn from
+/// `state` with no changes during the epoch: one `(slot, leader)` per slot,
+/// with `None` where no ticket is eligible (a protocol block). Each
+/// sub-epoch uses its own seed derived from `state.epoch_seed`.
+pub fn leader_schedule(
+    state: &ChainState,
+    epoch_start_slot: u64,
+    epoch_len_slots: u64,
+    sub_epoch_len_slots: u64,
+) -> Result<Vec<(u64, Option<u64>)>, SimError> {
+    leader_schedule_with_seed(state, state.epoch_seed, epoch_start_slot, epoch_len_slots, sub_epoch_len_slots)
+}
+
+/// `leader_schedule` as if the epoch seed were `epoch_seed`, e.g. to forecast
+/// the next epoch from a candidate beacon output.
+pub fn leader_schedule_with_seed(
+    state: &ChainState,
+    epoch_seed: [u8; 32],
+    epoch_start_slot: u64,
+    epoch_len_slots: u64,
+    sub_epoch_len_slots: u64,
+) -> Result<Vec<(u64, Option<u64>)>, SimError> {
+    let eligible = has_eligible_tickets(state);
 
-    ticket.owner
+    (epoch_start_slot..epoch_start_slot + epoch_len_slots)
+        .map(|slot| {
+            if !eligible {
+                return Ok((slot, None));
+            }
+            let sub_epoch = (slot - epoch_start_slot).checked_div(sub_epoch_len_slots).unwrap_or(0);
+            let seed = sub_epoch_seed(epoch_seed, sub_epoch);
+            Ok((slot, Some(select_leader_with_seed(state, seed, slot)?)))
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn slot_seed_is_deterministic() {
@@ -141,7 +261,7 @@ mod tests {
     fn bucket_selection_is_deterministic() {
         let seed = [1u8; 32];
 
-        let mut buckets = HashMap::new();
+        let mut buckets = BTreeMap::new();
         buckets.insert(1, 10);
         buckets.insert(2, 20);
         buckets.insert(3, 30);
@@ -151,5 +271,32 @@ mod tests {
 
         assert_eq!(a, b);
     }
-}
 
+    #[test]
+    fn empty_inputs_are_errors() {
+        let seed = [1u8; 32];
+        let empty = BTreeMap::from([(1, 0)]);
+
+        assert_eq!(select_bucket(seed, &empty), Err(SimError::NoEligibleTickets));
+        assert_eq!(select_bucket_proportional(seed, &empty), Err(SimError::NoEligibleTickets));
+        assert_eq!(select_ticket(seed, &[]), Err(SimError::EmptyTicketSet));
+    }
+
+    #[test]
+    fn schedule_matches_the_leaders_that_propose() {
+        let mut scenario = crate::scenario::config::Scenario::load("scenarios/full_layout.toml").unwrap();
+        scenario.chain.sub_epoch_len_slots = 8;
+        let mut sim = scenario.build_simulator().unwrap();
+        let schedule =
+            leader_schedule(&sim.state, sim.epoch_start_slot, sim.epoch_len_slots, sim.sub_epoch_len_slots).unwrap();
+        assert_eq!(schedule.len() as u64, sim.epoch_len_slots);
+
+        for &(slot, leader) in &schedule {
+            let block = sim.run_one_slot().unwrap();
+            assert_eq!(block.slot_index, slot);
+            if block.proposer.is_some() {
+                assert_eq!(block.proposer, leader);
+            }
+        }
+    }
+}
@@ -2,3 +2,6 @@ pub mod leader_selection;
 pub mod slot;
 pub mod sub_epoch;
 pub mod epoch;
+pub mod beacon;
+pub mod evidence;
+pub mod params;
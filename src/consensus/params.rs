@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+use crate::economics::slashing::SlashBasis;
+
+/// One step of the double-sign escalation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DoubleSignTier {
+    /// Slash rate in basis points of `basis`.
+    pub slash_bps: u32,
+    #[serde(default)]
+    pub basis: SlashBasis,
+    /// The cooldown ends at the start of epoch `offence epoch + cooldown_epochs`.
+    #[serde(default)]
+    pub cooldown_epochs: u64,
+    /// Jail instead of a cooldown. Must be the last tier.
+    #[serde(default)]
+    pub jail: bool,
+}
+
+/// Punishment parameters, the `[protocol]` table of a scenario. The default
+/// preset is the rule set described in the README.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtocolParams {
+    /// `miss_counter` value at which the first liveness slash happens.
+    #[serde(default = "default_liveness_miss_threshold")]
+    pub liveness_miss_threshold: u32,
+    /// Past the threshold, another slash every this many misses.
+    #[serde(default = "default_liveness_repeat_interval")]
+    pub liveness_repeat_interval: u32,
+    /// Liveness slash rate in basis points of `liveness_slash_basis`.
+    #[serde(default = "default_liveness_slash_bps")]
+    pub liveness_slash_bps: u32,
+    #[serde(default)]
+    pub liveness_slash_basis: SlashBasis,
+    /// The liveness cooldown ends at the start of epoch `offence epoch + liveness_cooldown_epochs`.
+    #[serde(default = "default_liveness_cooldown_epochs")]
+    pub liveness_cooldown_epochs: u64,
+    /// Tier `n` applies to the `n`-th offence; the last tier repeats.
+    #[serde(default = "default_double_sign_tiers")]
+    pub double_sign_tiers: Vec<DoubleSignTier>,
+}
+
+fn default_liveness_miss_threshold() -> u32 {
+    5
+}
+
+fn default_liveness_repeat_interval() -> u32 {
+    100
+}
+
+fn default_liveness_slash_bps() -> u32 {
+    500
+}
+
+fn default_liveness_cooldown_epochs() -> u64 {
+    2
+}
+
+fn default_double_sign_tiers() -> Vec<DoubleSignTier> {
+    vec![
+        // 50%, then 50% of what is left (75% of the original vault), then everything.
+        DoubleSignTier { slash_bps: 5_000, basis: SlashBasis::CurrentVault, cooldown_epochs: 3, jail: false },
+        DoubleSignTier { slash_bps: 5_000, basis: SlashBasis::CurrentVault, cooldown_epochs: 6, jail: false },
+        DoubleSignTier { slash_bps: 10_000, basis: SlashBasis::CurrentVault, cooldown_epochs: 0, jail: true },
+    ]
+}
+
+impl Default for ProtocolParams {
+    fn default() -> Self {
+        ProtocolParams {
+            liveness_miss_threshold: default_liveness_miss_threshold(),
+            liveness_repeat_interval: default_liveness_repeat_interval(),
+            liveness_slash_bps: default_liveness_slash_bps(),
+            liveness_slash_basis: SlashBasis::CurrentVault,
+            liveness_cooldown_epochs: default_liveness_cooldown_epochs(),
+            double_sign_tiers: default_double_sign_tiers(),
+        }
+    }
+}
+
+impl ProtocolParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.liveness_miss_threshold == 0 {
+            return Err("liveness_miss_threshold must be greater than zero".to_string());
+        }
+        if self.liveness_repeat_interval == 0 {
+            return Err("liveness_repeat_interval must be greater than zero".to_string());
+        }
+        if self.liveness_slash_bps > 10_000 {
+            return Err(format!("liveness_slash_bps {} exceeds 10000", self.liveness_slash_bps));
+        }
+        if self.liveness_cooldown_epochs == 0 {
+            return Err("liveness_cooldown_epochs must be greater than zero".to_string());
+        }
+
+        if self.double_sign_tiers.is_empty() {
+            return Err("double_sign_tiers must not be empty".to_string());
+        }
+        let last = self.double_sign_tiers.len() - 1;
+        for (i, tier) in self.double_sign_tiers.iter().enumerate() {
+            let n = i + 1;
+            if tier.slash_bps > 10_000 {
+                return Err(format!("double-sign tier {}: slash_bps {} exceeds 10000", n, tier.slash_bps));
+            }
+            if tier.jail && i != last {
+                return Err(format!("double-sign tier {} jails, so later tiers are unreachable", n));
+            }
+            if tier.jail && tier.cooldown_epochs > 0 {
+                return Err(format!("double-sign tier {} jails and cannot also set cooldown_epochs", n));
+            }
+            if !tier.jail && tier.cooldown_epochs == 0 {
+                return Err(format!("double-sign tier {} needs cooldown_epochs greater than zero", n));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `miss_counter` going from `prev` to `now` triggers a liveness slash.
+    pub fn should_liveness_slash(&self, prev: u32, now: u32) -> bool {
+        let threshold = self.liveness_miss_threshold;
+        if now <= prev || now < threshold {
+            return false;
+        }
+        (now - threshold).is_multiple_of(self.liveness_repeat_interval)
+    }
+
+    /// Tier for a validator's `offense`-th double-sign (1-based).
+    pub fn double_sign_tier(&self, offense: u8) -> DoubleSignTier {
+        let i = (offense.max(1) as usize - 1).min(self.double_sign_tiers.len() - 1);
+        self.double_sign_tiers[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_preset_matches_the_original_rules() {
+        let params = ProtocolParams::default();
+        assert!(params.validate().is_ok());
+
+        let slashed: Vec<u32> = (1..=400).filter(|&n| params.should_liveness_slash(n - 1, n)).collect();
+        assert_eq!(slashed, vec![5, 105, 205, 305]);
+        assert!(!params.should_liveness_slash(5, 5));
+
+        assert!(!params.double_sign_tier(2).jail);
+        assert!(params.double_sign_tier(3).jail);
+        assert!(params.double_sign_tier(9).jail);
+    }
+
+    #[test]
+    fn nonsensical_tiers_are_rejected() {
+        let mut params = ProtocolParams::default();
+        params.double_sign_tiers.swap(1, 2);
+        assert!(params.validate().unwrap_err().contains("unreachable"));
+
+        params.double_sign_tiers.clear();
+        assert!(params.validate().is_err());
+
+        let params = ProtocolParams { liveness_slash_bps: 20_000, ..Default::default() };
+        assert!(params.validate().is_err());
+    }
+}
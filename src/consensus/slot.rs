@@ -1,3 +1,7 @@
+use crate::consensus::evidence::{observe_double_sign, report_and_include, DoubleSignEvidence};
+use crate::economics::rewards::{reward_block, reward_protocol_block};
+use crate::economics::slashing::{slash, SlashReason};
+use crate::error::SimError;
 use crate::state::chain_state::ChainState;
 use crate::types::block::Block;
 use crate::consensus::leader_selection::select_leader;
@@ -5,16 +9,11 @@ use crate::state::bucket_ops::{any_muted_bucket, move_all_validator_tickets_to_b
 use crate::types::validator::ValidatorState;
 use crate::types::proposal::Proposal;
 use crate::state::validator_ops::jail_validator;
+use crate::types::event::{ProtocolBlockReason, SimEvent};
 
-pub fn process_slot(
-    state: &mut ChainState,
-    slot_index: u64,
-    slot_start_ms: u64,
-    proposals: &[Proposal],
-) -> Block {
-    
-    // If no ACTIVE buckets exist, protocol produces block immediately
-    let has_eligible_tickets = state
+/// True if at least one ACTIVE bucket holds a ticket, i.e. a leader can be selected.
+pub fn has_eligible_tickets(state: &ChainState) -> bool {
+    state
         .active_bucket_ids
         .iter()
         .any(|bucket_id| {
@@ -23,18 +22,43 @@ pub fn process_slot(
                 .get(bucket_id)
                 .map(|b| !b.ticket_ids.is_empty())
                 .unwrap_or(false)
-        });
+        })
+}
 
-    if !has_eligible_tickets {
-        return Block {
-            slot_index,
-            timestamp_ms: slot_start_ms + 3_000,
-            proposer: None,
-        };
+/// The leader of `slot_index`, or `None` if no ACTIVE bucket holds a ticket.
+pub fn slot_leader(state: &ChainState, slot_index: u64) -> Result<Option<u64>, SimError> {
+    if !has_eligible_tickets(state) {
+        return Ok(None);
     }
+    select_leader(state, slot_index).map(Some)
+}
+
+/// Who submits evidence of a double-sign seen in the current slot, if anyone.
+pub type SameSlotReporter<'a> = dyn FnMut(&ChainState, &DoubleSignEvidence) -> Option<u64> + 'a;
 
-    // Select leader (pure)
-    let leader = select_leader(state, slot_index);
+/// Run one slot on `proposals`, led by `leader` as computed by
+/// `slot_leader` on the state passed in.
+///
+/// When evidence is reported without delay (`auto_report` with
+/// `report_delay_slots = 0`), a leader that double-signs is reported by
+/// `reporter` and punished here, before the slot's protocol block is
+/// rewarded; otherwise the evidence waits in the pool.
+pub fn process_slot(
+    state: &mut ChainState,
+    slot_index: u64,
+    slot_start_ms: u64,
+    leader: Option<u64>,
+    proposals: &[Proposal],
+    reporter: &mut SameSlotReporter<'_>,
+    events: &mut Vec<SimEvent>,
+) -> Result<Block, SimError> {
+    // If no ACTIVE buckets exist, protocol produces block immediately
+    let Some(leader) = leader else {
+        events.push(SimEvent::ProtocolBlock { reason: ProtocolBlockReason::NoEligibleTickets });
+        reward_protocol_block(state, events)?;
+        return Ok(unsealed_block(state, slot_index, slot_start_ms, None));
+    };
+    events.push(SimEvent::LeaderSelected { leader });
 
     // Collect all proposlas from the selected leader
     let leader_proposals: Vec<&Proposal> = proposals
@@ -42,7 +66,7 @@ pub fn process_slot(
         .filter(|p| p.proposer_id == leader)
         .collect();
 
-    let mut unique_block_ids = std::collections::HashSet::new();
+    let mut unique_block_ids = std::collections::BTreeSet::new();
     for p in &leader_proposals {
         unique_block_ids.insert(p.block_id);
     }
@@ -50,14 +74,29 @@ pub fn process_slot(
     let leader_double_signed = unique_block_ids.len() >= 2;
 
     if leader_double_signed {
-        apply_double_sign_punishment(state, leader);
+        state.liveness.get_or_default(leader).missed += 1;
+
+        // Punished once the evidence is submitted and included; see `consensus::evidence`.
+        let mut conflicting = unique_block_ids.iter().copied();
+        let evidence = DoubleSignEvidence {
+            validator: leader,
+            slot: slot_index,
+            epoch: state.epoch_index,
+            block_ids: [conflicting.next().unwrap_or_default(), conflicting.next().unwrap_or_default()],
+        };
+        observe_double_sign(state, evidence, events);
+        let params = state.evidence;
+        if params.auto_report
+            && params.report_delay_slots == 0
+            && let Some(reporter) = reporter(state, &evidence)
+        {
+            report_and_include(state, &evidence, reporter, events)?;
+        }
 
         // Protocol-produced block
-        return Block {
-            slot_index,
-            timestamp_ms: slot_start_ms + 3_000,
-            proposer: None,
-        };
+        events.push(SimEvent::ProtocolBlock { reason: ProtocolBlockReason::DoubleSign });
+        reward_protocol_block(state, events)?;
+        return Ok(unsealed_block(state, slot_index, slot_start_ms, None));
     }
 
     let proposer: Option<u64>;
@@ -65,107 +104,92 @@ pub fn process_slot(
     if leader_proposals.len() == 1 {
         // Validator successfully proposed
         proposer = Some(leader);
+        events.push(SimEvent::BlockProposed { proposer: leader, block_id: leader_proposals[0].block_id });
+        state.liveness.get_or_default(leader).proposed += 1;
 
-        let val = state.validators.get_mut(&leader).unwrap();
+        let val = state.validators.get_mut(&leader).ok_or(SimError::UnknownValidator(leader))?;
         val.miss_counter = val.miss_counter.saturating_sub(1);
+
+        reward_block(state, leader, events)?;
     } else {
         //Protocol-produced block (miss)
         proposer = None;
+        state.liveness.get_or_default(leader).missed += 1;
 
-        let val = state.validators.get_mut(&leader).unwrap();
+        let val = state.validators.get_mut(&leader).ok_or(SimError::UnknownValidator(leader))?;
         let prev = val.miss_counter;
         val.miss_counter += 1;
+        let miss_counter = val.miss_counter;
 
-        if should_liveness_slash(prev, val.miss_counter) {
-            apply_liveness_slash(state, leader);
+        events.push(SimEvent::ProtocolBlock { reason: ProtocolBlockReason::LeaderMissed });
+        events.push(SimEvent::MissRecorded { validator: leader, miss_counter });
+        reward_protocol_block(state, events)?;
+
+        if state.protocol.should_liveness_slash(prev, miss_counter) {
+            apply_liveness_slash(state, leader, events)?;
         }
     }
 
-    // Publish block at slot end
+    Ok(unsealed_block(state, slot_index, slot_start_ms, proposer))
+}
+
+/// The slot's block, published at slot end. `state_root` and `parent_hash`
+/// are left zero; the simulator fills them in once the slot is complete.
+fn unsealed_block(state: &ChainState, slot_index: u64, slot_start_ms: u64, proposer: Option<u64>) -> Block {
     Block {
         slot_index,
+        sub_epoch_index: state.sub_epoch_index,
         timestamp_ms: slot_start_ms + 3_000,
         proposer,
+        state_root: [0; 32],
+        parent_hash: [0; 32],
     }
 }
 
-fn should_liveness_slash(prev: u32, now: u32) -> bool {
-    if now <= prev {
-        return false;
-    }
-
-    if now == 5 {
-        return true;
-    }
-
-    now > 5 && (now - 5) % 100 == 0
-}
-
-fn apply_liveness_slash(state: &mut ChainState, validator_id: u64) {
-    let val = state.validators.get_mut(&validator_id).unwrap();
-
-    // 5% slash
-    let slash_amount = val.vault_balance / 20;
-    val.vault_balance -= slash_amount;
+fn apply_liveness_slash(state: &mut ChainState, validator_id: u64, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    let params = &state.protocol;
+    let (bps, basis) = (params.liveness_slash_bps, params.liveness_slash_basis);
+    // Muted for the rest of this epoch and the next `liveness_cooldown_epochs - 1`.
+    let until_epoch = state.epoch_index + params.liveness_cooldown_epochs;
 
-    println!(
-        "!!!  LIVENESS SLASH: validator {} slashed by 5%, new vault = {} !!!",
-        validator_id, val.vault_balance
-    );
+    slash(state, validator_id, bps, basis, SlashReason::Liveness, None, events)?;
 
-    // Enter cooldown for 1 epoch
+    let val = state.validators.get_mut(&validator_id).ok_or(SimError::UnknownValidator(validator_id))?;
     val.state = ValidatorState::PunishedCooldown;
-    val.cooldown_until_epoch = Some(state.epoch_index + 2);
+    val.cooldown_until_epoch = Some(until_epoch);
+    events.push(SimEvent::CooldownStarted { validator: validator_id, until_epoch });
 
     // Move tickets to MUTED immediately
-    let muted_bucket = any_muted_bucket(state);
-    move_all_validator_tickets_to_bucket(state, validator_id, muted_bucket);
+    let muted_bucket = any_muted_bucket(state)?;
+    move_all_validator_tickets_to_bucket(state, validator_id, muted_bucket)
 }
 
-fn apply_double_sign_punishment(state: &mut ChainState, validator_id: u64) {
-    let val = state.validators.get_mut(&validator_id).unwrap();
-
-    val.double_sign_offenses += 1;
+/// Escalating double-sign punishment per `state.protocol.double_sign_tiers`;
+/// `reporter` submitted the evidence.
+pub fn apply_double_sign_punishment(
+    state: &mut ChainState,
+    validator_id: u64,
+    reporter: Option<u64>,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    let val = state.validators.get_mut(&validator_id).ok_or(SimError::UnknownValidator(validator_id))?;
+    val.double_sign_offenses = val.double_sign_offenses.saturating_add(1);
     let offense = val.double_sign_offenses;
+    let tier = state.protocol.double_sign_tier(offense);
 
-    match offense {
-        1 => {
-            // 50% slash
-            val.vault_balance /= 2;
-            println!(
-                "!!! DOUBLE-SIGN: validator {} offense #1 => 50% slash, 2 epoch mute. New vault={} !!!",
-                validator_id, val.vault_balance
-            );
-
-            val.state = ValidatorState::PunishedCooldown;
-            val.cooldown_until_epoch = Some(state.epoch_index + 2 + 1);
+    let reason = SlashReason::DoubleSign { offense };
+    slash(state, validator_id, tier.slash_bps, tier.basis, reason, reporter, events)?;
 
-            let muted = any_muted_bucket(state);
-            move_all_validator_tickets_to_bucket(state, validator_id, muted);
-        }
-        2 => {
-            // 75% slash
-            val.vault_balance /= 4;
-            println!(
-                "!!! DOUBLE-SIGN: validator {} offense #2 => 75% slash, 5 epoch mute. New vault={} !!!",
-                validator_id, val.vault_balance
-            );
-
-            val.state = ValidatorState::PunishedCooldown;
-            val.cooldown_until_epoch = Some(state.epoch_index + 5 + 1);
-
-            let muted = any_muted_bucket(state);
-            move_all_validator_tickets_to_bucket(state, validator_id, muted);
-        }
-        _ => {
-            // 100% slash + jail
-            val.vault_balance = 0;
-            jail_validator(state, validator_id);
-
-            println!(
-                "!!!!! DOUBLE-SIGN: validator {} offense #{} => 100% slash + JAILED. New vault=0 !!!!!",
-                validator_id, offense
-            );
-        }
+    if tier.jail {
+        return jail_validator(state, validator_id, events);
     }
-}
\ No newline at end of file
+
+    let until_epoch = state.epoch_index + tier.cooldown_epochs;
+    let val = state.validators.get_mut(&validator_id).ok_or(SimError::UnknownValidator(validator_id))?;
+    val.state = ValidatorState::PunishedCooldown;
+    val.cooldown_until_epoch = Some(until_epoch);
+    events.push(SimEvent::CooldownStarted { validator: validator_id, until_epoch });
+
+    let muted = any_muted_bucket(state)?;
+    move_all_validator_tickets_to_bucket(state, validator_id, muted)
+}
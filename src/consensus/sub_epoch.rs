@@ -0,0 +1,91 @@
+use sha2::{Digest, Sha256};
+
+use crate::error::SimError;
+use crate::state::chain_state::ChainState;
+use crate::state::validator_ops::on_vault_refill;
+use crate::types::event::SimEvent;
+
+/// Seed for sub-epoch `sub_epoch_index` of the epoch seeded with `epoch_seed`.
+/// Sub-epoch 0 uses the epoch seed itself.
+pub fn sub_epoch_seed(epoch_seed: [u8; 32], sub_epoch_index: u64) -> [u8; 32] {
+    if sub_epoch_index == 0 {
+        return epoch_seed;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"eternix/sub-epoch");
+    hasher.update(epoch_seed);
+    hasher.update(sub_epoch_index.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// Advance to the next sub-epoch within the current epoch.
+pub fn process_sub_epoch_transition(state: &mut ChainState, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    state.sub_epoch_index += 1;
+    state.sub_epoch_seed = sub_epoch_seed(state.epoch_seed, state.sub_epoch_index);
+
+    events.push(SimEvent::SubEpochTransition {
+        epoch: state.epoch_index,
+        sub_epoch: state.sub_epoch_index,
+    });
+
+    begin_sub_epoch(state, events)
+}
+
+/// Reset to sub-epoch 0; called from `process_epoch_transition`.
+pub fn reset_sub_epoch(state: &mut ChainState, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    state.sub_epoch_index = 0;
+    state.sub_epoch_seed = sub_epoch_seed(state.epoch_seed, 0);
+
+    begin_sub_epoch(state, events)
+}
+
+/// Actions run at every sub-epoch boundary (including the first sub-epoch of an epoch):
+/// snapshot the liveness window that just ended, then apply queued refills.
+fn begin_sub_epoch(state: &mut ChainState, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    for (validator, stats) in state.liveness.take() {
+        events.push(SimEvent::LivenessSnapshot {
+            validator,
+            proposed: stats.proposed,
+            missed: stats.missed,
+        });
+    }
+
+    for (validator, amount) in state.pending_refills.take() {
+        if state.validators.contains_key(&validator) {
+            on_vault_refill(state, validator, amount, events)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::sim::timeline::{Action, Trigger, TimelineEvent};
+
+    #[test]
+    fn sub_epochs_reset_each_epoch_and_apply_queued_refills() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        sim.sub_epoch_len_slots = 4;
+        sim.timeline.push(TimelineEvent {
+            at: Trigger::Slot(1),
+            action: Action::QueueVaultRefill { validator: 2, amount: 500 },
+        });
+
+        let sub_epochs: Vec<u64> = (0..20).map(|_| sim.run_one_slot().unwrap().sub_epoch_index).collect();
+        assert_eq!(sub_epochs, vec![0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2]);
+
+        // Queued at slot 1, applied at the slot-4 boundary.
+        assert_eq!(sim.state.validators[&2].vault_balance, 1_000_500);
+        assert!(sim.state.pending_refills.is_empty());
+    }
+
+    #[test]
+    fn sub_epoch_zero_uses_epoch_seed() {
+        let seed = [3u8; 32];
+        assert_eq!(sub_epoch_seed(seed, 0), seed);
+        assert_ne!(sub_epoch_seed(seed, 1), seed);
+    }
+}
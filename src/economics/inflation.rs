@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+
+use crate::economics::rewards::{active_ticket_weights, credit_untallied, split_pro_rata};
+use crate::economics::supply::SupplyReport;
+use crate::error::SimError;
+use crate::state::chain_state::ChainState;
+use crate::types::event::SimEvent;
+
+/// Annual inflation rate as a function of time and staking. Rates are in basis points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InflationSchedule {
+    #[default]
+    None,
+    Fixed { annual_rate_bps: u32 },
+    /// `initial_rate_bps`, reduced by `decay_bps` (relative) at the end of every
+    /// year, never below `floor_rate_bps`.
+    Decaying {
+        initial_rate_bps: u32,
+        decay_bps: u32,
+        #[serde(default)]
+        floor_rate_bps: u32,
+    },
+    /// `max_rate_bps` with nothing staked, falling linearly to `min_rate_bps`
+    /// at `target_staking_bps` bonded and staying there above it.
+    TargetStaking {
+        min_rate_bps: u32,
+        max_rate_bps: u32,
+        target_staking_bps: u32,
+    },
+}
+
+/// Who receives newly minted tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MintDestination {
+    /// Pro rata by eligible tickets, credited like rewards; to the treasury if nobody is eligible.
+    #[default]
+    Stakers,
+    Treasury,
+}
+
+/// The `[inflation]` table of a scenario. No inflation by default.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InflationParams {
+    #[serde(default)]
+    pub schedule: InflationSchedule,
+    #[serde(default = "default_epochs_per_year")]
+    pub epochs_per_year: u64,
+    #[serde(default)]
+    pub destination: MintDestination,
+}
+
+fn default_epochs_per_year() -> u64 {
+    // one-hour epochs
+    24 * 365
+}
+
+impl Default for InflationParams {
+    fn default() -> Self {
+        InflationParams {
+            schedule: InflationSchedule::None,
+            epochs_per_year: default_epochs_per_year(),
+            destination: MintDestination::Stakers,
+        }
+    }
+}
+
+impl InflationParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.epochs_per_year == 0 {
+            return Err("epochs_per_year must be greater than zero".to_string());
+        }
+        match self.schedule {
+            InflationSchedule::Decaying { decay_bps, .. } if decay_bps > 10_000 => {
+                Err(format!("decay_bps {} exceeds 10000", decay_bps))
+            }
+            InflationSchedule::Decaying { initial_rate_bps, floor_rate_bps, .. } if floor_rate_bps > initial_rate_bps => {
+                Err(format!("floor_rate_bps {} exceeds initial_rate_bps {}", floor_rate_bps, initial_rate_bps))
+            }
+            InflationSchedule::TargetStaking { min_rate_bps, max_rate_bps, .. } if min_rate_bps > max_rate_bps => {
+                Err(format!("min_rate_bps {} exceeds max_rate_bps {}", min_rate_bps, max_rate_bps))
+            }
+            InflationSchedule::TargetStaking { target_staking_bps: 0, .. } => {
+                Err("target_staking_bps must be greater than zero".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Annual rate in effect during `epoch`, given the current staking ratio.
+    pub fn annual_rate_bps(&self, epoch: u64, staking_ratio_bps: u128) -> u128 {
+        match self.schedule {
+            InflationSchedule::None => 0,
+            InflationSchedule::Fixed { annual_rate_bps } => annual_rate_bps as u128,
+            InflationSchedule::Decaying { initial_rate_bps, decay_bps, floor_rate_bps } => {
+                let years = epoch / self.epochs_per_year;
+                let kept = retained_fraction(10_000 - decay_bps as u128, years);
+                (initial_rate_bps as u128 * kept / DECAY_SCALE).max(floor_rate_bps as u128)
+            }
+            InflationSchedule::TargetStaking { min_rate_bps, max_rate_bps, target_staking_bps } => {
+                let (min, max, target) = (min_rate_bps as u128, max_rate_bps as u128, target_staking_bps as u128);
+                if staking_ratio_bps >= target {
+                    min
+                } else {
+                    max - (max - min) * staking_ratio_bps / target
+                }
+            }
+        }
+    }
+}
+
+/// Fixed-point scale of `retained_fraction`.
+const DECAY_SCALE: u128 = 1_000_000_000_000;
+
+/// `(keep_bps / 10000) ^ years`, scaled by `DECAY_SCALE`, by repeated squaring.
+fn retained_fraction(keep_bps: u128, mut years: u64) -> u128 {
+    let mut base = keep_bps * (DECAY_SCALE / 10_000);
+    let mut result = DECAY_SCALE;
+    while years > 0 && result > 0 {
+        if years & 1 == 1 {
+            result = result * base / DECAY_SCALE;
+        }
+        base = base * base / DECAY_SCALE;
+        years >>= 1;
+    }
+    result
+}
+
+/// Mint one epoch's worth of inflation at the end of `epoch`. Mints are
+/// reported by `InflationMinted`, not in the epoch's reward tally.
+pub fn mint_epoch_inflation(state: &mut ChainState, epoch: u64, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    let report = SupplyReport::from_state(state);
+    let rate_bps = state.inflation.annual_rate_bps(epoch, report.staking_ratio_bps());
+    let amount = report.total_supply * rate_bps / 10_000 / state.inflation.epochs_per_year as u128;
+    if amount == 0 {
+        return Ok(());
+    }
+
+    state.ledger.inflation_minted += amount;
+    events.push(SimEvent::InflationMinted { epoch, amount, annual_rate_bps: rate_bps as u64 });
+
+    let shares = match state.inflation.destination {
+        MintDestination::Stakers => split_pro_rata(amount, &active_ticket_weights(state)),
+        MintDestination::Treasury => Default::default(),
+    };
+    if shares.is_empty() {
+        state.ledger.treasury += amount;
+        events.push(SimEvent::RewardToTreasury { amount });
+        return Ok(());
+    }
+
+    for (validator, share) in shares {
+        credit_untallied(state, validator, share, events)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(schedule: InflationSchedule) -> InflationParams {
+        InflationParams { schedule, epochs_per_year: 10, ..Default::default() }
+    }
+
+    #[test]
+    fn schedules_follow_their_curves() {
+        let decaying = params(InflationSchedule::Decaying { initial_rate_bps: 1_000, decay_bps: 5_000, floor_rate_bps: 200 });
+        assert_eq!(decaying.annual_rate_bps(9, 0), 1_000);
+        assert_eq!(decaying.annual_rate_bps(10, 0), 500);
+        assert_eq!(decaying.annual_rate_bps(20, 0), 250);
+        assert_eq!(decaying.annual_rate_bps(30, 0), 200);
+        assert_eq!(decaying.annual_rate_bps(u64::MAX, 0), 200);
+
+        let slow = params(InflationSchedule::Decaying { initial_rate_bps: 1_000, decay_bps: 1_000, floor_rate_bps: 0 });
+        assert_eq!(slow.annual_rate_bps(20, 0), 810);
+        assert_eq!(slow.annual_rate_bps(u64::MAX, 0), 0);
+        let flat = params(InflationSchedule::Decaying { initial_rate_bps: 1_000, decay_bps: 0, floor_rate_bps: 0 });
+        assert_eq!(flat.annual_rate_bps(u64::MAX, 0), 1_000);
+
+        let target = params(InflationSchedule::TargetStaking { min_rate_bps: 200, max_rate_bps: 1_000, target_staking_bps: 5_000 });
+        assert_eq!(target.annual_rate_bps(0, 0), 1_000);
+        assert_eq!(target.annual_rate_bps(0, 2_500), 600);
+        assert_eq!(target.annual_rate_bps(0, 8_000), 200);
+    }
+
+    #[test]
+    fn treasury_mints_are_logged() {
+        use crate::scenario::config::Scenario;
+        use crate::sim::sink::MemorySink;
+
+        let mut scenario = Scenario::load("scenarios/default.toml").unwrap();
+        scenario.inflation = InflationParams {
+            schedule: InflationSchedule::Fixed { annual_rate_bps: 500 },
+            epochs_per_year: 12,
+            destination: MintDestination::Treasury,
+        };
+        let mut sim = scenario.build_simulator().unwrap();
+        let sink = MemorySink::default();
+        sim.add_sink(Box::new(sink.clone()));
+        for _ in 0..100 {
+            sim.run_one_slot().unwrap();
+        }
+
+        let logged: u128 = sink
+            .records()
+            .iter()
+            .filter_map(|r| match r.event {
+                SimEvent::RewardToTreasury { amount } => Some(amount),
+                _ => None,
+            })
+            .sum();
+        assert!(logged > 0);
+        assert_eq!(logged, sim.state.ledger.inflation_minted);
+    }
+
+    #[test]
+    fn mints_stay_out_of_the_reward_tally() {
+        use crate::scenario::config::Scenario;
+        use crate::sim::sink::MemorySink;
+
+        let mut scenario = Scenario::load("scenarios/default.toml").unwrap();
+        scenario.inflation = InflationParams {
+            schedule: InflationSchedule::Fixed { annual_rate_bps: 500 },
+            epochs_per_year: 12,
+            destination: MintDestination::Stakers,
+        };
+        let mut sim = scenario.build_simulator().unwrap();
+        let sink = MemorySink::default();
+        sim.add_sink(Box::new(sink.clone()));
+        for _ in 0..100 {
+            sim.run_one_slot().unwrap();
+        }
+
+        let records = sink.records();
+        let minted: u128 = records
+            .iter()
+            .filter_map(|r| match r.event {
+                SimEvent::InflationMinted { amount, .. } => Some(amount),
+                _ => None,
+            })
+            .sum();
+        let tallied: u128 = records
+            .iter()
+            .filter_map(|r| match r.event {
+                SimEvent::EpochRewardSummary { paid, burned, to_treasury, .. } => Some(paid + burned + to_treasury),
+                _ => None,
+            })
+            .sum();
+        assert!(minted > 0);
+        assert_eq!(minted, sim.state.ledger.inflation_minted);
+        assert_eq!(tallied, 0);
+    }
+
+    #[test]
+    fn decaying_floor_may_not_exceed_initial_rate() {
+        let inverted = params(InflationSchedule::Decaying { initial_rate_bps: 200, decay_bps: 1_000, floor_rate_bps: 500 });
+        assert!(inverted.validate().unwrap_err().contains("floor_rate_bps"));
+        let flat = params(InflationSchedule::Decaying { initial_rate_bps: 500, decay_bps: 1_000, floor_rate_bps: 500 });
+        assert!(flat.validate().is_ok());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod inflation;
+pub mod rewards;
+pub mod slashing;
+pub mod supply;
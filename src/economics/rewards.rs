@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SimError;
+use crate::state::chain_state::ChainState;
+use crate::types::amount;
+use crate::types::event::SimEvent;
+use crate::types::validator::ValidatorState;
+
+/// What happens to the block reward of a protocol-produced block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolBlockPolicy {
+    #[default]
+    Burn,
+    Treasury,
+    /// Split pro rata by eligible tickets across the active set; burned if the set is empty.
+    Redistribute,
+}
+
+/// Where rewards are credited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardDestination {
+    /// Added to `vault_balance`, i.e. auto-compounded into the bond.
+    #[default]
+    Vault,
+    /// Added to `withdrawable_balance`.
+    Withdrawable,
+}
+
+/// Reward parameters, the `[rewards]` table of a scenario. All zero by default.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RewardParams {
+    /// Paid to the proposer of every successful block.
+    #[serde(default, with = "amount")]
+    pub block_reward: u128,
+    /// Paid on every successful block to each holder of eligible tickets, per ticket.
+    #[serde(default, with = "amount")]
+    pub per_ticket_reward: u128,
+    #[serde(default)]
+    pub protocol_blocks: ProtocolBlockPolicy,
+    #[serde(default)]
+    pub destination: RewardDestination,
+}
+
+/// Rewards accumulated over the current epoch, reported at its end. What
+/// each validator was paid is kept in `ChainState::rewards_paid`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewardTally {
+    #[serde(with = "amount")]
+    pub burned: u128,
+    #[serde(with = "amount")]
+    pub to_treasury: u128,
+}
+
+/// Eligible tickets per validator: Active tickets in ACTIVE buckets owned by
+/// Active validators, i.e. the weights leader selection uses. Read from the
+/// per-owner counts of `ticket_index`, so it costs O(owners), not O(tickets).
+pub fn active_ticket_weights(state: &ChainState) -> BTreeMap<u64, u128> {
+    state
+        .ticket_index
+        .eligible_by_owner()
+        .filter(|(owner, _)| state.validators.get(owner).is_some_and(|v| v.state == ValidatorState::Active))
+        .map(|(owner, count)| (owner, count as u128))
+        .collect()
+}
+
+/// Split `total` in proportion to `weights`. The rounding remainder goes one
+/// unit at a time to the lowest ids, so the parts always sum to `total`.
+pub fn split_pro_rata(total: u128, weights: &BTreeMap<u64, u128>) -> BTreeMap<u64, u128> {
+    let weight_sum: u128 = weights.values().sum();
+    if weight_sum == 0 {
+        return BTreeMap::new();
+    }
+
+    let mut shares: BTreeMap<u64, u128> = weights
+        .iter()
+        .map(|(&id, &w)| (id, total / weight_sum * w + total % weight_sum * w / weight_sum))
+        .collect();
+    let mut remainder = total - shares.values().sum::<u128>();
+    for share in shares.values_mut() {
+        if remainder == 0 {
+            break;
+        }
+        *share += 1;
+        remainder -= 1;
+    }
+    shares
+}
+
+/// Credit a reward to `validator_id` according to `state.rewards.destination`
+/// and add it to the epoch's `rewards_paid`.
+pub fn credit_reward(
+    state: &mut ChainState,
+    validator_id: u64,
+    amount: u128,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    credit_untallied(state, validator_id, amount, events)?;
+    if amount > 0 {
+        *state.rewards_paid.get_or_default(validator_id) += amount;
+    }
+    Ok(())
+}
+
+/// `credit_reward` without the tally, for payouts reported by their own event
+/// (inflation mints are reported by `InflationMinted`).
+pub fn credit_untallied(
+    state: &mut ChainState,
+    validator_id: u64,
+    amount: u128,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let destination = state.rewards.destination;
+    let v = state.validators.get_mut(&validator_id).ok_or(SimError::UnknownValidator(validator_id))?;
+    match destination {
+        RewardDestination::Vault => v.vault_balance += amount,
+        RewardDestination::Withdrawable => v.withdrawable_balance += amount,
+    }
+    events.push(SimEvent::RewardPaid { validator: validator_id, amount, destination });
+    Ok(())
+}
+
+/// Rewards for a successful block: the block reward to `proposer`, then the
+/// per-ticket participation reward to every eligible ticket holder.
+pub fn reward_block(state: &mut ChainState, proposer: u64, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    let block_reward = state.rewards.block_reward;
+    credit_reward(state, proposer, block_reward, events)?;
+    state.ledger.rewards_issued += block_reward;
+
+    let per_ticket = state.rewards.per_ticket_reward;
+    if per_ticket > 0 {
+        for (validator, tickets) in active_ticket_weights(state) {
+            credit_reward(state, validator, per_ticket * tickets, events)?;
+            state.ledger.rewards_issued += per_ticket * tickets;
+        }
+    }
+    Ok(())
+}
+
+/// Dispose of the block reward of a protocol-produced block per `state.rewards.protocol_blocks`.
+pub fn reward_protocol_block(state: &mut ChainState, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    let amount = state.rewards.block_reward;
+    if amount == 0 {
+        return Ok(());
+    }
+    state.ledger.rewards_issued += amount;
+
+    let shares = match state.rewards.protocol_blocks {
+        ProtocolBlockPolicy::Burn => BTreeMap::new(),
+        ProtocolBlockPolicy::Treasury => {
+            state.ledger.treasury += amount;
+            state.reward_tally.to_treasury += amount;
+            events.push(SimEvent::RewardToTreasury { amount });
+            return Ok(());
+        }
+        ProtocolBlockPolicy::Redistribute => split_pro_rata(amount, &active_ticket_weights(state)),
+    };
+
+    if shares.is_empty() {
+        state.ledger.burned += amount;
+        state.reward_tally.burned += amount;
+        events.push(SimEvent::RewardBurned { amount });
+        return Ok(());
+    }
+
+    for (validator, share) in shares {
+        credit_reward(state, validator, share, events)?;
+    }
+    Ok(())
+}
+
+/// Report and reset the tally of the epoch that just ended.
+pub fn report_epoch_rewards(state: &mut ChainState, epoch: u64, events: &mut Vec<SimEvent>) {
+    let tally = std::mem::take(&mut state.reward_tally);
+    let paid = state.rewards_paid.take();
+    if tally == RewardTally::default() && paid.is_empty() {
+        return;
+    }
+
+    for (&validator, &amount) in &paid {
+        events.push(SimEvent::EpochRewards { epoch, validator, amount });
+    }
+    events.push(SimEvent::EpochRewardSummary {
+        epoch,
+        paid: paid.values().sum(),
+        burned: tally.burned,
+        to_treasury: tally.to_treasury,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::sim::sink::MemorySink;
+
+    #[test]
+    fn pro_rata_split_is_exact() {
+        let weights = BTreeMap::from([(1, 1), (2, 1), (3, 1)]);
+        let shares = split_pro_rata(100, &weights);
+        assert_eq!(shares, BTreeMap::from([(1, 34), (2, 33), (3, 33)]));
+
+        let weights = BTreeMap::from([(1, 1), (2, 3)]);
+        assert_eq!(split_pro_rata(8, &weights), BTreeMap::from([(1, 2), (2, 6)]));
+        assert!(split_pro_rata(8, &BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn weights_match_a_scan_of_every_ticket() {
+        use crate::types::ticket::TicketState;
+
+        let scan = |state: &ChainState| {
+            let mut weights = BTreeMap::new();
+            for t in state.tickets.values() {
+                let owner_active = state.validators.get(&t.owner).is_some_and(|v| v.state == ValidatorState::Active);
+                if owner_active && t.state == TicketState::Active && state.active_bucket_ids.contains(&t.bucket) {
+                    *weights.entry(t.owner).or_default() += 1;
+                }
+            }
+            weights
+        };
+
+        for path in ["scenarios/interventions.toml", "scenarios/full_layout.toml"] {
+            let mut sim = Scenario::load(path).unwrap().build_simulator().unwrap();
+            for _ in 0..200 {
+                sim.run_one_slot().unwrap();
+                assert_eq!(active_ticket_weights(&sim.state), scan(&sim.state), "{} slot {}", path, sim.clock.slot_index);
+            }
+        }
+    }
+
+    #[test]
+    fn every_block_reward_is_accounted_for() {
+        let mut scenario = Scenario::load("scenarios/default.toml").unwrap();
+        scenario.rewards = RewardParams {
+            block_reward: 10,
+            per_ticket_reward: 0,
+            protocol_blocks: ProtocolBlockPolicy::Treasury,
+            destination: RewardDestination::Withdrawable,
+        };
+        let mut sim = scenario.build_simulator().unwrap();
+        let sink = MemorySink::default();
+        sim.add_sink(Box::new(sink.clone()));
+
+        for _ in 0..100 {
+            sim.run_one_slot().unwrap();
+        }
+
+        let withdrawable: u128 = sim.state.validators.values().map(|v| v.withdrawable_balance).sum();
+        assert_eq!(withdrawable + sim.state.ledger.treasury, 10 * 100);
+
+        let reported: u128 = sink
+            .records()
+            .iter()
+            .filter_map(|r| match r.event {
+                SimEvent::EpochRewardSummary { paid, to_treasury, .. } => Some(paid + to_treasury),
+                _ => None,
+            })
+            .sum();
+        assert_eq!(reported, 10 * 100);
+    }
+}
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+
+use crate::economics::rewards::{active_ticket_weights, credit_reward, split_pro_rata};
+use crate::error::SimError;
+use crate::state::chain_state::ChainState;
+use crate::types::event::SimEvent;
+
+/// What a slash rate is measured against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlashBasis {
+    /// The vault balance at the time of the slash.
+    #[default]
+    CurrentVault,
+    /// The validator's `initial_bond`, capped at what the vault holds.
+    InitialBond,
+}
+
+impl SlashBasis {
+    /// `bps` of the basis, as a human-readable share ("50%", "2.5% of bond").
+    pub fn describe(self, bps: u32) -> String {
+        let percent = if bps.is_multiple_of(100) {
+            format!("{}%", bps / 100)
+        } else {
+            format!("{}.{:02}", bps / 100, bps % 100).trim_end_matches('0').to_string() + "%"
+        };
+        match self {
+            SlashBasis::CurrentVault => percent,
+            SlashBasis::InitialBond => percent + " of bond",
+        }
+    }
+}
+
+/// The offence a slash punishes; decides which event `slash` emits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlashReason {
+    Liveness,
+    DoubleSign { offense: u8 },
+}
+
+/// Where slashed funds go once any whistleblower share has been paid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlashDestination {
+    #[default]
+    Burn,
+    Treasury,
+    /// Pro rata by eligible tickets to every validator other than the offender;
+    /// burned if there is nobody to pay.
+    Redistribute,
+}
+
+/// The `[slashing]` table of a scenario. Burns everything by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlashPolicy {
+    #[serde(default)]
+    pub destination: SlashDestination,
+    /// Share of each slash paid to the reporter, when there is one.
+    #[serde(default)]
+    pub whistleblower_bps: u32,
+}
+
+impl SlashPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.whistleblower_bps > 10_000 {
+            return Err(format!("whistleblower_bps {} exceeds 10000", self.whistleblower_bps));
+        }
+        Ok(())
+    }
+}
+
+/// Take `bps` of `basis` from `validator_id`'s vault, emit the slash event for
+/// `reason` and settle the funds with `settle_slash`. The amount never exceeds
+/// the vault; the exact amount taken is returned.
+pub fn slash(
+    state: &mut ChainState,
+    validator_id: u64,
+    bps: u32,
+    basis: SlashBasis,
+    reason: SlashReason,
+    reporter: Option<u64>,
+    events: &mut Vec<SimEvent>,
+) -> Result<u128, SimError> {
+    let val = state.validators.get_mut(&validator_id).ok_or(SimError::UnknownValidator(validator_id))?;
+
+    let base = match basis {
+        SlashBasis::CurrentVault => val.vault_balance,
+        SlashBasis::InitialBond => val.initial_bond,
+    };
+    let amount = (base * bps as u128 / 10_000).min(val.vault_balance);
+    val.vault_balance -= amount;
+    let new_vault = val.vault_balance;
+
+    events.push(match reason {
+        SlashReason::Liveness => SimEvent::LivenessSlash { validator: validator_id, bps, basis, amount, new_vault },
+        SlashReason::DoubleSign { offense } => {
+            SimEvent::DoubleSign { validator: validator_id, offense, bps, basis, amount, new_vault }
+        }
+    });
+    settle_slash(state, validator_id, amount, reporter, events)?;
+    Ok(amount)
+}
+
+/// Route `amount` slashed from `offender` per `state.slashing` and record
+/// each part in the ledger. `reporter` is whoever submitted the evidence;
+/// offences the protocol detects by itself have none, and an offender cannot
+/// report itself.
+pub fn settle_slash(
+    state: &mut ChainState,
+    offender: u64,
+    amount: u128,
+    reporter: Option<u64>,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    if reporter == Some(offender) {
+        return Err(SimError::SelfReport(offender));
+    }
+    state
+        .slashing
+        .validate()
+        .map_err(|reason| SimError::InvalidParam { name: "slashing".to_string(), reason })?;
+    state.ledger.slashed += amount;
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let policy = state.slashing;
+    let mut rest = amount;
+
+    let mut whistleblower_reward = 0;
+    if let Some(reporter) = reporter {
+        whistleblower_reward = amount * policy.whistleblower_bps as u128 / 10_000;
+        credit_reward(state, reporter, whistleblower_reward, events)?;
+        state.ledger.whistleblower_rewards += whistleblower_reward;
+        rest -= whistleblower_reward;
+    }
+
+    let (mut burned, mut to_treasury, mut redistributed) = (0, 0, 0);
+    match policy.destination {
+        SlashDestination::Burn => burned = rest,
+        SlashDestination::Treasury => to_treasury = rest,
+        SlashDestination::Redistribute => {
+            let mut weights = active_ticket_weights(state);
+            weights.remove(&offender);
+            let shares = split_pro_rata(rest, &weights);
+            if shares.is_empty() {
+                burned = rest;
+            }
+            for (validator, share) in shares {
+                credit_reward(state, validator, share, events)?;
+                redistributed += share;
+            }
+        }
+    }
+
+    state.ledger.burned += burned;
+    state.ledger.slashed_burned += burned;
+    state.ledger.treasury += to_treasury;
+    state.ledger.slashed_to_treasury += to_treasury;
+    state.ledger.slashed_redistributed += redistributed;
+
+    events.push(SimEvent::SlashSettled {
+        offender,
+        burned,
+        to_treasury,
+        redistributed,
+        reporter,
+        whistleblower_reward,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::slot::apply_double_sign_punishment;
+    use crate::scenario::config::Scenario;
+
+    #[test]
+    fn slash_is_split_between_reporter_and_honest_holders() {
+        let mut state = Scenario::load("scenarios/full_layout.toml").unwrap().build_state().unwrap();
+        state.slashing = SlashPolicy { destination: SlashDestination::Redistribute, whistleblower_bps: 1_000 };
+        let vaults_before: u128 = state.validators.values().map(|v| v.vault_balance).sum();
+        let mut events = Vec::new();
+
+        settle_slash(&mut state, 4, 1_000, Some(1), &mut events).unwrap();
+
+        let ledger = &state.ledger;
+        assert_eq!(ledger.whistleblower_rewards, 100);
+        assert_eq!(ledger.slashed_redistributed, 900);
+        assert_eq!(ledger.slashed, 1_000);
+        // Nothing went to the offender; everything landed in other vaults.
+        let vaults_after: u128 = state.validators.values().map(|v| v.vault_balance).sum();
+        assert_eq!(vaults_after, vaults_before + 1_000);
+        assert_eq!(state.validators[&4].vault_balance, 10_000_000);
+    }
+
+    #[test]
+    fn offenders_cannot_report_themselves_and_shares_are_bounded() {
+        let mut state = Scenario::load("scenarios/full_layout.toml").unwrap().build_state().unwrap();
+        let mut events = Vec::new();
+        assert_eq!(settle_slash(&mut state, 4, 1_000, Some(4), &mut events), Err(SimError::SelfReport(4)));
+        assert_eq!(state.ledger.slashed, 0);
+
+        state.slashing.whistleblower_bps = 10_001;
+        assert!(state.slashing.validate().is_err());
+        assert!(matches!(settle_slash(&mut state, 4, 1_000, Some(1), &mut events), Err(SimError::InvalidParam { .. })));
+        assert_eq!(state.ledger.slashed, 0);
+
+        let mut scenario = Scenario::load("scenarios/default.toml").unwrap();
+        scenario.slashing.whistleblower_bps = 20_000;
+        assert!(scenario.build_state().unwrap_err().to_string().contains("whistleblower_bps"));
+    }
+
+    #[test]
+    fn rates_apply_to_their_basis() {
+        let mut state = Scenario::load("scenarios/default.toml").unwrap().build_state().unwrap();
+        let v = state.validators.get_mut(&2).unwrap();
+        (v.vault_balance, v.initial_bond) = (400, 1_000);
+        let mut events = Vec::new();
+
+        let taken = slash(&mut state, 2, 2_500, SlashBasis::InitialBond, SlashReason::Liveness, None, &mut events);
+        assert_eq!(taken, Ok(250));
+        let taken = slash(&mut state, 2, 2_500, SlashBasis::InitialBond, SlashReason::Liveness, None, &mut events);
+        assert_eq!(taken, Ok(150));
+        assert_eq!(state.validators[&2].vault_balance, 0);
+        assert_eq!(SlashBasis::InitialBond.describe(250), "2.5% of bond");
+    }
+
+    #[test]
+    fn two_double_signs_take_three_quarters_of_the_vault() {
+        let mut state = Scenario::load("scenarios/default.toml").unwrap().build_state().unwrap();
+        let original = state.validators[&1].vault_balance;
+        let mut events = Vec::new();
+
+        apply_double_sign_punishment(&mut state, 1, None, &mut events).unwrap();
+        assert_eq!(state.validators[&1].vault_balance, original / 2);
+        apply_double_sign_punishment(&mut state, 1, None, &mut events).unwrap();
+        assert_eq!(state.validators[&1].vault_balance, original / 4);
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::chain_state::ChainState;
+use crate::types::amount;
+
+/// Cumulative supply accounting. Every operation that creates, moves out of
+/// validator balances, or destroys tokens records itself here.
+///
+/// Ticket retirement moves no funds in this model, so it has no entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplyLedger {
+    /// Vault balances at genesis.
+    #[serde(with = "amount")]
+    pub genesis: u128,
+    /// Block and participation rewards created, including burned or treasury-bound ones.
+    #[serde(with = "amount")]
+    pub rewards_issued: u128,
+    /// Minted by the inflation schedule.
+    #[serde(with = "amount")]
+    pub inflation_minted: u128,
+    /// Brought in from outside: refills and the vaults of validators joining mid-run.
+    #[serde(with = "amount")]
+    pub deposited: u128,
+    /// Taken from vaults by punishment; split into the four entries below.
+    #[serde(with = "amount")]
+    pub slashed: u128,
+    #[serde(with = "amount")]
+    pub slashed_burned: u128,
+    #[serde(with = "amount")]
+    pub slashed_to_treasury: u128,
+    #[serde(with = "amount")]
+    pub slashed_redistributed: u128,
+    #[serde(with = "amount")]
+    pub whistleblower_rewards: u128,
+    #[serde(with = "amount")]
+    pub burned: u128,
+    #[serde(with = "amount")]
+    pub treasury: u128,
+}
+
+impl SupplyLedger {
+    pub fn total_issued(&self) -> u128 {
+        self.genesis + self.rewards_issued + self.inflation_minted
+    }
+
+    /// Tokens in existence: everything issued or deposited, less what was burned.
+    pub fn total_supply(&self) -> u128 {
+        self.total_issued() + self.deposited - self.burned
+    }
+}
+
+/// Where the supply sits right now, checked against the ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupplyReport {
+    pub total_supply: u128,
+    pub bonded: u128,
+    pub withdrawable: u128,
+    pub treasury: u128,
+    pub burned: u128,
+    pub slashed: u128,
+    pub inflation_minted: u128,
+}
+
+impl SupplyReport {
+    pub fn from_state(state: &ChainState) -> Self {
+        let ledger = &state.ledger;
+        SupplyReport {
+            total_supply: ledger.total_supply(),
+            bonded: state.validators.values().map(|v| v.vault_balance).sum(),
+            withdrawable: state.validators.values().map(|v| v.withdrawable_balance).sum(),
+            treasury: ledger.treasury,
+            burned: ledger.burned,
+            slashed: ledger.slashed,
+            inflation_minted: ledger.inflation_minted,
+        }
+    }
+
+    /// Supply held somewhere: vaults, withdrawable balances or the treasury.
+    pub fn accounted(&self) -> u128 {
+        self.bonded + self.withdrawable + self.treasury
+    }
+
+    /// Share of the supply bonded in vaults, in basis points.
+    pub fn staking_ratio_bps(&self) -> u128 {
+        if self.total_supply == 0 {
+            return 0;
+        }
+        self.bonded * 10_000 / self.total_supply
+    }
+}
+
+impl fmt::Display for SupplyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "supply={} bonded={} withdrawable={} treasury={} | burned={} slashed={} minted={}",
+            self.total_supply,
+            self.bonded,
+            self.withdrawable,
+            self.treasury,
+            self.burned,
+            self.slashed,
+            self.inflation_minted
+        )
+    }
+}
+
+/// Conservation: every unit issued or deposited is either held (vaults,
+/// withdrawable balances, treasury) or burned.
+pub fn check_conservation(state: &ChainState) -> Result<(), String> {
+    let report = SupplyReport::from_state(state);
+    let ledger = &state.ledger;
+
+    if report.accounted() + ledger.burned != ledger.total_issued() + ledger.deposited {
+        return Err(format!(
+            "issued {} + deposited {} != held {} + burned {} ({})",
+            ledger.total_issued(),
+            ledger.deposited,
+            report.accounted(),
+            ledger.burned,
+            report
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economics::inflation::{InflationParams, InflationSchedule};
+    use crate::economics::rewards::{ProtocolBlockPolicy, RewardParams};
+    use crate::scenario::config::Scenario;
+    use crate::state::invariants::Invariants;
+
+    #[test]
+    fn supply_is_conserved_through_slashes_rewards_and_inflation() {
+        let mut scenario = Scenario::load("scenarios/full_layout.toml").unwrap();
+        scenario.rewards = RewardParams {
+            block_reward: 1_000,
+            per_ticket_reward: 10,
+            protocol_blocks: ProtocolBlockPolicy::Redistribute,
+            ..Default::default()
+        };
+        scenario.inflation = InflationParams {
+            schedule: InflationSchedule::Fixed { annual_rate_bps: 500 },
+            epochs_per_year: 12,
+            ..Default::default()
+        };
+        let mut sim = scenario.build_simulator().unwrap();
+        sim.invariants = Some(Invariants::default());
+
+        for _ in 0..scenario.run_slots().unwrap() {
+            sim.run_one_slot().unwrap();
+        }
+
+        let ledger = &sim.state.ledger;
+        assert!(ledger.slashed > 0 && ledger.inflation_minted > 0);
+        assert_eq!(SupplyReport::from_state(&sim.state).accounted(), ledger.total_supply());
+    }
+}
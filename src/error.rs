@@ -0,0 +1,91 @@
+use std::fmt;
+
+use crate::state::invariants::Violation;
+
+/// An invalid state transition.
+///
+/// Operations check their inputs before mutating where they can, but a
+/// compound operation (e.g. jailing, which kills every ticket) may fail part
+/// way through. `Simulator::run_one_slot` restores its pre-slot snapshot in
+/// that case, so a failed slot leaves no trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimError {
+    UnknownValidator(u64),
+    UnknownTicket(u64),
+    UnknownBucket(u64),
+    TicketNotInBucket { ticket: u64, bucket: u64 },
+    TicketAlreadyInBucket { ticket: u64, bucket: u64 },
+    DuplicateValidator(u64),
+    DuplicateTicket(u64),
+    ValidatorJailed(u64),
+    NoActiveBucket,
+    NoMutedBucket,
+    /// Leader selection ran with no ticket in any ACTIVE bucket.
+    NoEligibleTickets,
+    /// Ticket selection ran on an empty bucket.
+    EmptyTicketSet,
+    UnknownParam(String),
+    /// Evidence submitted for an equivocation nobody observed.
+    UnknownEvidence { validator: u64, slot: u64 },
+    /// The offence was already submitted or punished.
+    DuplicateEvidence { validator: u64, slot: u64 },
+    EvidenceExpired { validator: u64, slot: u64 },
+    /// A validator submitted evidence of its own offence.
+    SelfReport(u64),
+    InvalidParam { name: String, reason: String },
+    /// Raised by the invariant checker, when enabled on the simulator.
+    InvariantViolation(Box<Violation>),
+    /// An automatic snapshot could not be written; the slot did not run.
+    Snapshot(String),
+    /// Crediting `amount` would overflow a balance or supply total.
+    BalanceOverflow { validator: u64, amount: u128 },
+    /// Part of the state could not be encoded for its hash, or a recorded
+    /// field change could not be decoded.
+    Encoding(String),
+    /// `ticket_index` was asked to drop a ticket it does not count.
+    TicketNotIndexed(u64),
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::UnknownValidator(id) => write!(f, "unknown validator {}", id),
+            SimError::UnknownTicket(id) => write!(f, "unknown ticket {}", id),
+            SimError::UnknownBucket(id) => write!(f, "unknown bucket {}", id),
+            SimError::TicketNotInBucket { ticket, bucket } => {
+                write!(f, "ticket {} not found in bucket {}", ticket, bucket)
+            }
+            SimError::TicketAlreadyInBucket { ticket, bucket } => {
+                write!(f, "ticket {} already in bucket {}", ticket, bucket)
+            }
+            SimError::DuplicateValidator(id) => write!(f, "validator {} already exists", id),
+            SimError::DuplicateTicket(id) => write!(f, "ticket {} already exists", id),
+            SimError::ValidatorJailed(id) => write!(f, "validator {} is jailed", id),
+            SimError::NoActiveBucket => write!(f, "no ACTIVE bucket defined"),
+            SimError::NoMutedBucket => write!(f, "no MUTED bucket defined"),
+            SimError::NoEligibleTickets => write!(f, "no ACTIVE bucket holds a ticket"),
+            SimError::EmptyTicketSet => write!(f, "cannot select a ticket from an empty bucket"),
+            SimError::UnknownEvidence { validator, slot } => {
+                write!(f, "no double-sign by validator {} observed at slot {}", validator, slot)
+            }
+            SimError::DuplicateEvidence { validator, slot } => {
+                write!(f, "evidence for validator {} at slot {} already submitted", validator, slot)
+            }
+            SimError::EvidenceExpired { validator, slot } => {
+                write!(f, "evidence for validator {} at slot {} is outside the evidence window", validator, slot)
+            }
+            SimError::SelfReport(id) => write!(f, "validator {} cannot report its own offence", id),
+            SimError::UnknownParam(name) => write!(f, "unknown parameter {:?}", name),
+            SimError::InvalidParam { name, reason } => write!(f, "{} {}", name, reason),
+            SimError::InvariantViolation(violation) => write!(f, "{}", violation),
+            SimError::Snapshot(msg) => write!(f, "snapshot failed: {}", msg),
+            SimError::BalanceOverflow { validator, amount } => {
+                write!(f, "crediting {} to validator {} overflows a balance", amount, validator)
+            }
+            SimError::Encoding(msg) => write!(f, "cannot encode or decode state: {}", msg),
+            SimError::TicketNotIndexed(id) => write!(f, "ticket {} is not counted by the ticket index", id),
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
@@ -1,4 +1,9 @@
+pub mod error;
 pub mod types;
 pub mod state;
 pub mod consensus;
 pub mod sim;
+pub mod economics;
+pub mod scenario;
+pub mod analysis;
+pub mod sweep;
@@ -1,180 +1,566 @@
-use std::collections::{HashMap, HashSet, BTreeMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter};
 
-use eternix_sim::sim::clock::SimClock;
+use serde::Serialize;
+use serde_json::json;
+
+use eternix_sim::consensus::leader_selection::leader_schedule;
+use eternix_sim::economics::supply::SupplyReport;
+use eternix_sim::scenario::config::Scenario;
 use eternix_sim::sim::simulator::Simulator;
+use eternix_sim::sim::sink::{ConsoleSink, JsonLinesSink, MemorySink};
+use eternix_sim::sim::recording::{Recording, ReplayOutcome};
+use eternix_sim::sim::snapshot::{AutoSnapshot, Snapshot, SnapshotError};
 use eternix_sim::state::chain_state::ChainState;
+use eternix_sim::state::merkle::{Record, StateTree};
+use eternix_sim::sweep::runner::run_sweep;
+use eternix_sim::sweep::spec::SweepSpec;
+use eternix_sim::sweep::table::{write_csv, write_json};
+use eternix_sim::types::amount;
+use eternix_sim::types::event::EventRecord;
 use eternix_sim::types::validator::{Validator, ValidatorState};
-use eternix_sim::types::ticket::Ticket;
-use eternix_sim::types::bucket::Bucket;
-use eternix_sim::types::ticket::TicketState;
-// use eternix_sim::state::retirement_ops::request_ticket_retire;
-// use eternix_sim::state::validator_ops::{on_vault_refill};
+
+const DEFAULT_SCENARIO: &str = "scenarios/default.toml";
+
+const USAGE: &str = "\
+usage: eternix-sim [scenario]                 run a scenario (default scenarios/default.toml)
+       eternix-sim run <scenario> [options]
+           --slots N | --epochs N    run length, overriding [run]
+           --until-jail              stop after the first slot that jails a validator
+           --format text|json|csv    per-slot validator table (default text)
+           -v, --verbose             print every event
+           -q, --quiet               print no events
+           --log PATH                also write every event to PATH as JSON lines
+           --resume SNAPSHOT         continue the scenario's run from a snapshot
+           --snapshot-every N        snapshot every N epochs (overrides [snapshots])
+           --snapshot-dir DIR        where to write them (default snapshots)
+           --snapshot-keep K         keep the latest K, 0 for all (default 3)
+           --record PATH             write the run's inputs and blocks to PATH for replay
+       eternix-sim sweep <sweep.toml> [--threads N] [--format csv|json] [--out PATH]
+       eternix-sim replay <events.jsonl | recording> [-v] [--format text|json]
+       eternix-sim inspect <scenario | snapshot>
+       eternix-sim schedule <scenario> [--epoch N] [--format text|json|csv]
+       eternix-sim prove <scenario> --slot N (--validator ID | --ticket ID)";
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl Format {
+    fn parse(text: &str, allowed: &[Format]) -> Format {
+        let format = match text {
+            "text" => Format::Text,
+            "json" => Format::Json,
+            "csv" => Format::Csv,
+            _ => fail(format!("unknown format {:?}\n\n{}", text, USAGE)),
+        };
+        if !allowed.contains(&format) {
+            fail(format!("format {:?} is not supported here\n\n{}", text, USAGE));
+        }
+        format
+    }
+}
+
+/// Arguments of one subcommand: positionals, `--flag value` options and switches.
+struct Args {
+    positional: Vec<String>,
+    values: HashMap<&'static str, String>,
+    switches: HashSet<&'static str>,
+}
+
+impl Args {
+    /// `options` take a value; `switches` are `(long, short)` pairs.
+    fn parse(
+        args: impl Iterator<Item = String>,
+        options: &[&'static str],
+        switches: &[(&'static str, &'static str)],
+    ) -> Args {
+        let mut parsed = Args { positional: Vec::new(), values: HashMap::new(), switches: HashSet::new() };
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            if let Some(&option) = options.iter().find(|&&o| o == arg) {
+                let value = args.next().unwrap_or_else(|| fail(format!("{} needs a value\n\n{}", option, USAGE)));
+                parsed.values.insert(option, value);
+            } else if let Some(&(long, _)) =
+                switches.iter().find(|&&(long, short)| long == arg || (!short.is_empty() && short == arg))
+            {
+                parsed.switches.insert(long);
+            } else if arg.starts_with('-') {
+                fail(format!("unknown option {}\n\n{}", arg, USAGE));
+            } else {
+                parsed.positional.push(arg);
+            }
+        }
+        parsed
+    }
+
+    fn path(&self, what: &str) -> &str {
+        match self.positional.as_slice() {
+            [path] => path,
+            [] => fail(format!("missing {}\n\n{}", what, USAGE)),
+            _ => fail(format!("expected one {}\n\n{}", what, USAGE)),
+        }
+    }
+
+    fn number(&self, option: &str) -> Option<u64> {
+        self.values.get(option).map(|v| {
+            v.replace('_', "").parse().unwrap_or_else(|_| fail(format!("{} must be a number, got {:?}", option, v)))
+        })
+    }
+
+    fn format(&self, default: Format, allowed: &[Format]) -> Format {
+        self.values.get("--format").map(|f| Format::parse(f, allowed)).unwrap_or(default)
+    }
+
+    fn switch(&self, name: &str) -> bool {
+        self.switches.contains(name)
+    }
+}
 
 fn main() {
-    // --- Genesis validator ---
-    let validator1_id = 1u64;
-    let validator2_id = 2u64;
-
-    let mut validators = HashMap::new();
-    validators.insert(
-        validator1_id,
-        Validator {
-            id: validator1_id,
-            state: ValidatorState::Active,
-            vault_balance: 1_000_000_000,
-            initial_bond: 1_000_000,
-            miss_counter: 0,
-            double_sign_offenses: 0,
-            cooldown_until_epoch: None,
-        },
-    );
-    validators.insert(
-        validator2_id,
-        Validator{
-            id: validator2_id,
-            state: ValidatorState::Active,
-            vault_balance: 1_000_000,
-            initial_bond: 1_000_000,
-            miss_counter: 0,
-            double_sign_offenses: 0,
-            cooldown_until_epoch: None,
-        },
-    );
+    let mut args = std::env::args().skip(1).peekable();
+    let command = args.peek().cloned();
+    match command.as_deref() {
+        None => run(Args::parse(std::iter::once(DEFAULT_SCENARIO.to_string()), &[], &[])),
+        Some("run") => run(Args::parse(args.skip(1), RUN_OPTIONS, RUN_SWITCHES)),
+        Some("sweep") => sweep(Args::parse(args.skip(1), &["--threads", "--format", "--out"], &[])),
+        Some("replay") => replay(Args::parse(args.skip(1), &["--format"], &[("--verbose", "-v")])),
+        Some("inspect") => inspect(Args::parse(args.skip(1), &[], &[])),
+        Some("schedule") => schedule(Args::parse(args.skip(1), &["--epoch", "--format"], &[])),
+        Some("prove") => prove(Args::parse(args.skip(1), &["--slot", "--validator", "--ticket"], &[])),
+        Some("help" | "-h" | "--help") => println!("{}", USAGE),
+        // A bare scenario path runs it, as before subcommands existed.
+        Some(_) => run(Args::parse(args, RUN_OPTIONS, RUN_SWITCHES)),
+    }
+}
 
-    // --- Bucket IDs ---
-    let active_bucket_id = 0u64;
-    let muted_bucket_id = 1u64;
-    let dead_bucket_id = 2u64;
+const RUN_OPTIONS: &[&str] = &[
+    "--slots",
+    "--epochs",
+    "--format",
+    "--log",
+    "--resume",
+    "--snapshot-every",
+    "--snapshot-dir",
+    "--snapshot-keep",
+    "--record",
+];
+const RUN_SWITCHES: &[(&str, &str)] = &[("--until-jail", ""), ("--verbose", "-v"), ("--quiet", "-q")];
 
-    // --- Buckets ---
-    let mut buckets = HashMap::new();
+fn load_scenario(path: &str) -> Scenario {
+    Scenario::load(path).unwrap_or_else(|e| fail(format!("failed to load scenario {}: {}", path, e)))
+}
 
-    buckets.insert(active_bucket_id, Bucket {
-        id: active_bucket_id,
-        ticket_ids: HashSet::new(),
-    });
+fn build_simulator(path: &str, scenario: &Scenario) -> Simulator {
+    scenario.build_simulator().unwrap_or_else(|e| fail(format!("invalid scenario {}: {}", path, e)))
+}
 
-    buckets.insert(muted_bucket_id, Bucket {
-        id: muted_bucket_id,
-        ticket_ids: HashSet::new(),
-    });
+fn run_slot(sim: &mut Simulator) -> eternix_sim::types::block::Block {
+    sim.run_one_slot().unwrap_or_else(|e| fail(format!("slot {} failed: {}", sim.clock.slot_index, e)))
+}
 
-    buckets.insert(dead_bucket_id, Bucket {
-        id: dead_bucket_id,
-        ticket_ids: HashSet::new(),
-    });
-    // --- Two tickets (start ACTIVE) ---
-    let ticket1_id = 1u64;
-    let ticket2_id = 2u64;
-
-    let mut tickets = HashMap::new();
-    tickets.insert(
-        ticket1_id,
-        Ticket {
-            id: ticket1_id,
-            owner: validator1_id,
-            bucket: active_bucket_id,
-            creation_epoch: 0,
-            state: TicketState::Active,
-            retire_requested_epoch: None,
-            retire_effective_epoch: None,
-        },
-    );
-    tickets.insert(
-        ticket2_id,
-        Ticket{
-            id: ticket2_id,
-            owner: validator2_id,
-            bucket: active_bucket_id,
-            creation_epoch: 0,
-            state: TicketState::Active,
-            retire_requested_epoch: None,
-            retire_effective_epoch: None,
-        },
-    );
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
 
-    // Put tickets into ACTIVE bucket
-    buckets
-    .get_mut(&active_bucket_id)
-    .unwrap()
-    .ticket_ids
-    .insert(ticket1_id);
-
-    buckets
-    .get_mut(&active_bucket_id)
-    .unwrap()
-    .ticket_ids
-    .insert(ticket2_id);
-
-    // --- Bucket category sets ---
-    let mut active_bucket_ids = HashSet::new();
-    active_bucket_ids.insert(active_bucket_id);
-
-    let mut muted_bucket_ids = HashSet::new();
-    muted_bucket_ids.insert(muted_bucket_id);
-
-    let state = ChainState {
-        validators,
-        tickets,
-        buckets,
-
-        active_bucket_ids,
-        muted_bucket_ids,
-        dead_bucket_id,
-
-        epoch_index: 0,
-        sub_epoch_index: 0,
-        epoch_seed: [7u8; 32],
-
-        retire_per_epoch_limit: 2,
-        retire_schedule: BTreeMap::new(),
-        retire_finalize: BTreeMap::new(),
-    };
+fn state_name(state: ValidatorState) -> String {
+    serde_json::to_value(state).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default()
+}
 
-    let clock = SimClock {
-        now_ms: 0,
-        slot_start_ms: 0,
-        slot_index: 0,
-    };
+fn sorted_validators(state: &ChainState) -> Vec<&Validator> {
+    let mut validators: Vec<&Validator> = state.validators.values().collect();
+    validators.sort_unstable_by_key(|v| v.id);
+    validators
+}
+
+/// One validator's row in the per-slot JSON table.
+#[derive(Serialize)]
+struct ValidatorRow {
+    id: u64,
+    state: ValidatorState,
+    miss_counter: u32,
+    #[serde(with = "amount")]
+    vault_balance: u128,
+}
+
+fn run(args: Args) {
+    let path = args.path("scenario");
+    let mut scenario = load_scenario(path);
+    match (args.number("--slots"), args.number("--epochs")) {
+        (Some(_), Some(_)) => fail("--slots and --epochs are mutually exclusive"),
+        (Some(slots), None) => (scenario.run.slots, scenario.run.epochs) = (Some(slots), None),
+        (None, Some(epochs)) => (scenario.run.slots, scenario.run.epochs) = (None, Some(epochs)),
+        (None, None) => {}
+    }
+    let format = args.format(Format::Text, &[Format::Text, Format::Json, Format::Csv]);
+    let (verbose, quiet) = (args.switch("--verbose"), args.switch("--quiet"));
+
+    if let Some(every_epochs) = args.number("--snapshot-every") {
+        scenario.snapshots = Some(AutoSnapshot {
+            dir: args.values.get("--snapshot-dir").map(String::as_str).unwrap_or("snapshots").into(),
+            every_epochs,
+            keep: args.number("--snapshot-keep").unwrap_or(3) as usize,
+        });
+    }
+
+    let mut sim = build_simulator(path, &scenario);
+    let slots = scenario.run_slots().unwrap_or_else(|e| fail(format!("invalid scenario {}: {}", path, e)));
+    if let Some(snapshot) = args.values.get("--resume") {
+        let auto_snapshot = sim.auto_snapshot.take();
+        sim = Snapshot::load(snapshot)
+            .and_then(Simulator::from_snapshot)
+            .unwrap_or_else(|e| fail(format!("cannot resume from {}: {}", snapshot, e)));
+        sim.auto_snapshot = auto_snapshot;
+        eprintln!("resuming at slot {} (epoch {})", sim.clock.slot_index, sim.state.epoch_index);
+    }
+    let record = args.values.get("--record");
+    if let Some(path) = record {
+        if args.values.contains_key("--resume") {
+            fail("--record cannot be combined with --resume");
+        }
+        sim.recording = Some(Recording::new(scenario.clone(), sim.state.clone()));
+        // Fail before the run rather than after it.
+        File::create(path).unwrap_or_else(|e| fail(format!("cannot create {}: {}", path, e)));
+    }
 
-    let mut sim = Simulator {
-        clock,
-        state,
-        blocks: Vec::new(),
-        epoch_len_slots: 10,
+    if let Some(log) = args.values.get("--log") {
+        let sink = JsonLinesSink::create(log).unwrap_or_else(|e| fail(format!("cannot create {}: {}", log, e)));
+        sim.add_sink(Box::new(sink));
+    }
+    let events = MemorySink::default();
+    if !quiet {
+        match format {
+            Format::Text => sim.add_sink(Box::new(ConsoleSink { verbose })),
+            Format::Json => sim.add_sink(Box::new(events.clone())),
+            Format::Csv => {}
+        }
+    }
+    if format == Format::Csv {
+        println!("slot,proposer,state_root,validator,state,miss_counter,vault_balance");
+    }
+
+    let mut jailed: BTreeSet<u64> = jailed_ids(&sim.state);
+    while sim.clock.slot_index < slots {
+        let block = match sim.run_one_slot() {
+            Ok(block) => block,
+            Err(e) => {
+                // The recording ends with the failing slot's inputs; keep it.
+                sim.flush_sinks();
+                if let (Some(path), Some(recording)) = (record, &sim.recording) {
+                    match recording.save(path) {
+                        Ok(()) => eprintln!("recording written to {}", path),
+                        Err(e) => eprintln!("cannot write recording: {}", e),
+                    }
+                }
+                fail(format!("slot {} failed: {}", sim.clock.slot_index, e))
+            }
+        };
+        let validators = sorted_validators(&sim.state);
+
+        match format {
+            Format::Text => {
+                let mut line = format!("Block {} | proposer: {:?}", block.slot_index, block.proposer);
+                for v in &validators {
+                    line.push_str(&format!(
+                        " | v{}: {:?} miss={} vault={}",
+                        v.id, v.state, v.miss_counter, v.vault_balance
+                    ));
+                }
+                println!("{}", line);
+            }
+            Format::Json => {
+                let rows: Vec<ValidatorRow> = validators
+                    .iter()
+                    .map(|v| ValidatorRow {
+                        id: v.id,
+                        state: v.state,
+                        miss_counter: v.miss_counter,
+                        vault_balance: v.vault_balance,
+                    })
+                    .collect();
+                let mut line = json!({
+                    "slot": block.slot_index,
+                    "proposer": block.proposer,
+                    "state_root": hex(&block.state_root),
+                    "validators": rows,
+                });
+                if !quiet {
+                    line["events"] = serde_json::to_value(events.records()).unwrap_or_default();
+                    events.clear();
+                }
+                println!("{}", line);
+            }
+            Format::Csv => {
+                let proposer = block.proposer.map(|p| p.to_string()).unwrap_or_default();
+                let root = hex(&block.state_root);
+                for v in &validators {
+                    println!(
+                        "{},{},{},{},{},{},{}",
+                        block.slot_index,
+                        proposer,
+                        root,
+                        v.id,
+                        state_name(v.state),
+                        v.miss_counter,
+                        v.vault_balance
+                    );
+                }
+            }
+        }
+
+        if args.switch("--until-jail") {
+            let now = jailed_ids(&sim.state);
+            if let Some(id) = now.difference(&jailed).next() {
+                eprintln!("stopping after slot {}: validator {} jailed", block.slot_index, id);
+                break;
+            }
+            jailed = now;
+        }
+    }
+    sim.flush_sinks();
+
+    if let (Some(path), Some(recording)) = (record, sim.recording.take()) {
+        recording.save(path).unwrap_or_else(|e| fail(format!("cannot write recording: {}", e)));
+    }
+}
+
+fn jailed_ids(state: &ChainState) -> BTreeSet<u64> {
+    state.validators.values().filter(|v| v.state == ValidatorState::Jailed).map(|v| v.id).collect()
+}
+
+fn sweep(args: Args) {
+    let path = args.path("sweep file");
+    let threads = args.number("--threads").unwrap_or(0) as usize;
+    let format = args.format(Format::Csv, &[Format::Csv, Format::Json]);
+
+    let spec = SweepSpec::load(path).unwrap_or_else(|e| fail(format!("invalid sweep {}: {}", path, e)));
+    let base = load_scenario(&spec.scenario);
+    let runs = spec.plan(&base).unwrap_or_else(|e| fail(format!("invalid sweep {}: {}", path, e)));
+
+    eprintln!("running {} runs", runs.len());
+    let results = run_sweep(&runs, threads);
+
+    let written = match (args.values.get("--out"), format) {
+        (None, Format::Json) => write_json(&results, std::io::stdout().lock()),
+        (None, _) => write_csv(&results, std::io::stdout().lock()),
+        (Some(out), format) => {
+            let file = File::create(out).unwrap_or_else(|e| fail(format!("cannot create {}: {}", out, e)));
+            if format == Format::Json || (!args.values.contains_key("--format") && out.ends_with(".json")) {
+                write_json(&results, BufWriter::new(file))
+            } else {
+                write_csv(&results, BufWriter::new(file))
+            }
+        }
     };
+    if let Err(e) = written {
+        fail(format!("cannot write results: {}", e));
+    }
 
-    // request_ticket_retire(&mut sim.state, 1, vec![1]);
-    // println!("{:?}", sim.state.retire_schedule);
+    let failed = results.iter().filter(|r| r.summary.is_err()).count();
+    if failed > 0 {
+        eprintln!("{} of {} runs failed", failed, results.len());
+    }
+}
 
-    // --- Run a few slots ---
-    for _ in 0..500 {
-//        let current_slot = sim.clock.slot_index;
+/// Re-render an event log written with `run --log`, or re-run a recording
+/// written with `run --record` and check that it reproduces every block.
+fn replay(args: Args) {
+    let path = args.path("event log or recording");
+    let format = args.format(Format::Text, &[Format::Text, Format::Json]);
+    let verbose = args.switch("--verbose");
 
-        // Inject at specific block
-//        if current_slot == 25 {
-//            on_vault_refill(&mut sim.state, 1, 50_000u128);
-//            println!(50,000 refilled to Validator 1)
-//        }
+    let file = File::open(path).unwrap_or_else(|e| fail(format!("cannot read {}: {}", path, e)));
+    let mut lines = BufReader::new(file).lines().map(|line| {
+        line.unwrap_or_else(|e| fail(format!("cannot read {}: {}", path, e)))
+    });
+    // `Recording::save` writes a single line; an event log has one per record.
+    let first = lines.next().unwrap_or_default();
+    if Recording::is_recording(&first) {
+        return replay_recording(path, &first, format);
+    }
+    for (n, line) in std::iter::once(first).chain(lines).enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: EventRecord =
+            serde_json::from_str(&line).unwrap_or_else(|e| fail(format!("{}:{}: {}", path, n + 1, e)));
+        match format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(&record).unwrap_or_default()),
+            _ => {
+                if let Some(text) = ConsoleSink::format(&record, verbose) {
+                    println!("[slot {} epoch {}] {}", record.slot, record.epoch, text);
+                }
+            }
+        }
+    }
+}
 
-        
+fn replay_recording(path: &str, text: &str, format: Format) {
+    let outcome = Recording::from_json(text)
+        .and_then(|recording| recording.replay())
+        .unwrap_or_else(|e| fail(format!("cannot replay {}: {}", path, e)));
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&outcome).unwrap_or_default()),
+        _ => println!("{}", outcome),
+    }
+    if !matches!(outcome, ReplayOutcome::Matched { .. } | ReplayOutcome::FailureReproduced { .. }) {
+        std::process::exit(1);
+    }
+}
+
+/// Print the state in a snapshot, or a scenario's genesis state.
+fn inspect(args: Args) {
+    let path = args.path("scenario or snapshot");
+    let sim = match Snapshot::load(path) {
+        Ok(snapshot) => Simulator::from_snapshot(snapshot).unwrap_or_else(|e| fail(format!("{}: {}", path, e))),
+        Err(e @ SnapshotError::UnsupportedVersion(_)) => fail(format!("{}: {}", path, e)),
+        Err(_) => build_simulator(path, &load_scenario(path)),
+    };
+    print_state(&sim);
+}
 
-        let block = sim.run_one_slot();
+fn print_state(sim: &Simulator) {
+    let state = &sim.state;
+    println!(
+        "slot {} | epoch {} | sub-epoch {} | {} blocks",
+        sim.clock.slot_index,
+        state.epoch_index,
+        state.sub_epoch_index,
+        sim.blocks.len()
+    );
+    println!("epoch seed     {}", hex(&state.epoch_seed));
+    println!("sub-epoch seed {}", hex(&state.sub_epoch_seed));
 
-        let v1 = sim.state.validators.get(&1).unwrap();
-        let v2 = sim.state.validators.get(&2).unwrap();
+    println!();
+    println!(
+        "{} buckets ({} active, {} muted), {} tickets",
+        state.buckets.len(),
+        state.active_bucket_ids.len(),
+        state.muted_bucket_ids.len(),
+        state.tickets.len()
+    );
+    let mut bucket_ids: Vec<u64> = state.buckets.keys().copied().collect();
+    bucket_ids.sort_unstable();
+    // Empty active buckets are left out; large layouts have many.
+    for id in bucket_ids {
+        if state.buckets[&id].ticket_ids.is_empty() && state.active_bucket_ids.contains(&id) {
+            continue;
+        }
+        let role = if state.active_bucket_ids.contains(&id) {
+            "active"
+        } else if state.muted_bucket_ids.contains(&id) {
+            "muted"
+        } else if id == state.dead_bucket_id {
+            "dead"
+        } else {
+            "-"
+        };
+        println!("bucket {:>3} {:<6} {} tickets", id, role, state.buckets[&id].ticket_ids.len());
+    }
 
+    println!();
+    println!(
+        "{:>6} {:<8} {:>20} {:>20} {:>20} {:>5} {:>8} {:>8}",
+        "id", "state", "vault", "initial bond", "withdrawable", "miss", "offenses", "cooldown"
+    );
+    for v in sorted_validators(state) {
         println!(
-            "Block {} | proposer: {:?} | v1: {:?} miss={} vault={} | v2: {:?} miss={} vault={}",
-            block.slot_index,
-            block.proposer,
-            v1.state,
-            v1.miss_counter,
-            v1.vault_balance,
-            v2.state,
-            v2.miss_counter,
-            v2.vault_balance,
+            "{:>6} {:<8} {:>20} {:>20} {:>20} {:>5} {:>8} {:>8}",
+            v.id,
+            state_name(v.state),
+            v.vault_balance,
+            v.initial_bond,
+            v.withdrawable_balance,
+            v.miss_counter,
+            v.double_sign_offenses,
+            v.cooldown_until_epoch.map(|e| e.to_string()).unwrap_or_else(|| "-".to_string())
         );
     }
 
+    println!();
+    println!("{}", SupplyReport::from_state(state));
+}
+
+/// Print the leaders of one epoch, drawn from the state at its first slot.
+fn schedule(args: Args) {
+    let path = args.path("scenario");
+    let format = args.format(Format::Text, &[Format::Text, Format::Json, Format::Csv]);
+    let scenario = load_scenario(path);
+    let mut sim = build_simulator(path, &scenario);
+
+    let epoch = args.number("--epoch").unwrap_or(0);
+    while sim.state.epoch_index < epoch {
+        run_slot(&mut sim);
+    }
+
+    let schedule = leader_schedule(&sim.state, sim.epoch_start_slot, sim.epoch_len_slots, sim.sub_epoch_len_slots)
+        .unwrap_or_else(|e| fail(format!("cannot compute the schedule: {}", e)));
+    let sub_epoch = |slot: u64| (slot - sim.epoch_start_slot).checked_div(sim.sub_epoch_len_slots).unwrap_or(0);
+
+    match format {
+        Format::Text => {
+            println!("epoch {} | seed {}", epoch, hex(&sim.state.epoch_seed));
+            for (slot, leader) in schedule {
+                let leader = leader.map(|v| format!("v{}", v)).unwrap_or_else(|| "protocol".to_string());
+                println!("slot {:>6} | sub-epoch {} | leader {}", slot, sub_epoch(slot), leader);
+            }
+        }
+        Format::Json => {
+            let rows: Vec<_> = schedule
+                .into_iter()
+                .map(|(slot, leader)| json!({ "slot": slot, "sub_epoch": sub_epoch(slot), "leader": leader }))
+                .collect();
+            println!("{}", json!({ "epoch": epoch, "seed": hex(&sim.state.epoch_seed), "slots": rows }));
+        }
+        Format::Csv => {
+            println!("slot,sub_epoch,leader");
+            for (slot, leader) in schedule {
+                println!("{},{},{}", slot, sub_epoch(slot), leader.map(|v| v.to_string()).unwrap_or_default());
+            }
+        }
+    }
+}
+
+/// Print a proof of one validator or ticket record against block N's state
+/// root, after checking that it verifies.
+fn prove(args: Args) {
+    let path = args.path("scenario");
+    let slot = args.number("--slot").unwrap_or_else(|| fail(format!("missing --slot\n\n{}", USAGE)));
+    let scenario = load_scenario(path);
+    let mut sim = build_simulator(path, &scenario);
+    while sim.clock.slot_index <= slot {
+        run_slot(&mut sim);
+    }
+    let root = sim.blocks[slot as usize].state_root;
+    let tree = StateTree::build(&sim.state).unwrap_or_else(|e| fail(format!("cannot build the state tree: {}", e)));
+
+    match (args.number("--validator"), args.number("--ticket")) {
+        (Some(id), None) => print_proof(tree.prove_validator(&sim.state, id), root, slot),
+        (None, Some(id)) => print_proof(tree.prove_ticket(&sim.state, id), root, slot),
+        _ => fail(format!("expected one of --validator or --ticket\n\n{}", USAGE)),
+    }
+}
+
+fn print_proof<T: Record>(proof: eternix_sim::state::merkle::Proof<T>, root: [u8; 32], slot: u64) {
+    if let Err(e) = proof.verify(root) {
+        fail(format!("proof does not verify: {}", e));
+    }
+    println!("{}", serde_json::to_string_pretty(&proof).expect("proofs are always serialisable"));
+    eprintln!(
+        "{} {} {} at block {} (state root {})",
+        String::from_utf8_lossy(T::TREE).trim_start_matches("eternix/"),
+        proof.id,
+        if proof.record.is_some() { "included" } else { "excluded" },
+        slot,
+        hex(&root)
+    );
 }
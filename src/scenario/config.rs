@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::beacon::BeaconSpec;
+use crate::consensus::evidence::EvidenceParams;
+use crate::consensus::leader_selection::BucketSelection;
+use crate::consensus::params::ProtocolParams;
+use crate::economics::inflation::InflationParams;
+use crate::economics::rewards::RewardParams;
+use crate::economics::slashing::SlashPolicy;
+use crate::scenario::loader::ScenarioError;
+use crate::sim::behavior::BehaviorSpec;
+use crate::sim::snapshot::AutoSnapshot;
+use crate::types::amount;
+use crate::sim::timeline::TimelineEvent;
+use crate::types::validator::ValidatorState;
+
+/// A declarative description of a genesis state and how long to run it.
+///
+/// Scenarios are plain data; `loader` turns them into a validated
+/// `ChainState` / `Simulator`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub chain: ChainConfig,
+    pub buckets: BucketLayout,
+    pub validators: Vec<ValidatorConfig>,
+    #[serde(default)]
+    pub tickets: Vec<TicketConfig>,
+    pub run: RunConfig,
+    #[serde(default)]
+    pub protocol: ProtocolParams,
+    #[serde(default)]
+    pub rewards: RewardParams,
+    #[serde(default)]
+    pub inflation: InflationParams,
+    #[serde(default)]
+    pub slashing: SlashPolicy,
+    #[serde(default)]
+    pub evidence: EvidenceParams,
+    /// Periodic snapshots of the run; none by default.
+    #[serde(default)]
+    pub snapshots: Option<AutoSnapshot>,
+    /// Scheduled mid-run interventions.
+    #[serde(default)]
+    pub events: Vec<TimelineEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    /// 32-byte genesis seed, hex encoded (64 characters).
+    pub epoch_seed: String,
+    pub epoch_len_slots: u64,
+    /// Sub-epoch length in slots; omitted or 0 means one sub-epoch per epoch.
+    #[serde(default)]
+    pub sub_epoch_len_slots: u64,
+    pub retire_per_epoch_limit: u64,
+    /// How epoch seeds evolve; `static` keeps the genesis seed.
+    #[serde(default)]
+    pub beacon: BeaconSpec,
+    /// First-stage leader selection rule; `scaled_min` unless set.
+    #[serde(default)]
+    pub bucket_selection: BucketSelection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BucketLayout {
+    pub active: BucketIds,
+    #[serde(default)]
+    pub muted: Vec<u64>,
+    #[serde(default)]
+    pub dead: Option<u64>,
+
+    /// Optional explicit bucket membership. Tickets not listed here are
+    /// placed in the bucket named by their own `bucket` field.
+    #[serde(default)]
+    pub contents: Vec<BucketContents>,
+}
+
+/// ACTIVE bucket ids, either listed or as a contiguous range
+/// (`active = { first = 0, count = 254 }`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BucketIds {
+    List(Vec<u64>),
+    Range { first: u64, count: u64 },
+}
+
+/// Most ACTIVE buckets a range may declare.
+pub const MAX_ACTIVE_BUCKETS: u64 = 1 << 16;
+
+impl BucketIds {
+    pub fn ids(&self) -> Result<Vec<u64>, ScenarioError> {
+        match *self {
+            BucketIds::List(ref ids) => Ok(ids.clone()),
+            BucketIds::Range { first, count } => match first.checked_add(count) {
+                Some(end) if count <= MAX_ACTIVE_BUCKETS => Ok((first..end).collect()),
+                _ => Err(ScenarioError::InvalidBucketRange { first, count }),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BucketContents {
+    pub id: u64,
+    pub ticket_ids: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorConfig {
+    pub id: u64,
+    #[serde(with = "amount")]
+    pub vault_balance: u128,
+    #[serde(with = "amount")]
+    pub initial_bond: u128,
+    #[serde(default = "default_validator_state")]
+    pub state: ValidatorState,
+    /// Required for, and only allowed with, `state = "punished_cooldown"`.
+    #[serde(default)]
+    pub cooldown_until_epoch: Option<u64>,
+    /// How this validator acts; honest when omitted.
+    #[serde(default)]
+    pub behavior: Option<BehaviorSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TicketConfig {
+    pub id: u64,
+    pub owner: u64,
+    /// Omit to place the ticket in its deterministic home bucket.
+    #[serde(default)]
+    pub bucket: Option<u64>,
+    #[serde(default)]
+    pub creation_epoch: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunConfig {
+    /// Number of slots to simulate.
+    #[serde(default)]
+    pub slots: Option<u64>,
+    /// Number of epochs to simulate (converted using `epoch_len_slots`).
+    #[serde(default)]
+    pub epochs: Option<u64>,
+    /// Seed for probabilistic validator behaviour.
+    #[serde(default)]
+    pub seed: u64,
+    /// Check consensus invariants after every slot; see `state::invariants`.
+    #[serde(default)]
+    pub check_invariants: bool,
+}
+
+fn default_validator_state() -> ValidatorState {
+    ValidatorState::Active
+}
@@ -0,0 +1,552 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+use crate::consensus::evidence::EvidencePool;
+use crate::economics::rewards::RewardTally;
+use crate::economics::supply::SupplyLedger;
+use crate::scenario::config::{Scenario, MAX_ACTIVE_BUCKETS};
+use crate::sim::timeline::Action;
+use crate::state::bucket_ops::home_bucket_for;
+use crate::sim::clock::SimClock;
+use crate::sim::simulator::Simulator;
+use crate::state::chain_state::ChainState;
+use crate::state::journal::JournaledMap;
+use crate::state::invariants::Invariants;
+use crate::state::ticket_index::TicketIndex;
+use crate::types::bucket::Bucket;
+use crate::types::ticket::{Ticket, TicketState};
+use crate::types::validator::{Validator, ValidatorState};
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(String, std::io::Error),
+    Parse(String),
+    UnsupportedFormat(String),
+    InvalidSeed(String),
+    ZeroEpochLength,
+    ZeroRetireLimit,
+    MissingRunLength,
+    ConflictingRunLength,
+    RunLengthOverflow { epochs: u64, epoch_len_slots: u64 },
+    DuplicateValidator(u64),
+    DuplicateTicket(u64),
+    DuplicateBucket(u64),
+    NoActiveBuckets,
+    InvalidBucketRange { first: u64, count: u64 },
+    MissingMutedBucket,
+    MissingDeadBucket,
+    UnknownOwner { ticket: u64, owner: u64 },
+    UnknownBucket { ticket: u64, bucket: u64 },
+    UnknownBucketContents(u64),
+    UnknownTicketInBucket { bucket: u64, ticket: u64 },
+    TicketInSeveralBuckets { ticket: u64, first: u64, second: u64 },
+    TicketBucketMismatch { ticket: u64, declared: u64, listed_in: u64 },
+    /// A PunishedCooldown validator without `cooldown_until_epoch`.
+    MissingCooldown(u64),
+    /// `cooldown_until_epoch` on a validator that is not PunishedCooldown.
+    UnexpectedCooldown(u64),
+    /// A Jailed validator owning any ticket, or an Inactive one owning a
+    /// ticket in an ACTIVE bucket; genesis tickets are all Active.
+    LiveTicketOfIdleOwner { ticket: u64, owner: u64, state: ValidatorState },
+    /// Genesis tickets are Active, so none may start in the DEAD bucket.
+    ActiveTicketInDeadBucket(u64),
+    InvalidBehavior { validator: u64, reason: String },
+    InvalidEvent { index: usize, reason: String },
+    InvalidInflation(String),
+    InvalidProtocol(String),
+    InvalidSlashing(String),
+    InvalidEvidence(String),
+    InvalidSnapshots(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(path, e) => write!(f, "cannot read {}: {}", path, e),
+            ScenarioError::Parse(msg) => write!(f, "parse error: {}", msg),
+            ScenarioError::UnsupportedFormat(path) => {
+                write!(f, "unsupported scenario format for {} (expected .toml or .json)", path)
+            }
+            ScenarioError::InvalidSeed(seed) => {
+                write!(f, "epoch_seed must be 64 hex characters, got {:?}", seed)
+            }
+            ScenarioError::ZeroEpochLength => write!(f, "epoch_len_slots must be greater than zero"),
+            ScenarioError::ZeroRetireLimit => write!(f, "retire_per_epoch_limit must be greater than zero"),
+            ScenarioError::MissingRunLength => write!(f, "run needs either `slots` or `epochs`"),
+            ScenarioError::ConflictingRunLength => write!(f, "run cannot set both `slots` and `epochs`"),
+            ScenarioError::RunLengthOverflow { epochs, epoch_len_slots } => {
+                write!(f, "{} epochs of {} slots do not fit in a u64 slot count", epochs, epoch_len_slots)
+            }
+            ScenarioError::DuplicateValidator(id) => write!(f, "validator {} declared twice", id),
+            ScenarioError::DuplicateTicket(id) => write!(f, "ticket {} declared twice", id),
+            ScenarioError::DuplicateBucket(id) => write!(f, "bucket {} declared twice", id),
+            ScenarioError::NoActiveBuckets => write!(f, "no ACTIVE bucket defined"),
+            ScenarioError::InvalidBucketRange { first, count } => write!(
+                f,
+                "ACTIVE bucket range of {} from {} must stay within u64 and hold at most {} buckets",
+                count, first, MAX_ACTIVE_BUCKETS
+            ),
+            ScenarioError::MissingMutedBucket => write!(f, "no MUTED bucket defined"),
+            ScenarioError::MissingDeadBucket => write!(f, "no DEAD bucket defined"),
+            ScenarioError::UnknownOwner { ticket, owner } => {
+                write!(f, "ticket {} is owned by unknown validator {}", ticket, owner)
+            }
+            ScenarioError::UnknownBucket { ticket, bucket } => {
+                write!(f, "ticket {} refers to unknown bucket {}", ticket, bucket)
+            }
+            ScenarioError::UnknownBucketContents(bucket) => {
+                write!(f, "contents given for unknown bucket {}", bucket)
+            }
+            ScenarioError::UnknownTicketInBucket { bucket, ticket } => {
+                write!(f, "bucket {} lists unknown ticket {}", bucket, ticket)
+            }
+            ScenarioError::TicketInSeveralBuckets { ticket, first, second } => {
+                write!(f, "ticket {} is listed in both bucket {} and bucket {}", ticket, first, second)
+            }
+            ScenarioError::TicketBucketMismatch { ticket, declared, listed_in } => write!(
+                f,
+                "ticket {} declares bucket {} but is listed in bucket {}",
+                ticket, declared, listed_in
+            ),
+            ScenarioError::MissingCooldown(id) => {
+                write!(f, "validator {} is punished_cooldown but has no cooldown_until_epoch", id)
+            }
+            ScenarioError::UnexpectedCooldown(id) => {
+                write!(f, "validator {} has cooldown_until_epoch but is not punished_cooldown", id)
+            }
+            ScenarioError::LiveTicketOfIdleOwner { ticket, owner, state } => {
+                write!(f, "ticket {} is live but its owner {} is {:?}", ticket, owner, state)
+            }
+            ScenarioError::ActiveTicketInDeadBucket(id) => write!(f, "Active ticket {} is placed in the DEAD bucket", id),
+            ScenarioError::InvalidBehavior { validator, reason } => {
+                write!(f, "invalid behaviour for validator {}: {}", validator, reason)
+            }
+            ScenarioError::InvalidEvent { index, reason } => {
+                write!(f, "invalid event #{}: {}", index, reason)
+            }
+            ScenarioError::InvalidInflation(reason) => write!(f, "invalid inflation: {}", reason),
+            ScenarioError::InvalidSlashing(reason) => write!(f, "invalid slashing policy: {}", reason),
+            ScenarioError::InvalidEvidence(reason) => write!(f, "invalid evidence parameters: {}", reason),
+            ScenarioError::InvalidProtocol(reason) => write!(f, "invalid protocol parameters: {}", reason),
+            ScenarioError::InvalidSnapshots(reason) => write!(f, "invalid snapshot settings: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl Scenario {
+    /// Load a scenario from a `.toml` or `.json` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Scenario, ScenarioError> {
+        let path = path.as_ref();
+        let display = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|e| ScenarioError::Io(display.clone(), e))?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Scenario::from_toml_str(&text),
+            Some("json") => Scenario::from_json_str(&text),
+            _ => Err(ScenarioError::UnsupportedFormat(display)),
+        }
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<Scenario, ScenarioError> {
+        toml::from_str(text).map_err(|e| ScenarioError::Parse(e.to_string()))
+    }
+
+    pub fn from_json_str(text: &str) -> Result<Scenario, ScenarioError> {
+        serde_json::from_str(text).map_err(|e| ScenarioError::Parse(e.to_string()))
+    }
+
+    /// Total number of slots the scenario asks to run.
+    pub fn run_slots(&self) -> Result<u64, ScenarioError> {
+        match (self.run.slots, self.run.epochs) {
+            (Some(_), Some(_)) => Err(ScenarioError::ConflictingRunLength),
+            (Some(slots), None) => Ok(slots),
+            (None, Some(epochs)) => epochs
+                .checked_mul(self.chain.epoch_len_slots)
+                .ok_or(ScenarioError::RunLengthOverflow { epochs, epoch_len_slots: self.chain.epoch_len_slots }),
+            (None, None) => Err(ScenarioError::MissingRunLength),
+        }
+    }
+
+    /// Build and validate the genesis `ChainState`.
+    pub fn build_state(&self) -> Result<ChainState, ScenarioError> {
+        let epoch_seed = parse_seed(&self.chain.epoch_seed)?;
+
+        if self.chain.epoch_len_slots == 0 {
+            return Err(ScenarioError::ZeroEpochLength);
+        }
+        if self.chain.retire_per_epoch_limit == 0 {
+            return Err(ScenarioError::ZeroRetireLimit);
+        }
+        self.protocol.validate().map_err(ScenarioError::InvalidProtocol)?;
+        self.inflation.validate().map_err(ScenarioError::InvalidInflation)?;
+        self.slashing.validate().map_err(ScenarioError::InvalidSlashing)?;
+        self.evidence.validate().map_err(ScenarioError::InvalidEvidence)?;
+
+        // --- Validators ---
+        let mut validators = BTreeMap::new();
+        for v in &self.validators {
+            let validator = Validator {
+                id: v.id,
+                state: v.state,
+                vault_balance: v.vault_balance,
+                initial_bond: v.initial_bond,
+                withdrawable_balance: 0,
+            miss_counter: 0,
+                double_sign_offenses: 0,
+                cooldown_until_epoch: v.cooldown_until_epoch,
+            };
+            match (v.state, v.cooldown_until_epoch) {
+                (ValidatorState::PunishedCooldown, None) => return Err(ScenarioError::MissingCooldown(v.id)),
+                (ValidatorState::PunishedCooldown, Some(_)) | (_, None) => {}
+                (_, Some(_)) => return Err(ScenarioError::UnexpectedCooldown(v.id)),
+            }
+            if validators.insert(v.id, validator).is_some() {
+                return Err(ScenarioError::DuplicateValidator(v.id));
+            }
+        }
+
+        // --- Bucket layout ---
+        let layout = &self.buckets;
+        let active_ids = layout.active.ids()?;
+        if active_ids.is_empty() {
+            return Err(ScenarioError::NoActiveBuckets);
+        }
+        if layout.muted.is_empty() {
+            return Err(ScenarioError::MissingMutedBucket);
+        }
+        let dead_bucket_id = layout.dead.ok_or(ScenarioError::MissingDeadBucket)?;
+
+        let mut buckets = BTreeMap::new();
+        let all_ids = active_ids.iter().chain(layout.muted.iter()).chain(std::iter::once(&dead_bucket_id));
+        for &id in all_ids {
+            let bucket = Bucket { id, ticket_ids: BTreeSet::new() };
+            if buckets.insert(id, bucket).is_some() {
+                return Err(ScenarioError::DuplicateBucket(id));
+            }
+        }
+
+        let mut sorted_active = active_ids.clone();
+        sorted_active.sort_unstable();
+
+        // --- Explicit bucket contents ---
+        let declared: BTreeSet<u64> = self.tickets.iter().map(|t| t.id).collect();
+        let mut listed_in: BTreeMap<u64, u64> = BTreeMap::new();
+        for contents in &layout.contents {
+            if !buckets.contains_key(&contents.id) {
+                return Err(ScenarioError::UnknownBucketContents(contents.id));
+            }
+            for &tid in &contents.ticket_ids {
+                if !declared.contains(&tid) {
+                    return Err(ScenarioError::UnknownTicketInBucket { bucket: contents.id, ticket: tid });
+                }
+                if let Some(&first) = listed_in.get(&tid) {
+                    return Err(ScenarioError::TicketInSeveralBuckets { ticket: tid, first, second: contents.id });
+                }
+                listed_in.insert(tid, contents.id);
+            }
+        }
+
+        // --- Tickets ---
+        // A ticket's bucket comes from its own `bucket` field or from the contents
+        // listing (which must agree); with neither it starts in its home bucket.
+        let mut tickets = BTreeMap::new();
+        for t in &self.tickets {
+            if !validators.contains_key(&t.owner) {
+                return Err(ScenarioError::UnknownOwner { ticket: t.id, owner: t.owner });
+            }
+            if let Some(bucket) = t.bucket
+                && !buckets.contains_key(&bucket)
+            {
+                return Err(ScenarioError::UnknownBucket { ticket: t.id, bucket });
+            }
+
+            let listed = listed_in.get(&t.id).copied();
+            if let (Some(declared), Some(listed_in)) = (t.bucket, listed)
+                && declared != listed_in
+            {
+                return Err(ScenarioError::TicketBucketMismatch { ticket: t.id, declared, listed_in });
+            }
+
+            let computed_home =
+                home_bucket_for(t.id, epoch_seed, &sorted_active).map_err(|_| ScenarioError::NoActiveBuckets)?;
+            let bucket = t.bucket.or(listed).unwrap_or(computed_home);
+            let home_bucket = if sorted_active.binary_search(&bucket).is_ok() {
+                bucket
+            } else {
+                computed_home
+            };
+
+            if bucket == dead_bucket_id {
+                return Err(ScenarioError::ActiveTicketInDeadBucket(t.id));
+            }
+            let owner_state = validators[&t.owner].state;
+            let idle = match owner_state {
+                ValidatorState::Jailed => true,
+                ValidatorState::Inactive => sorted_active.binary_search(&bucket).is_ok(),
+                _ => false,
+            };
+            if idle {
+                return Err(ScenarioError::LiveTicketOfIdleOwner { ticket: t.id, owner: t.owner, state: owner_state });
+            }
+
+            let ticket = Ticket {
+                id: t.id,
+                owner: t.owner,
+                bucket,
+                home_bucket,
+                creation_epoch: t.creation_epoch,
+                state: TicketState::Active,
+                retire_requested_epoch: None,
+                retire_effective_epoch: None,
+            };
+            if tickets.insert(t.id, ticket).is_some() {
+                return Err(ScenarioError::DuplicateTicket(t.id));
+            }
+        }
+
+        for ticket in tickets.values() {
+            buckets.get_mut(&ticket.bucket).unwrap().ticket_ids.insert(ticket.id);
+        }
+
+        let active_bucket_ids: BTreeSet<u64> = active_ids.into_iter().collect();
+        Ok(ChainState {
+            validators: validators.into(),
+            ticket_index: TicketIndex::build(tickets.values(), &active_bucket_ids),
+            tickets: tickets.into(),
+            buckets: buckets.into(),
+
+            active_bucket_ids,
+            muted_bucket_ids: layout.muted.iter().copied().collect(),
+            dead_bucket_id,
+            bucket_selection: self.chain.bucket_selection,
+
+            epoch_index: 0,
+            sub_epoch_index: 0,
+            epoch_seed,
+            sub_epoch_seed: epoch_seed,
+            randao_mix: epoch_seed,
+
+            retire_per_epoch_limit: self.chain.retire_per_epoch_limit,
+            retire_schedule: BTreeMap::new(),
+            retire_finalize: BTreeMap::new(),
+            pending_refills: JournaledMap::default(),
+            liveness: JournaledMap::default(),
+
+            protocol: self.protocol.clone(),
+            rewards: self.rewards.clone(),
+            inflation: self.inflation.clone(),
+            slashing: self.slashing,
+            evidence: self.evidence,
+            evidence_pool: EvidencePool::default(),
+            ledger: SupplyLedger {
+                genesis: self.validators.iter().map(|v| v.vault_balance).sum(),
+                ..Default::default()
+            },
+            reward_tally: RewardTally::default(),
+            rewards_paid: JournaledMap::default(),
+        })
+    }
+
+    /// Build a `Simulator` positioned at genesis.
+    pub fn build_simulator(&self) -> Result<Simulator, ScenarioError> {
+        let state = self.build_state()?;
+
+        let clock = SimClock {
+            now_ms: 0,
+            slot_start_ms: 0,
+            slot_index: 0,
+        };
+
+        let mut sim = Simulator::new(clock, state, self.chain.epoch_len_slots);
+        sim.behavior_seed = self.run.seed;
+        if self.run.check_invariants {
+            sim.invariants = Some(Invariants::default());
+        }
+        sim.sub_epoch_len_slots = self.chain.sub_epoch_len_slots;
+        sim.beacon = self.chain.beacon.build();
+        if let Some(auto) = &self.snapshots {
+            auto.validate().map_err(ScenarioError::InvalidSnapshots)?;
+            sim.auto_snapshot = Some(auto.clone());
+        }
+
+        for v in &self.validators {
+            if let Some(spec) = &v.behavior {
+                spec.validate()
+                    .map_err(|reason| ScenarioError::InvalidBehavior { validator: v.id, reason })?;
+                sim.set_behavior(v.id, spec.build());
+            }
+        }
+
+        for (index, event) in self.events.iter().enumerate() {
+            validate_action(&event.action).map_err(|reason| ScenarioError::InvalidEvent { index, reason })?;
+            sim.timeline.push(event.clone());
+        }
+
+        Ok(sim)
+    }
+}
+
+/// Static checks on a scheduled action; runtime checks (unknown validators,
+/// existing tickets) happen when it is applied.
+fn validate_action(action: &Action) -> Result<(), String> {
+    match action {
+        Action::SetParam { name, .. } if !Simulator::PARAM_NAMES.contains(&name.as_str()) => {
+            Err(format!("unknown parameter {:?}", name))
+        }
+        Action::SetParam { name, value: 0 } if name == "epoch_len_slots" || name == "retire_per_epoch_limit" => {
+            Err(format!("{} must be greater than zero", name))
+        }
+        Action::JoinValidator { behavior: Some(spec), .. } | Action::SetBehavior { behavior: spec, .. } => {
+            spec.validate()
+        }
+        _ => Ok(()),
+    }
+}
+
+fn parse_seed(hex: &str) -> Result<[u8; 32], ScenarioError> {
+    let invalid = || ScenarioError::InvalidSeed(hex.to_string());
+
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut seed = [0u8; 32];
+    for (i, byte) in seed.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::BucketIds;
+
+    const BASE: &str = r#"
+        [chain]
+        epoch_seed = "0707070707070707070707070707070707070707070707070707070707070707"
+        epoch_len_slots = 10
+        retire_per_epoch_limit = 2
+
+        [buckets]
+        active = [0]
+        muted = [1]
+        dead = 2
+
+        [[validators]]
+        id = 1
+        vault_balance = 1000
+        initial_bond = 1000
+
+        [[tickets]]
+        id = 1
+        owner = 1
+        bucket = 0
+
+        [run]
+        slots = 20
+    "#;
+
+    #[test]
+    fn builds_valid_scenario() {
+        let scenario = Scenario::from_toml_str(BASE).unwrap();
+        let state = scenario.build_state().unwrap();
+
+        assert_eq!(state.epoch_seed, [7u8; 32]);
+        assert!(state.buckets[&0].ticket_ids.contains(&1));
+        assert_eq!(scenario.run_slots().unwrap(), 20);
+    }
+
+    #[test]
+    fn rejects_ticket_with_unknown_owner() {
+        let text = BASE.replace("owner = 1", "owner = 9");
+        let err = Scenario::from_toml_str(&text).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::UnknownOwner { ticket: 1, owner: 9 }));
+    }
+
+    #[test]
+    fn rejects_bucket_contents_mismatch() {
+        let text = format!("{}\n[[buckets.contents]]\nid = 1\nticket_ids = [1]\n", BASE);
+        let err = Scenario::from_toml_str(&text).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::TicketBucketMismatch { ticket: 1, declared: 0, listed_in: 1 }));
+    }
+
+    #[test]
+    fn rejects_missing_muted_and_dead_buckets() {
+        let no_muted = BASE.replace("muted = [1]", "");
+        let err = Scenario::from_toml_str(&no_muted).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::MissingMutedBucket));
+
+        let no_dead = BASE.replace("dead = 2", "");
+        let err = Scenario::from_toml_str(&no_dead).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::MissingDeadBucket));
+    }
+
+    #[test]
+    fn rejects_zero_retire_limit() {
+        let text = BASE.replace("retire_per_epoch_limit = 2", "retire_per_epoch_limit = 0");
+        let err = Scenario::from_toml_str(&text).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::ZeroRetireLimit));
+    }
+
+    #[test]
+    fn rejects_zero_limits_in_scheduled_actions() {
+        for name in ["epoch_len_slots", "retire_per_epoch_limit"] {
+            let event = format!("[[events]]\nat = {{ slot = 5 }}\naction = {{ kind = \"set_param\", name = \"{}\", value = 0 }}", name);
+            let text = format!("{}\n{}\n", BASE, event);
+            let err = Scenario::from_toml_str(&text).unwrap().build_simulator().err().unwrap();
+            assert!(matches!(err, ScenarioError::InvalidEvent { index: 0, .. }), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_bucket_ranges() {
+
+        for (first, count) in [(u64::MAX, 2), (0, MAX_ACTIVE_BUCKETS + 1)] {
+            let mut scenario = Scenario::from_toml_str(BASE).unwrap();
+            scenario.buckets.active = BucketIds::Range { first, count };
+            let err = scenario.build_state().unwrap_err();
+            assert!(matches!(err, ScenarioError::InvalidBucketRange { .. }), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn rejects_contradictory_genesis_states() {
+        let state = |state: &str| BASE.replace("initial_bond = 1000", &format!("initial_bond = 1000\n{}", state));
+
+        let err = Scenario::from_toml_str(&state("state = \"punished_cooldown\"")).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::MissingCooldown(1)));
+        let cooling = state("state = \"punished_cooldown\"\ncooldown_until_epoch = 2");
+        assert!(Scenario::from_toml_str(&cooling).unwrap().build_state().is_ok());
+        let err = Scenario::from_toml_str(&state("cooldown_until_epoch = 2")).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::UnexpectedCooldown(1)));
+
+        let err = Scenario::from_toml_str(&state("state = \"jailed\"")).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::LiveTicketOfIdleOwner { ticket: 1, owner: 1, state: ValidatorState::Jailed }));
+        let err = Scenario::from_toml_str(&state("state = \"inactive\"")).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::LiveTicketOfIdleOwner { state: ValidatorState::Inactive, .. }));
+        let muted = state("state = \"inactive\"").replace("bucket = 0", "bucket = 1");
+        assert!(Scenario::from_toml_str(&muted).unwrap().build_state().is_ok());
+
+        let text = BASE.replace("bucket = 0", "bucket = 2");
+        let err = Scenario::from_toml_str(&text).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::ActiveTicketInDeadBucket(1)));
+    }
+
+    #[test]
+    fn rejects_invalid_evidence_params() {
+        let text = format!("{}\n[evidence]\nwindow_epochs = {}\n", BASE, u64::MAX / 2);
+        let err = Scenario::from_toml_str(&text).unwrap().build_state().unwrap_err();
+        assert!(matches!(err, ScenarioError::InvalidEvidence(_)));
+    }
+
+    #[test]
+    fn rejects_run_length_overflow() {
+        let text = BASE.replace("slots = 20", &format!("epochs = {}", u64::MAX / 2));
+        let err = Scenario::from_toml_str(&text).unwrap().run_slots().unwrap_err();
+        assert!(matches!(err, ScenarioError::RunLengthOverflow { epoch_len_slots: 10, .. }));
+    }
+}
@@ -0,0 +1,339 @@
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::beacon::randao_next_mix;
+use crate::consensus::evidence::DoubleSignEvidence;
+use crate::consensus::leader_selection::leader_schedule_with_seed;
+use crate::sim::rng::SimRng;
+use crate::state::chain_state::ChainState;
+use crate::types::proposal::Proposal;
+
+/// Offset used for the second, conflicting block of an equivocation.
+const EQUIVOCATION_BLOCK_OFFSET: u64 = 9999;
+
+/// What a behaviour gets to see when deciding what to propose.
+pub struct SlotContext<'a> {
+    pub slot_index: u64,
+    /// `None` when no ACTIVE tickets exist and the protocol produces the block.
+    pub leader: Option<u64>,
+    pub state: &'a ChainState,
+    pub epoch_start_slot: u64,
+    pub epoch_len_slots: u64,
+    /// 0 means one sub-epoch per epoch.
+    pub sub_epoch_len_slots: u64,
+}
+
+impl SlotContext<'_> {
+    pub fn is_last_slot_of_epoch(&self) -> bool {
+        self.slot_index + 1 == self.epoch_start_slot + self.epoch_len_slots
+    }
+}
+
+/// How a single validator acts in a slot.
+///
+/// Behaviours are asked for proposals every slot, whether or not their
+/// validator is the leader; only the leader's proposals are considered by
+/// `process_slot`.
+pub trait ValidatorBehavior {
+    fn proposals(&mut self, validator_id: u64, ctx: &SlotContext<'_>, rng: &mut SimRng) -> Vec<Proposal>;
+
+    /// Whether this validator submits observed double-sign evidence once the
+    /// reporting delay has passed. Everyone reports others' offences by default.
+    fn reports_evidence(&mut self, validator_id: u64, evidence: &DoubleSignEvidence) -> bool {
+        evidence.validator != validator_id
+    }
+
+    /// The spec this behaviour was built from, so a snapshot can rebuild it.
+    /// Behaviours without one cannot be snapshotted.
+    fn spec(&self) -> Option<BehaviorSpec> {
+        None
+    }
+}
+
+/// Always proposes exactly one block when selected.
+pub struct Honest;
+
+/// Never proposes.
+pub struct Offline;
+
+/// Misses its slot with probability `p`.
+pub struct Flaky {
+    pub p: f64,
+}
+
+/// Offline for `outage_slots` out of every `period_slots` slots. `offset`
+/// is kept below `period_slots` so the phase arithmetic cannot overflow.
+pub struct IntermittentOutage {
+    pub period_slots: u64,
+    pub outage_slots: u64,
+    pub offset: u64,
+}
+
+/// Proposes two conflicting blocks with probability `p`.
+pub struct Equivocating {
+    pub p: f64,
+}
+
+/// Strategic "last revealer" against the RANDAO beacon: when it leads the
+/// final slot of an epoch, it compares how many slots it would lead next
+/// epoch with and without its reveal, and withholds (skips the slot) if
+/// that wins it more. Honest otherwise.
+pub struct Withholding;
+
+fn single_block(validator_id: u64, slot_index: u64) -> Vec<Proposal> {
+    vec![Proposal { proposer_id: validator_id, block_id: slot_index }]
+}
+
+fn is_leader(validator_id: u64, ctx: &SlotContext<'_>) -> bool {
+    ctx.leader == Some(validator_id)
+}
+
+impl ValidatorBehavior for Honest {
+    fn proposals(&mut self, validator_id: u64, ctx: &SlotContext<'_>, _rng: &mut SimRng) -> Vec<Proposal> {
+        if !is_leader(validator_id, ctx) {
+            return Vec::new();
+        }
+        single_block(validator_id, ctx.slot_index)
+    }
+
+    fn spec(&self) -> Option<BehaviorSpec> {
+        Some(BehaviorSpec::Honest)
+    }
+}
+
+impl ValidatorBehavior for Offline {
+    fn proposals(&mut self, _validator_id: u64, _ctx: &SlotContext<'_>, _rng: &mut SimRng) -> Vec<Proposal> {
+        Vec::new()
+    }
+
+    fn reports_evidence(&mut self, _validator_id: u64, _evidence: &DoubleSignEvidence) -> bool {
+        false
+    }
+
+    fn spec(&self) -> Option<BehaviorSpec> {
+        Some(BehaviorSpec::Offline)
+    }
+}
+
+impl ValidatorBehavior for Flaky {
+    fn proposals(&mut self, validator_id: u64, ctx: &SlotContext<'_>, rng: &mut SimRng) -> Vec<Proposal> {
+        if !is_leader(validator_id, ctx) || rng.chance(self.p) {
+            return Vec::new();
+        }
+        single_block(validator_id, ctx.slot_index)
+    }
+
+    fn spec(&self) -> Option<BehaviorSpec> {
+        Some(BehaviorSpec::Flaky { p: self.p })
+    }
+}
+
+impl ValidatorBehavior for IntermittentOutage {
+    fn proposals(&mut self, validator_id: u64, ctx: &SlotContext<'_>, _rng: &mut SimRng) -> Vec<Proposal> {
+        let period = self.period_slots;
+        let (slot, offset) = (ctx.slot_index % period, self.offset % period);
+        let phase = if slot >= period - offset { slot - (period - offset) } else { slot + offset };
+        if !is_leader(validator_id, ctx) || phase < self.outage_slots {
+            return Vec::new();
+        }
+        single_block(validator_id, ctx.slot_index)
+    }
+
+    fn spec(&self) -> Option<BehaviorSpec> {
+        Some(BehaviorSpec::IntermittentOutage {
+            period_slots: self.period_slots,
+            outage_slots: self.outage_slots,
+            offset: self.offset,
+        })
+    }
+}
+
+impl ValidatorBehavior for Equivocating {
+    fn proposals(&mut self, validator_id: u64, ctx: &SlotContext<'_>, rng: &mut SimRng) -> Vec<Proposal> {
+        if !is_leader(validator_id, ctx) {
+            return Vec::new();
+        }
+
+        let mut proposals = single_block(validator_id, ctx.slot_index);
+        if rng.chance(self.p) {
+            proposals.push(Proposal {
+                proposer_id: validator_id,
+                block_id: ctx.slot_index + EQUIVOCATION_BLOCK_OFFSET,
+            });
+        }
+        proposals
+    }
+
+    fn spec(&self) -> Option<BehaviorSpec> {
+        Some(BehaviorSpec::Equivocating { p: self.p })
+    }
+}
+
+impl Withholding {
+    /// Slots of the next epoch `validator_id` would lead if its seed were
+    /// `seed`, with sub-epoch seeds derived as in `leader_schedule`.
+    fn next_epoch_slots_led(validator_id: u64, ctx: &SlotContext<'_>, seed: [u8; 32]) -> usize {
+        let next_start = ctx.epoch_start_slot + ctx.epoch_len_slots;
+        leader_schedule_with_seed(ctx.state, seed, next_start, ctx.epoch_len_slots, ctx.sub_epoch_len_slots)
+            .map(|schedule| schedule.iter().filter(|&&(_, leader)| leader == Some(validator_id)).count())
+            .unwrap_or(0)
+    }
+}
+
+impl ValidatorBehavior for Withholding {
+    fn proposals(&mut self, validator_id: u64, ctx: &SlotContext<'_>, _rng: &mut SimRng) -> Vec<Proposal> {
+        if !is_leader(validator_id, ctx) {
+            return Vec::new();
+        }
+
+        if ctx.is_last_slot_of_epoch() {
+            let mix = ctx.state.randao_mix;
+            let revealed = randao_next_mix(mix, ctx.slot_index, Some(validator_id));
+            let withheld = randao_next_mix(mix, ctx.slot_index, None);
+
+            if Self::next_epoch_slots_led(validator_id, ctx, withheld)
+                > Self::next_epoch_slots_led(validator_id, ctx, revealed)
+            {
+                return Vec::new();
+            }
+        }
+
+        single_block(validator_id, ctx.slot_index)
+    }
+
+    fn spec(&self) -> Option<BehaviorSpec> {
+        Some(BehaviorSpec::Withholding)
+    }
+}
+
+/// Serialisable description of a built-in behaviour, as used in scenarios.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BehaviorSpec {
+    Honest,
+    Offline,
+    Flaky { p: f64 },
+    IntermittentOutage {
+        period_slots: u64,
+        outage_slots: u64,
+        #[serde(default)]
+        offset: u64,
+    },
+    Equivocating { p: f64 },
+    Withholding,
+}
+
+impl BehaviorSpec {
+    /// Reject parameters the behaviour cannot act on.
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            BehaviorSpec::Flaky { p } | BehaviorSpec::Equivocating { p } if !(0.0..=1.0).contains(&p) => {
+                Err(format!("probability {} is outside [0, 1]", p))
+            }
+            BehaviorSpec::IntermittentOutage { period_slots: 0, .. } => {
+                Err("period_slots must be greater than zero".to_string())
+            }
+            BehaviorSpec::IntermittentOutage { period_slots, outage_slots, .. } if outage_slots > period_slots => {
+                Err(format!("outage_slots {} exceeds period_slots {}", outage_slots, period_slots))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn ValidatorBehavior> {
+        match *self {
+            BehaviorSpec::Honest => Box::new(Honest),
+            BehaviorSpec::Offline => Box::new(Offline),
+            BehaviorSpec::Flaky { p } => Box::new(Flaky { p }),
+            BehaviorSpec::IntermittentOutage { period_slots, outage_slots, offset } => {
+                Box::new(IntermittentOutage { period_slots, outage_slots, offset: offset % period_slots })
+            }
+            BehaviorSpec::Equivocating { p } => Box::new(Equivocating { p }),
+            BehaviorSpec::Withholding => Box::new(Withholding),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::leader_selection::leader_schedule;
+    use crate::scenario::config::Scenario;
+
+    fn state() -> ChainState {
+        Scenario::load("scenarios/default.toml").unwrap().build_state().unwrap()
+    }
+
+    fn context(slot_index: u64, leader: Option<u64>, state: &ChainState) -> SlotContext<'_> {
+        SlotContext { slot_index, leader, state, epoch_start_slot: 0, epoch_len_slots: 10, sub_epoch_len_slots: 0 }
+    }
+
+    #[test]
+    fn only_the_leader_proposes() {
+        let state = state();
+        let ctx = context(3, Some(2), &state);
+        let mut rng = SimRng::new(0);
+
+        assert!(Honest.proposals(1, &ctx, &mut rng).is_empty());
+        assert_eq!(Honest.proposals(2, &ctx, &mut rng).len(), 1);
+    }
+
+    #[test]
+    fn outage_window_follows_period() {
+        let state = state();
+        let mut outage = IntermittentOutage { period_slots: 10, outage_slots: 3, offset: 0 };
+        let mut rng = SimRng::new(0);
+
+        let offline: Vec<u64> = (0..10)
+            .filter(|&slot| {
+                let ctx = context(slot, Some(1), &state);
+                outage.proposals(1, &ctx, &mut rng).is_empty()
+            })
+            .collect();
+        assert_eq!(offline, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn large_offsets_wrap_within_the_period() {
+        let state = state();
+        let spec = BehaviorSpec::IntermittentOutage { period_slots: 10, outage_slots: 3, offset: u64::MAX };
+        let mut outage = spec.build();
+        let mut rng = SimRng::new(0);
+
+        // u64::MAX % 10 == 5, so the outage covers phases 0..3 at slots 5..8.
+        let offline: Vec<u64> = [0, 5, 6, 7, 8, u64::MAX - 1]
+            .into_iter()
+            .filter(|&slot| {
+                let ctx = context(slot, Some(1), &state);
+                outage.proposals(1, &ctx, &mut rng).is_empty()
+            })
+            .collect();
+        assert_eq!(offline, vec![5, 6, 7]);
+        assert_eq!(outage.spec(), Some(BehaviorSpec::IntermittentOutage { period_slots: 10, outage_slots: 3, offset: 5 }));
+    }
+
+    #[test]
+    fn withholding_forecast_follows_sub_epoch_seeds() {
+        let state = Scenario::load("scenarios/full_layout.toml").unwrap().build_state().unwrap();
+        let ctx = SlotContext { sub_epoch_len_slots: 4, epoch_len_slots: 32, ..context(31, Some(1), &state) };
+        let seed = randao_next_mix(state.randao_mix, 31, Some(1));
+
+        let mut next = state.clone();
+        next.epoch_seed = seed;
+        let schedule = leader_schedule(&next, 32, 32, 4).unwrap();
+        assert_ne!(schedule, leader_schedule(&next, 32, 32, 0).unwrap(), "sub-epochs change the leaders");
+        for validator in next.validators.keys() {
+            let led = schedule.iter().filter(|&&(_, leader)| leader == Some(*validator)).count();
+            assert_eq!(Withholding::next_epoch_slots_led(*validator, &ctx, seed), led, "validator {}", validator);
+        }
+    }
+
+    #[test]
+    fn equivocation_probability_extremes() {
+        let state = state();
+        let ctx = context(5, Some(1), &state);
+        let mut rng = SimRng::new(42);
+
+        assert_eq!(Equivocating { p: 1.0 }.proposals(1, &ctx, &mut rng).len(), 2);
+        assert_eq!(Equivocating { p: 0.0 }.proposals(1, &ctx, &mut rng).len(), 1);
+    }
+}
@@ -1,2 +1,8 @@
 pub mod clock;
+pub mod rng;
+pub mod behavior;
+pub mod timeline;
+pub mod sink;
 pub mod simulator;
+pub mod snapshot;
+pub mod recording;
@@ -0,0 +1,328 @@
+//! Record a run's inputs and replay them to check that they still produce
+//! the same chain.
+//!
+//! A `Recording` holds the scenario and genesis state of a run and, for each
+//! slot, everything the slot took from outside the protocol (`SlotInput`)
+//! together with the block and state changes it produced. Replaying feeds
+//! the inputs back through `process_slot` and the epoch / sub-epoch
+//! transitions, with no behaviours or timeline involved, and stops at the
+//! first block that differs.
+use std::fmt;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SimError;
+use crate::scenario::config::Scenario;
+use crate::sim::timeline::{Action, Timeline};
+use crate::state::chain_state::ChainState;
+use crate::state::diff::StateDiff;
+use crate::types::block::Block;
+use crate::types::proposal::Proposal;
+
+/// Format version written into every recording.
+pub const RECORDING_VERSION: u32 = 3;
+
+/// Evidence submitted by a validator's behaviour during a slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceReport {
+    pub validator: u64,
+    pub slot: u64,
+    pub reporter: u64,
+}
+
+/// Everything a slot took from outside the protocol. With the state before
+/// it, this determines the slot.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlotInput {
+    pub slot: u64,
+    /// Scheduled actions applied at the start of the slot, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<Action>,
+    /// The proposal set passed to `process_slot`.
+    pub proposals: Vec<Proposal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<EvidenceReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedSlot {
+    pub input: SlotInput,
+    pub block: Block,
+    /// What the slot, including any boundary it closed, changed.
+    pub changes: StateDiff,
+}
+
+/// The slot a recorded run failed at: the inputs it had taken when it
+/// failed, and the error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedSlot {
+    pub input: SlotInput,
+    pub error: String,
+}
+
+/// A run from genesis, slot by slot. Attach one to `Simulator::recording`
+/// before the first slot and take it back when the run is over.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    pub version: u32,
+    /// The scenario the run was built from, with its protocol, reward,
+    /// slashing and evidence parameters.
+    pub scenario: Scenario,
+    pub genesis: ChainState,
+    pub slots: Vec<RecordedSlot>,
+    /// Set when the last slot attempted failed; the simulator was rolled
+    /// back, so `slots` ends before it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<FailedSlot>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(String, std::io::Error),
+    Parse(String),
+    UnsupportedVersion(u32),
+    /// The recorded scenario no longer builds a simulator.
+    Scenario(String),
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(path, e) => write!(f, "{}: {}", path, e),
+            RecordingError::Parse(msg) => write!(f, "parse error: {}", msg),
+            RecordingError::UnsupportedVersion(v) => {
+                write!(f, "recording version {} is not supported (expected {})", v, RECORDING_VERSION)
+            }
+            RecordingError::Scenario(msg) => write!(f, "invalid recorded scenario: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+/// Only the fields that tell a recording apart, read before the rest.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+    #[allow(dead_code)]
+    scenario: serde::de::IgnoredAny,
+}
+
+/// The first slot at which a replay produced a different block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Divergence {
+    pub slot: u64,
+    pub expected: Block,
+    pub actual: Block,
+    /// From the recorded state after the slot to the replayed one. Empty
+    /// when the states agree and only the blocks differ.
+    pub diff: StateDiff,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ReplayOutcome {
+    /// Every recorded block was reproduced.
+    Matched { slots: u64 },
+    /// The scenario now builds a different genesis state; no slot was replayed.
+    GenesisDiverged { diff: StateDiff },
+    Diverged(Box<Divergence>),
+    /// A slot that succeeded when recorded failed on replay.
+    Failed { slot: u64, error: String },
+    /// Every recorded block was reproduced, and the slot the recorded run
+    /// failed at failed again with the same error.
+    FailureReproduced { slots: u64, slot: u64, error: String },
+    /// The slot the recorded run failed at succeeded on replay
+    /// (`actual: None`) or failed differently.
+    FailureNotReproduced { slot: u64, expected: String, actual: Option<String> },
+}
+
+impl fmt::Display for ReplayOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayOutcome::Matched { slots } => write!(f, "replay matched all {} slots", slots),
+            ReplayOutcome::GenesisDiverged { diff } => write!(f, "genesis state differs from the recording:\n{}", diff.to_string().trim_end()),
+            ReplayOutcome::Diverged(d) => {
+                let root = |block: &Block| block.state_root.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+                writeln!(f, "replay diverged at slot {}", d.slot)?;
+                writeln!(f, "  recorded: proposer {:?}, state root {}", d.expected.proposer, root(&d.expected))?;
+                writeln!(f, "  replayed: proposer {:?}, state root {}", d.actual.proposer, root(&d.actual))?;
+                if d.diff.is_empty() {
+                    write!(f, "  no difference in chain state")
+                } else {
+                    write!(f, "recorded -> replayed state:\n{}", d.diff.to_string().trim_end())
+                }
+            }
+            ReplayOutcome::Failed { slot, error } => write!(f, "slot {} failed on replay: {}", slot, error),
+            ReplayOutcome::FailureReproduced { slots, slot, error } => {
+                write!(f, "replay matched all {} slots, then slot {} failed as recorded: {}", slots, slot, error)
+            }
+            ReplayOutcome::FailureNotReproduced { slot, expected, actual: None } => {
+                write!(f, "slot {} failed when recorded ({}) but succeeded on replay", slot, expected)
+            }
+            ReplayOutcome::FailureNotReproduced { slot, expected, actual: Some(actual) } => {
+                write!(f, "slot {} failed when recorded ({}) but failed differently on replay: {}", slot, expected, actual)
+            }
+        }
+    }
+}
+
+impl Recording {
+    /// An empty recording of a run of `scenario` starting from `genesis`.
+    pub fn new(scenario: Scenario, genesis: ChainState) -> Recording {
+        Recording { version: RECORDING_VERSION, scenario, genesis, slots: Vec::new(), failure: None }
+    }
+
+    /// Write as JSON, through a temporary file as `Snapshot::save` does.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        let path = path.as_ref();
+        let io_error = |e| RecordingError::Io(path.display().to_string(), e);
+
+        let tmp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(fs::File::create(&tmp).map_err(io_error)?);
+        serde_json::to_writer(&mut writer, self).map_err(|e| RecordingError::Parse(e.to_string()))?;
+        writer.flush().map_err(io_error)?;
+        drop(writer);
+        fs::rename(&tmp, path).map_err(io_error)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Recording, RecordingError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| RecordingError::Io(path.display().to_string(), e))?;
+        Recording::from_json(&text)
+    }
+
+    pub fn from_json(text: &str) -> Result<Recording, RecordingError> {
+        let header: Header = serde_json::from_str(text).map_err(|e| RecordingError::Parse(e.to_string()))?;
+        if header.version != RECORDING_VERSION {
+            return Err(RecordingError::UnsupportedVersion(header.version));
+        }
+        let mut recording: Recording = serde_json::from_str(text).map_err(|e| RecordingError::Parse(e.to_string()))?;
+        recording.genesis.rebuild_ticket_index();
+        Ok(recording)
+    }
+
+    /// Whether `text` looks like a recording rather than some other JSON file.
+    pub fn is_recording(text: &str) -> bool {
+        serde_json::from_str::<Header>(text).is_ok()
+    }
+
+    /// Re-run the recorded inputs on the recorded scenario and compare every
+    /// block with the recorded one, stopping at the first difference. A
+    /// recorded failure must then happen again, with the same error.
+    pub fn replay(&self) -> Result<ReplayOutcome, RecordingError> {
+        let mut sim = self.scenario.build_simulator().map_err(|e| RecordingError::Scenario(e.to_string()))?;
+        sim.timeline = Timeline::default();
+        sim.behaviors.clear();
+        sim.auto_snapshot = None;
+
+        if sim.state != self.genesis {
+            return Ok(ReplayOutcome::GenesisDiverged { diff: StateDiff::between(&self.genesis, &sim.state) });
+        }
+
+        // Record the replay too, for what each slot changed on a divergence.
+        sim.recording = Some(Recording::new(self.scenario.clone(), self.genesis.clone()));
+        for recorded in &self.slots {
+            let block = match sim.replay_slot(&recorded.input) {
+                Ok(block) => block,
+                Err(e) => return Ok(ReplayOutcome::Failed { slot: recorded.input.slot, error: e.to_string() }),
+            };
+            let replayed = sim.recording.as_mut().and_then(|r| r.slots.pop());
+            if block != recorded.block {
+                let parse = |e: SimError| RecordingError::Parse(e.to_string());
+                let mut expected = sim.state.clone();
+                if let Some(replayed) = replayed {
+                    replayed.changes.revert(&mut expected).map_err(parse)?;
+                }
+                recorded.changes.apply(&mut expected).map_err(parse)?;
+                return Ok(ReplayOutcome::Diverged(Box::new(Divergence {
+                    slot: recorded.input.slot,
+                    expected: recorded.block.clone(),
+                    actual: block,
+                    diff: StateDiff::between(&expected, &sim.state),
+                })));
+            }
+        }
+        let slots = self.slots.len() as u64;
+        let Some(failure) = &self.failure else {
+            return Ok(ReplayOutcome::Matched { slots });
+        };
+        let slot = failure.input.slot;
+        Ok(match sim.replay_slot(&failure.input) {
+            Err(e) if e.to_string() == failure.error => ReplayOutcome::FailureReproduced { slots, slot, error: e.to_string() },
+            result => ReplayOutcome::FailureNotReproduced {
+                slot,
+                expected: failure.error.clone(),
+                actual: result.err().map(|e| e.to_string()),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SimError;
+
+    fn record(path: &str, slots: u64) -> Recording {
+        let scenario = Scenario::load(path).unwrap();
+        let mut sim = scenario.build_simulator().unwrap();
+        sim.recording = Some(Recording::new(scenario, sim.state.clone()));
+        for _ in 0..slots {
+            sim.run_one_slot().unwrap();
+        }
+        sim.recording.take().unwrap()
+    }
+
+    #[test]
+    fn recorded_runs_replay_exactly() {
+        for path in ["scenarios/default.toml", "scenarios/interventions.toml", "scenarios/randao_bias.toml"] {
+            let recording = record(path, 200);
+            assert_eq!(recording.slots.len(), 200);
+            let json = serde_json::to_string(&recording).unwrap();
+            let loaded = Recording::from_json(&json).unwrap();
+            assert_eq!(loaded.replay().unwrap(), ReplayOutcome::Matched { slots: 200 }, "{}", path);
+        }
+    }
+
+    #[test]
+    fn replay_stops_at_the_first_divergence_with_a_diff() {
+        let mut recording = record("scenarios/default.toml", 100);
+        let leader = recording.slots[40].block.proposer.unwrap();
+        recording.slots[40].input.proposals.retain(|p| p.proposer_id != leader);
+
+        let ReplayOutcome::Diverged(divergence) = recording.replay().unwrap() else {
+            panic!("the missing proposal changes slot 40");
+        };
+        assert_eq!(divergence.slot, 40);
+        assert_eq!(divergence.actual.proposer, None);
+        let change = divergence.diff.validators.iter().find(|c| c.id == leader).unwrap();
+        assert_eq!(change.after.as_ref().unwrap().miss_counter, change.before.as_ref().unwrap().miss_counter + 1);
+        assert!(divergence.diff.entries.iter().any(|c| c.field == "liveness"), "{}", divergence.diff);
+
+        recording.version += 1;
+        let json = serde_json::to_string(&recording).unwrap();
+        assert!(matches!(Recording::from_json(&json), Err(RecordingError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    fn replay_repeats_a_recorded_failure() {
+        let mut recording = record("scenarios/default.toml", 50);
+        let mut input = recording.slots.pop().unwrap().input;
+        input.evidence.push(EvidenceReport { validator: 2, slot: 0, reporter: 1 });
+        let error = SimError::UnknownEvidence { validator: 2, slot: 0 }.to_string();
+        recording.failure = Some(FailedSlot { input: input.clone(), error: error.clone() });
+
+        let json = serde_json::to_string(&recording).unwrap();
+        let loaded = Recording::from_json(&json).unwrap();
+        assert_eq!(loaded.replay().unwrap(), ReplayOutcome::FailureReproduced { slots: 49, slot: 49, error: error.clone() });
+
+        input.evidence.clear();
+        recording.failure = Some(FailedSlot { input, error: error.clone() });
+        let outcome = ReplayOutcome::FailureNotReproduced { slot: 49, expected: error, actual: None };
+        assert_eq!(recording.replay().unwrap(), outcome);
+    }
+}
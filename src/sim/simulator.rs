@@ -1,47 +1,802 @@
-use crate::state::chain_state::ChainState;
+use std::collections::BTreeMap;
+
+use crate::error::SimError;
+use crate::state::chain_state::{ChainState, StateCheckpoint};
+use crate::state::diff::StateDiff;
+use crate::state::invariants::{Invariants, Violation};
+use crate::state::journal::JournaledMap;
+use crate::state::merkle::StateTree;
 use crate::types::block::Block;
 use crate::sim::clock::SimClock;
-use crate::consensus::slot::process_slot;
+use crate::sim::recording::{EvidenceReport, FailedSlot, RecordedSlot, Recording, SlotInput};
+use crate::sim::behavior::{Honest, SlotContext, ValidatorBehavior};
+use crate::sim::rng::SimRng;
+use crate::sim::sink::EventSink;
+use crate::sim::snapshot::{AutoSnapshot, Snapshot, SnapshotError, SNAPSHOT_VERSION};
+use crate::sim::timeline::{Action, AppliedEvent, Timeline};
+use crate::state::bucket_ops::issue_ticket;
+use crate::state::retirement_ops::request_ticket_retire;
+use crate::state::validator_ops::{add_validator, jail_validator, on_vault_refill, queue_vault_refill};
+use crate::types::event::{EventRecord, SimEvent};
+use crate::consensus::slot::{process_slot, slot_leader};
 use crate::consensus::epoch::process_epoch_transition;
+use crate::consensus::beacon::{RandomnessBeacon, StaticBeacon};
+use crate::consensus::evidence::{include_evidence, submit_evidence, DoubleSignEvidence};
+use crate::consensus::sub_epoch::process_sub_epoch_transition;
 use crate::types::proposal::Proposal;
+use crate::types::validator::ValidatorState;
 
 pub struct Simulator {
     pub clock: SimClock,
     pub state: ChainState,
     pub blocks: Vec<Block>,
     pub epoch_len_slots: u64,
+    /// Slot at which the current epoch started.
+    pub epoch_start_slot: u64,
+    /// Sub-epoch length within an epoch; 0 means one sub-epoch per epoch.
+    pub sub_epoch_len_slots: u64,
+
+    /// Seed for behaviour randomness; each validator draws from its own stream.
+    pub behavior_seed: u64,
+    /// Validators without an entry behave honestly.
+    pub behaviors: BTreeMap<u64, Box<dyn ValidatorBehavior>>,
+    /// Each validator's behaviour stream once it has drawn from it; journaled
+    /// so a slot checkpoint does not copy every stream.
+    behavior_rngs: JournaledMap<SimRng>,
+
+    /// Derives each new epoch seed; static (genesis seed forever) by default.
+    pub beacon: Box<dyn RandomnessBeacon>,
+
+    /// Scheduled interventions not yet applied.
+    pub timeline: Timeline,
+    /// Every scheduled intervention applied so far, in order.
+    pub applied_events: Vec<AppliedEvent>,
+
+    /// When set, checked after every slot and every epoch / sub-epoch transition;
+    /// a violation fails the slot with `SimError::InvariantViolation`.
+    pub invariants: Option<Invariants>,
+
+    /// When set, a snapshot is written before the first slot of every due epoch.
+    pub auto_snapshot: Option<AutoSnapshot>,
+
+    /// When set, the inputs, block and state changes of every slot that
+    /// succeeds are appended to it.
+    pub recording: Option<Recording>,
+
+    /// Merkle trees of `state` as of the last sealed block, updated from the
+    /// record maps' dirty keys rather than rebuilt every slot.
+    tree: StateTree,
+    sinks: Vec<Box<dyn EventSink>>,
+    /// Events of the slot in progress; delivered to sinks once the slot succeeds.
+    pending_records: Vec<EventRecord>,
+    /// Behaviours replaced by scheduled actions during the slot in progress.
+    behavior_undo: Vec<(u64, Option<Box<dyn ValidatorBehavior>>)>,
+}
+
+/// Everything `run_one_slot` may change, captured before the slot starts.
+struct Checkpoint {
+    clock: SimClock,
+    state: StateCheckpoint,
+    blocks_len: usize,
+    epoch_len_slots: u64,
+    epoch_start_slot: u64,
+    sub_epoch_len_slots: u64,
+    behavior_rngs: usize,
+    timeline: Timeline,
+    applied_events_len: usize,
 }
 
 impl Simulator {
-    pub fn run_one_slot(&mut self) -> Block {
+    pub fn new(clock: SimClock, state: ChainState, epoch_len_slots: u64) -> Self {
+        Simulator {
+            clock,
+            state,
+            blocks: Vec::new(),
+            epoch_len_slots,
+            epoch_start_slot: 0,
+            sub_epoch_len_slots: 0,
+            behavior_seed: 0,
+            behaviors: BTreeMap::new(),
+            behavior_rngs: JournaledMap::default(),
+            beacon: Box::new(StaticBeacon),
+            timeline: Timeline::default(),
+            applied_events: Vec::new(),
+            invariants: None,
+            auto_snapshot: None,
+            recording: None,
+            tree: StateTree::default(),
+            sinks: Vec::new(),
+            pending_records: Vec::new(),
+            behavior_undo: Vec::new(),
+        }
+    }
+
+    pub fn add_sink(&mut self, sink: Box<dyn EventSink>) {
+        self.sinks.push(sink);
+    }
+
+    pub fn flush_sinks(&mut self) {
+        for sink in &mut self.sinks {
+            sink.flush();
+        }
+    }
+
+    /// Stamp buffered events with `slot` and the current epoch and queue them
+    /// for the sinks.
+    fn emit(&mut self, slot: u64, events: &[SimEvent]) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let epoch = self.state.epoch_index;
+        let sub_epoch = self.state.sub_epoch_index;
+        self.pending_records.extend(
+            events.iter().map(|event| EventRecord { slot, epoch, sub_epoch, event: event.clone() }),
+        );
+    }
+
+    /// Run the enabled invariants on the current state, reached from `start`
+    /// (the start of `slot`) through `events`.
+    fn check_invariants(&self, start: &StateCheckpoint, events: &[SimEvent], slot: u64) -> Result<(), SimError> {
+        let Some(invariants) = &self.invariants else { return Ok(()); };
+
+        let changes = StateDiff::since(start, &self.state);
+        invariants.check(&self.state, &changes, events).map_err(|(invariant, detail)| {
+            SimError::InvariantViolation(Box::new(Violation {
+                invariant,
+                slot,
+                epoch: self.state.epoch_index,
+                detail,
+                diff: changes,
+            }))
+        })
+    }
+
+    fn deliver_pending(&mut self) {
+        for record in std::mem::take(&mut self.pending_records) {
+            for sink in &mut self.sinks {
+                sink.emit(&record);
+            }
+        }
+    }
+
+    pub fn set_behavior(&mut self, validator_id: u64, behavior: Box<dyn ValidatorBehavior>) {
+        self.behaviors.insert(validator_id, behavior);
+    }
+
+    /// `set_behavior` from within a slot, undone if the slot fails.
+    fn replace_behavior(&mut self, validator_id: u64, behavior: Box<dyn ValidatorBehavior>) {
+        let previous = self.behaviors.insert(validator_id, behavior);
+        self.behavior_undo.push((validator_id, previous));
+    }
+
+    /// Names accepted by `set_param` and `Action::SetParam`.
+    pub const PARAM_NAMES: &'static [&'static str] =
+        &["epoch_len_slots", "sub_epoch_len_slots", "retire_per_epoch_limit"];
+
+    /// Change a run parameter by name.
+    pub fn set_param(&mut self, name: &str, value: u64) -> Result<(), SimError> {
+        match name {
+            "epoch_len_slots" | "retire_per_epoch_limit" if value == 0 => Err(SimError::InvalidParam {
+                name: name.to_string(),
+                reason: "must be greater than zero".to_string(),
+            }),
+            "epoch_len_slots" => {
+                self.epoch_len_slots = value;
+                Ok(())
+            }
+            "sub_epoch_len_slots" => {
+                self.sub_epoch_len_slots = value;
+                Ok(())
+            }
+            "retire_per_epoch_limit" => {
+                self.state.retire_per_epoch_limit = value;
+                Ok(())
+            }
+            _ => Err(SimError::UnknownParam(name.to_string())),
+        }
+    }
+
+    /// Apply one scheduled action. May leave state partially changed on error;
+    /// `apply_actions` rolls it back.
+    fn apply_action(&mut self, action: &Action, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+        match action {
+            Action::VaultRefill { validator, amount } => {
+                on_vault_refill(&mut self.state, *validator, *amount, events)?;
+            }
+            Action::QueueVaultRefill { validator, amount } => {
+                queue_vault_refill(&mut self.state, *validator, *amount, events)?;
+            }
+            Action::RetireTickets { validator, ticket_ids } => {
+                request_ticket_retire(&mut self.state, *validator, ticket_ids.clone(), events)?;
+            }
+            Action::JoinValidator { validator, vault_balance, initial_bond, ticket_ids, behavior } => {
+                add_validator(&mut self.state, *validator, *vault_balance, *initial_bond, events)?;
+                for &tid in ticket_ids {
+                    issue_ticket(&mut self.state, tid, *validator, events)?;
+                }
+                if let Some(spec) = behavior {
+                    self.replace_behavior(*validator, spec.build());
+                }
+            }
+            Action::IssueTickets { owner, ticket_ids } => {
+                for &tid in ticket_ids {
+                    issue_ticket(&mut self.state, tid, *owner, events)?;
+                }
+            }
+            Action::ForceJail { validator } => {
+                jail_validator(&mut self.state, *validator, events)?;
+            }
+            Action::SetParam { name, value } => self.set_param(name, *value)?,
+            Action::SetBehavior { validator, behavior } => {
+                if !self.state.validators.contains_key(validator) {
+                    return Err(SimError::UnknownValidator(*validator));
+                }
+                self.replace_behavior(*validator, behavior.build());
+            }
+            Action::SubmitEvidence { validator, slot, reporter } => {
+                submit_evidence(&mut self.state, *validator, *slot, *reporter, events)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Scheduled actions due at the current slot, removed from the timeline.
+    fn take_due_actions(&mut self, slot_index: u64) -> Vec<Action> {
+        if self.timeline.is_empty() {
+            return Vec::new();
+        }
+        self.timeline.take_due(slot_index, self.state.epoch_index)
+    }
+
+    /// Apply the slot's scheduled actions in order, recording each one.
+    /// A rejected action is recorded with its error and leaves state untouched.
+    fn apply_actions(&mut self, slot_index: u64, actions: &[Action], events: &mut Vec<SimEvent>) {
+        for action in actions.iter().cloned() {
+            let epoch = self.state.epoch_index;
+            let before = self.state.checkpoint();
+            let mut effects = Vec::new();
+            let error = match self.apply_action(&action, &mut effects) {
+                Ok(()) => None,
+                Err(e) => {
+                    self.state.rollback(before);
+                    effects.clear();
+                    Some(e.to_string())
+                }
+            };
+
+            events.push(SimEvent::ScheduledAction { action: action.clone(), error: error.clone() });
+            events.extend(effects);
+            self.applied_events.push(AppliedEvent { slot: slot_index, epoch, action, error });
+        }
+    }
+
+    /// Ask every validator's behaviour for its proposals this slot.
+    fn gather_proposals(&mut self, slot_index: u64, leader: Option<u64>) -> Vec<Proposal> {
+        let ctx = SlotContext {
+            slot_index,
+            leader,
+            state: &self.state,
+            epoch_start_slot: self.epoch_start_slot,
+            epoch_len_slots: self.epoch_len_slots,
+            sub_epoch_len_slots: self.sub_epoch_len_slots,
+        };
+
+        let validator_ids: Vec<u64> = self.state.validators.keys().copied().collect();
+
+        let mut proposals = Vec::new();
+        for vid in validator_ids {
+            let seed = self.behavior_seed;
+            let drawn = self.behavior_rngs.get(&vid).cloned();
+            let mut rng = drawn.clone().unwrap_or_else(|| SimRng::derive(seed, vid));
+
+            match self.behaviors.get_mut(&vid) {
+                Some(behavior) => proposals.extend(behavior.proposals(vid, &ctx, &mut rng)),
+                None => proposals.extend(Honest.proposals(vid, &ctx, &mut rng)),
+            }
+            if drawn.as_ref() != Some(&rng) {
+                self.behavior_rngs.insert(vid, rng);
+            }
+        }
+        proposals
+    }
+
+    /// Submit observed evidence whose reporting delay has passed. Each offence
+    /// is reported by the lowest-id Active validator whose behaviour reports it.
+    fn report_evidence(&mut self, slot_index: u64, events: &mut Vec<SimEvent>) -> Result<Vec<EvidenceReport>, SimError> {
+        let params = self.state.evidence;
+        if !params.auto_report {
+            return Ok(Vec::new());
+        }
+
+        let due: Vec<_> = self
+            .state
+            .evidence_pool
+            .observed
+            .values()
+            .filter(|e| e.slot.saturating_add(params.report_delay_slots) <= slot_index)
+            .copied()
+            .collect();
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut reports = Vec::new();
+        for evidence in due {
+            if let Some(reporter) = choose_reporter(&mut self.behaviors, &self.state, &evidence) {
+                let report = EvidenceReport { validator: evidence.validator, slot: evidence.slot, reporter };
+                self.submit_report(&report, events)?;
+                reports.push(report);
+            }
+        }
+        Ok(reports)
+    }
+
+    fn submit_report(&mut self, report: &EvidenceReport, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+        submit_evidence(&mut self.state, report.validator, report.slot, Some(report.reporter), events)
+    }
+
+    /// Capture the simulator between slots. Fails if a behaviour or the
+    /// beacon was not built from a spec.
+    pub fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        let behaviors = self
+            .behaviors
+            .iter()
+            .map(|(&vid, behavior)| behavior.spec().map(|spec| (vid, spec)).ok_or(SnapshotError::UnsupportedBehavior(vid)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Snapshot {
+            version: SNAPSHOT_VERSION,
+            clock: self.clock.clone(),
+            state: self.state.clone(),
+            blocks: self.blocks.clone(),
+            epoch_len_slots: self.epoch_len_slots,
+            epoch_start_slot: self.epoch_start_slot,
+            sub_epoch_len_slots: self.sub_epoch_len_slots,
+            behavior_seed: self.behavior_seed,
+            behaviors,
+            behavior_rngs: self.behavior_rngs.iter().map(|(&vid, rng)| (vid, rng.clone())).collect(),
+            beacon: self.beacon.spec().ok_or(SnapshotError::UnsupportedBeacon)?,
+            timeline: self.timeline.clone(),
+            applied_events: self.applied_events.clone(),
+            invariants: self.invariants.clone(),
+            auto_snapshot: self.auto_snapshot.clone(),
+        })
+    }
+
+    /// Resume a run from `snapshot`. The result runs exactly as the
+    /// simulator it was taken from would have; add sinks again as needed.
+    pub fn from_snapshot(snapshot: Snapshot) -> Result<Simulator, SnapshotError> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(snapshot.version));
+        }
+
+        let mut sim = Simulator::new(snapshot.clock, snapshot.state, snapshot.epoch_len_slots);
+        sim.blocks = snapshot.blocks;
+        sim.epoch_start_slot = snapshot.epoch_start_slot;
+        sim.sub_epoch_len_slots = snapshot.sub_epoch_len_slots;
+        sim.behavior_seed = snapshot.behavior_seed;
+        sim.behaviors = snapshot.behaviors.iter().map(|(&vid, spec)| (vid, spec.build())).collect();
+        sim.behavior_rngs = snapshot.behavior_rngs.into();
+        sim.beacon = snapshot.beacon.build();
+        sim.timeline = snapshot.timeline;
+        sim.applied_events = snapshot.applied_events;
+        sim.invariants = snapshot.invariants;
+        sim.auto_snapshot = snapshot.auto_snapshot;
+        Ok(sim)
+    }
+
+    /// Write the automatic snapshot if the next slot starts a due epoch.
+    fn write_auto_snapshot(&self) -> Result<(), SimError> {
+        let Some(auto) = &self.auto_snapshot else { return Ok(()); };
+        if self.clock.slot_index != self.epoch_start_slot || !auto.is_due(self.state.epoch_index) {
+            return Ok(());
+        }
+
+        self.snapshot()
+            .and_then(|snapshot| auto.write(self.state.epoch_index, &snapshot))
+            .map(|_| ())
+            .map_err(|e| SimError::Snapshot(e.to_string()))
+    }
+
+    /// Run one slot and its epoch / sub-epoch boundary, if any.
+    ///
+    /// On error the simulator is put back exactly as it was before the slot
+    /// (chain state, clock, timeline, behaviours and their RNG streams) and no
+    /// events reach the sinks. Beacons are assumed to keep their state in
+    /// `ChainState`, as the built-in ones do.
+    pub fn run_one_slot(&mut self) -> Result<Block, SimError> {
+        self.write_auto_snapshot()?;
+        self.run_slot(None)
+    }
+
+    /// Run the next slot on recorded inputs: `input`'s actions, proposals and
+    /// evidence reports replace the timeline and the behaviours. Otherwise
+    /// as `run_one_slot`.
+    pub fn replay_slot(&mut self, input: &SlotInput) -> Result<Block, SimError> {
+        self.run_slot(Some(input))
+    }
+
+    fn run_slot(&mut self, replay: Option<&SlotInput>) -> Result<Block, SimError> {
+        // Only this slot's changes need to be undone or diffed.
+        self.state.commit();
+        self.behavior_rngs.commit();
+        let checkpoint = self.checkpoint();
+
+        let mut input = SlotInput { slot: self.clock.slot_index, ..SlotInput::default() };
+        match self.step(&checkpoint.state, replay, &mut input) {
+            Ok(block) => {
+                self.behavior_undo.clear();
+                self.deliver_pending();
+                if let Some(recording) = &mut self.recording {
+                    recording.failure = None;
+                    recording.slots.push(RecordedSlot {
+                        input,
+                        block: block.clone(),
+                        changes: StateDiff::since(&checkpoint.state, &self.state),
+                    });
+                }
+                Ok(block)
+            }
+            Err(e) => {
+                self.restore(checkpoint);
+                if let Some(recording) = &mut self.recording {
+                    recording.failure = Some(FailedSlot { input, error: e.to_string() });
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        Checkpoint {
+            clock: self.clock.clone(),
+            state: self.state.checkpoint(),
+            blocks_len: self.blocks.len(),
+            epoch_len_slots: self.epoch_len_slots,
+            epoch_start_slot: self.epoch_start_slot,
+            sub_epoch_len_slots: self.sub_epoch_len_slots,
+            behavior_rngs: self.behavior_rngs.mark(),
+            timeline: self.timeline.clone(),
+            applied_events_len: self.applied_events.len(),
+        }
+    }
+
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        self.clock = checkpoint.clock;
+        self.state.rollback(checkpoint.state);
+        self.blocks.truncate(checkpoint.blocks_len);
+        self.epoch_len_slots = checkpoint.epoch_len_slots;
+        self.epoch_start_slot = checkpoint.epoch_start_slot;
+        self.sub_epoch_len_slots = checkpoint.sub_epoch_len_slots;
+        self.behavior_rngs.rollback(checkpoint.behavior_rngs, |_, _| {});
+        self.timeline = checkpoint.timeline;
+        self.applied_events.truncate(checkpoint.applied_events_len);
+        self.pending_records.clear();
+
+        while let Some((vid, previous)) = self.behavior_undo.pop() {
+            match previous {
+                Some(behavior) => self.behaviors.insert(vid, behavior),
+                None => self.behaviors.remove(&vid),
+            };
+        }
+    }
+
+    /// One slot on `replay`'s inputs, or on the timeline's and behaviours'
+    /// when `None`. Fills `input` with the inputs used as it goes, so a slot
+    /// that fails still says what it failed on.
+    fn step(&mut self, start: &StateCheckpoint, replay: Option<&SlotInput>, input: &mut SlotInput) -> Result<Block, SimError> {
         let slot_index = self.clock.slot_index;
-        let _slot_start_ms = self.clock.slot_start_ms;
 
-        let proposals = vec![
-            Proposal { proposer_id: 1, block_id: slot_index },
-            Proposal { proposer_id: 1, block_id: slot_index + 9999 },
-            Proposal { proposer_id: 2, block_id: slot_index },
-        ];
+        let mut events = Vec::new();
+        input.actions = match replay {
+            Some(recorded) => recorded.actions.clone(),
+            None => self.take_due_actions(slot_index),
+        };
+        self.apply_actions(slot_index, &input.actions, &mut events);
+
+        let leader = slot_leader(&self.state, slot_index)?;
+        input.proposals = match replay {
+            Some(recorded) => recorded.proposals.clone(),
+            None => self.gather_proposals(slot_index, leader),
+        };
 
-        let block = process_slot(
+        // Reports made inside `process_slot`, for a double-sign in this slot.
+        let evidence = &mut input.evidence;
+        let behaviors = &mut self.behaviors;
+        let mut reporter = |state: &ChainState, offence: &DoubleSignEvidence| {
+            let reporter = match replay {
+                Some(recorded) => recorded
+                    .evidence
+                    .iter()
+                    .find(|r| (r.validator, r.slot) == offence.offence())
+                    .map(|r| r.reporter),
+                None => choose_reporter(behaviors, state, offence),
+            };
+            if let Some(reporter) = reporter {
+                evidence.push(EvidenceReport { validator: offence.validator, slot: offence.slot, reporter });
+            }
+            reporter
+        };
+        let mut block = process_slot(
             &mut self.state,
             self.clock.slot_index,
             self.clock.slot_start_ms,
-            &proposals,
-        );
-
-        self.blocks.push(block.clone());
+            leader,
+            &input.proposals,
+            &mut reporter,
+            &mut events,
+        )?;
+        match replay {
+            Some(recorded) => {
+                let later: Vec<EvidenceReport> =
+                    recorded.evidence.iter().filter(|r| !input.evidence.contains(r)).copied().collect();
+                for report in later {
+                    self.submit_report(&report, &mut events)?;
+                    input.evidence.push(report);
+                }
+            }
+            None => {
+                let reports = self.report_evidence(slot_index, &mut events)?;
+                input.evidence.extend(reports);
+            }
+        }
+        include_evidence(&mut self.state, &mut events)?;
+        self.beacon.on_block(&mut self.state, &block);
+        self.check_invariants(start, &events, slot_index)?;
+        self.emit(slot_index, &events);
 
         // Advance time deterministically
         self.clock.slot_index += 1;
         self.clock.slot_start_ms += 3_000;
         self.clock.now_ms = self.clock.slot_start_ms;
 
-        if self.clock.slot_index % self.epoch_len_slots == 0 {
-            process_epoch_transition(&mut self.state);
+        if self.clock.slot_index >= self.epoch_start_slot + self.epoch_len_slots {
+            self.epoch_start_slot = self.clock.slot_index;
+            let boundary = events.len();
+            self.state.epoch_seed = self.beacon.next_epoch_seed(&self.state);
+            process_epoch_transition(&mut self.state, &mut events)?;
+            events.push(SimEvent::EpochSeed { seed: self.state.epoch_seed });
+            self.check_invariants(start, &events, slot_index)?;
+            self.emit(self.clock.slot_index, &events[boundary..]);
+        } else if self.sub_epoch_len_slots > 0
+            && (self.clock.slot_index - self.epoch_start_slot).is_multiple_of(self.sub_epoch_len_slots)
+        {
+            let boundary = events.len();
+            process_sub_epoch_transition(&mut self.state, &mut events)?;
+            self.check_invariants(start, &events, slot_index)?;
+            self.emit(self.clock.slot_index, &events[boundary..]);
         }
 
-        block
+        // Seal last, so the root covers any boundary this slot closed.
+        self.tree.update(&mut self.state)?;
+        block.state_root = self.tree.root();
+        block.parent_hash = self.blocks.last().map(Block::hash).unwrap_or_default();
+        self.blocks.push(block.clone());
+        Ok(block)
     }
 }
 
+/// The lowest-id Active validator whose behaviour reports `evidence`.
+fn choose_reporter(
+    behaviors: &mut BTreeMap<u64, Box<dyn ValidatorBehavior>>,
+    state: &ChainState,
+    evidence: &DoubleSignEvidence,
+) -> Option<u64> {
+    state
+        .validators
+        .values()
+        .filter(|v| v.state == ValidatorState::Active)
+        .map(|v| v.id)
+        .find(|&vid| match behaviors.get_mut(&vid) {
+            Some(behavior) => behavior.reports_evidence(vid, evidence),
+            None => Honest.reports_evidence(vid, evidence),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::sim::sink::MemorySink;
+    use crate::sim::timeline::{Trigger, TimelineEvent};
+    use crate::state::invariants::Invariant;
+    use crate::state::retirement_ops::request_ticket_retire;
+    use crate::state::root::state_root;
+    use crate::types::validator::ValidatorState;
+
+    #[test]
+    fn failed_slot_leaves_simulator_untouched() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        let sink = MemorySink::default();
+        sim.add_sink(Box::new(sink.clone()));
+
+        // Without a MUTED bucket, validator 1's first double-sign cannot be punished.
+        sim.state.muted_bucket_ids.clear();
+
+        let err = loop {
+            let state = sim.state.clone();
+            let (slot, emitted) = (sim.clock.slot_index, sink.len());
+            match sim.run_one_slot() {
+                Ok(_) => continue,
+                Err(e) => {
+                    assert_eq!(sim.state, state);
+                    assert_eq!(sim.clock.slot_index, slot);
+                    assert_eq!(sink.len(), emitted);
+                    break e;
+                }
+            }
+        };
+        assert_eq!(err, SimError::NoMutedBucket);
+    }
+
+    #[test]
+    fn failed_slot_inputs_are_recorded() {
+        let scenario = Scenario::load("scenarios/default.toml").unwrap();
+        let mut sim = scenario.build_simulator().unwrap();
+        sim.state.muted_bucket_ids.clear();
+        sim.recording = Some(Recording::new(scenario, sim.state.clone()));
+
+        let err = loop {
+            if let Err(e) = sim.run_one_slot() {
+                break e;
+            }
+        };
+        let recording = sim.recording.take().unwrap();
+        let failure = recording.failure.unwrap();
+        assert_eq!(failure.input.slot, sim.clock.slot_index);
+        assert_eq!(failure.input.slot, recording.slots.len() as u64);
+        assert_eq!(failure.error, err.to_string());
+        // Validator 1's two conflicting proposals, which it could not be punished for.
+        assert!(failure.input.proposals.iter().filter(|p| p.proposer_id == 1).count() >= 2);
+    }
+
+    #[test]
+    fn zero_retire_limit_is_rejected_mid_run() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        let limit = sim.state.retire_per_epoch_limit;
+        sim.timeline.push(TimelineEvent {
+            at: Trigger::Slot(0),
+            action: Action::SetParam { name: "retire_per_epoch_limit".to_string(), value: 0 },
+        });
+        sim.timeline.push(TimelineEvent {
+            at: Trigger::Slot(0),
+            action: Action::RetireTickets { validator: 1, ticket_ids: vec![1] },
+        });
+
+        sim.run_one_slot().unwrap();
+
+        assert_eq!(sim.applied_events[0].error.as_deref(), Some("retire_per_epoch_limit must be greater than zero"));
+        assert_eq!(sim.applied_events[1].error, None);
+        assert_eq!(sim.state.retire_per_epoch_limit, limit);
+
+        // Even with the limit already zero, scheduling fails instead of looping.
+        sim.state.retire_per_epoch_limit = 0;
+        let mut events = Vec::new();
+        let err = request_ticket_retire(&mut sim.state, 2, vec![2], &mut events).unwrap_err();
+        assert!(matches!(err, SimError::InvalidParam { .. }));
+    }
+
+    #[test]
+    fn overflowing_refills_are_rejected() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        let vault = sim.state.validators[&2].vault_balance;
+        let deposited = sim.state.ledger.deposited;
+        for action in [
+            Action::VaultRefill { validator: 2, amount: u128::MAX },
+            Action::QueueVaultRefill { validator: 2, amount: u128::MAX },
+        ] {
+            sim.timeline.push(TimelineEvent { at: Trigger::Slot(0), action });
+        }
+
+        sim.run_one_slot().unwrap();
+
+        let overflow = SimError::BalanceOverflow { validator: 2, amount: u128::MAX }.to_string();
+        assert!(sim.applied_events.iter().all(|e| e.error.as_deref() == Some(overflow.as_str())));
+        assert_eq!(sim.state.validators[&2].vault_balance, vault);
+        assert_eq!(sim.state.ledger.deposited, deposited);
+        assert!(sim.state.pending_refills.is_empty());
+    }
+
+    #[test]
+    fn rejected_action_is_recorded_and_has_no_effect() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        sim.timeline.push(TimelineEvent {
+            at: Trigger::Slot(0),
+            action: Action::IssueTickets { owner: 2, ticket_ids: vec![100, 1] },
+        });
+
+        sim.run_one_slot().unwrap();
+
+        assert_eq!(sim.applied_events[0].error.as_deref(), Some("ticket 1 already exists"));
+        assert!(!sim.state.tickets.contains_key(&100));
+        assert!(sim.state.buckets.values().all(|b| !b.ticket_ids.contains(&100)));
+        assert!(sim.state.ticket_index.owned_by(2).all(|tid| tid != 100));
+    }
+
+    #[test]
+    fn recorded_changes_match_a_full_comparison() {
+        let mut scenario = Scenario::load("scenarios/interventions.toml").unwrap();
+        // Rewards, so every keyed map changes.
+        scenario.rewards.per_ticket_reward = 1;
+        let mut sim = scenario.build_simulator().unwrap();
+        sim.recording = Some(Recording::new(scenario, sim.state.clone()));
+        for _ in 0..200 {
+            let before = sim.state.clone();
+            sim.run_one_slot().unwrap();
+            let recorded = sim.recording.as_ref().unwrap().slots.last().unwrap();
+            assert_eq!(recorded.changes, StateDiff::between(&before, &sim.state), "slot {}", recorded.input.slot);
+
+            let mut undone = sim.state.clone();
+            recorded.changes.revert(&mut undone).unwrap();
+            assert_eq!(undone, before);
+        }
+    }
+
+    #[test]
+    fn sealed_roots_match_a_full_rebuild() {
+        let mut scenario = Scenario::load("scenarios/interventions.toml").unwrap();
+        scenario.rewards.per_ticket_reward = 1;
+        let mut sim = scenario.build_simulator().unwrap();
+        // A ticket added by a rejected action, then one added for good.
+        sim.timeline.push(TimelineEvent {
+            at: Trigger::Slot(30),
+            action: Action::IssueTickets { owner: 2, ticket_ids: vec![100, 1] },
+        });
+        sim.timeline.push(TimelineEvent { at: Trigger::Slot(31), action: Action::IssueTickets { owner: 2, ticket_ids: vec![100] } });
+        for _ in 0..200 {
+            let block = sim.run_one_slot().unwrap();
+            assert_eq!(block.state_root, state_root(&sim.state).unwrap(), "slot {}", block.slot_index);
+        }
+        assert!(sim.state.tickets.contains_key(&100));
+    }
+
+    #[test]
+    fn invariants_hold_on_bundled_scenarios() {
+        for path in ["scenarios/default.toml", "scenarios/full_layout.toml", "scenarios/interventions.toml"] {
+            let scenario = Scenario::load(path).unwrap();
+            let mut sim = scenario.build_simulator().unwrap();
+            sim.invariants = Some(Invariants::default());
+            for _ in 0..scenario.run_slots().unwrap() {
+                if let Err(e) = sim.run_one_slot() {
+                    panic!("{}: {}", path, e);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn full_runs_reach_the_same_state_roots() {
+        let run = |path: &str| {
+            let scenario = Scenario::load(path).unwrap();
+            let mut sim = scenario.build_simulator().unwrap();
+            let roots: Vec<_> =
+                (0..scenario.run_slots().unwrap()).map(|_| sim.run_one_slot().unwrap().state_root).collect();
+            (roots, state_root(&sim.state).unwrap())
+        };
+
+        let paths =
+            ["scenarios/default.toml", "scenarios/full_layout.toml", "scenarios/interventions.toml", "scenarios/randao_bias.toml"];
+        for path in paths {
+            assert!(run(path) == run(path), "{} differs between runs", path);
+        }
+    }
+
+    #[test]
+    fn violation_reports_rule_slot_and_diff() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        sim.invariants = Some(Invariants::default());
+        sim.run_one_slot().unwrap();
+
+        // Corrupt state between slots: a cooldown with no end.
+        let v2 = sim.state.validators.get_mut(&2).unwrap();
+        v2.state = ValidatorState::PunishedCooldown;
+        v2.cooldown_until_epoch = None;
+
+        let Err(SimError::InvariantViolation(violation)) = sim.run_one_slot() else {
+            panic!("expected an invariant violation");
+        };
+        assert_eq!(violation.invariant, Invariant::CooldownHasEnd);
+        assert_eq!(violation.slot, 1);
+        assert!(!violation.diff.is_empty());
+    }
+}
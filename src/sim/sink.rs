@@ -0,0 +1,203 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::types::event::{EventRecord, SimEvent};
+
+/// Receives every event the simulator emits, in order.
+pub trait EventSink {
+    fn emit(&mut self, record: &EventRecord);
+
+    fn flush(&mut self) {}
+}
+
+/// Keeps events in memory. Clones share the same buffer, so a handle kept by
+/// the caller sees everything emitted into the copy owned by the simulator.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    records: Rc<RefCell<Vec<EventRecord>>>,
+}
+
+impl MemorySink {
+    pub fn records(&self) -> Vec<EventRecord> {
+        self.records.borrow().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.borrow().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.records.borrow_mut().clear();
+    }
+}
+
+impl EventSink for MemorySink {
+    fn emit(&mut self, record: &EventRecord) {
+        self.records.borrow_mut().push(record.clone());
+    }
+}
+
+/// Writes one JSON object per line.
+pub struct JsonLinesSink<W: Write> {
+    writer: W,
+    /// First write error, if any; later events are dropped.
+    pub error: Option<std::io::Error>,
+}
+
+impl JsonLinesSink<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(JsonLinesSink::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesSink { writer, error: None }
+    }
+}
+
+impl<W: Write> EventSink for JsonLinesSink<W> {
+    fn emit(&mut self, record: &EventRecord) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = serde_json::to_writer(&mut self.writer, record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            self.error.get_or_insert(e);
+        }
+    }
+}
+
+impl<W: Write> Drop for JsonLinesSink<W> {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Human-readable output. By default prints slashing and punishment,
+/// retirement steps, epoch transitions and scripted actions; `verbose` adds
+/// every other event.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleSink {
+    pub verbose: bool,
+}
+
+impl ConsoleSink {
+    pub fn format(record: &EventRecord, verbose: bool) -> Option<String> {
+        let line = match &record.event {
+            SimEvent::EpochTransition { epoch } => format!(
+                "=== EPOCH TRANSITION → {} ===\nProcessing retire begin for epoch {}",
+                epoch, epoch
+            ),
+            SimEvent::RetireBegin { ticket_ids } => format!("Tickets scheduled this epoch: {:?}", ticket_ids),
+            SimEvent::RetireFinalize { ticket_ids } => format!("Finalizing tickets this epoch: {:?}", ticket_ids),
+            SimEvent::LivenessSlash { validator, bps, basis, new_vault, .. } => format!(
+                "!!!  LIVENESS SLASH: validator {} slashed by {}, new vault = {} !!!",
+                validator,
+                basis.describe(*bps),
+                new_vault
+            ),
+            SimEvent::DoubleSign { validator, offense, bps, basis, new_vault, .. } => format!(
+                "!!! DOUBLE-SIGN: validator {} offense #{} => {} slash. New vault={} !!!",
+                validator,
+                offense,
+                basis.describe(*bps),
+                new_vault
+            ),
+            SimEvent::CooldownStarted { validator, until_epoch } => {
+                format!("Validator {} muted until epoch {}", validator, until_epoch)
+            }
+            SimEvent::Jailed { validator } => format!("!!!!! Validator {} JAILED !!!!!", validator),
+            SimEvent::ScheduledAction { action, error: None } => {
+                format!("Event @ slot {} (epoch {}): {:?}", record.slot, record.epoch, action)
+            }
+            SimEvent::ScheduledAction { action, error: Some(e) } => format!(
+                "Event @ slot {} (epoch {}) REJECTED: {:?}: {}",
+                record.slot, record.epoch, action, e
+            ),
+            other if verbose => format!(
+                "[slot {} epoch {}.{}] {:?}",
+                record.slot, record.epoch, record.sub_epoch, other
+            ),
+            _ => return None,
+        };
+        Some(line)
+    }
+}
+
+impl EventSink for ConsoleSink {
+    fn emit(&mut self, record: &EventRecord) {
+        if let Some(line) = ConsoleSink::format(record, self.verbose) {
+            println!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economics::slashing::SlashBasis;
+    use crate::scenario::config::Scenario;
+
+    #[test]
+    fn memory_sink_sees_double_sign_escalation() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        let sink = MemorySink::default();
+        sim.add_sink(Box::new(sink.clone()));
+
+        for _ in 0..200 {
+            sim.run_one_slot().unwrap();
+        }
+
+        let records = sink.records();
+        let offenses: Vec<u8> = records
+            .iter()
+            .filter_map(|r| match r.event {
+                SimEvent::DoubleSign { validator: 1, offense, .. } => Some(offense),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(offenses, vec![1, 2, 3]);
+        assert!(records.iter().any(|r| r.event == SimEvent::Jailed { validator: 1 }));
+        assert!(records.windows(2).all(|w| w[0].slot <= w[1].slot));
+    }
+
+    #[test]
+    fn json_lines_round_trip() {
+        let record = EventRecord {
+            slot: 12,
+            epoch: 1,
+            sub_epoch: 0,
+            event: SimEvent::LivenessSlash {
+                validator: 2,
+                bps: 500,
+                basis: SlashBasis::CurrentVault,
+                amount: 50,
+                new_vault: 950,
+            },
+        };
+
+        let mut sink = JsonLinesSink::new(Vec::new());
+        sink.emit(&record);
+        sink.emit(&record);
+
+        let text = String::from_utf8(sink.writer.clone()).unwrap();
+        let parsed: Vec<EventRecord> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(parsed, vec![record.clone(), record]);
+    }
+}
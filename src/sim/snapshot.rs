@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::beacon::BeaconSpec;
+use crate::sim::behavior::BehaviorSpec;
+use crate::sim::clock::SimClock;
+use crate::sim::rng::SimRng;
+use crate::sim::timeline::{AppliedEvent, Timeline};
+use crate::state::chain_state::ChainState;
+use crate::state::invariants::Invariants;
+use crate::types::block::Block;
+
+/// Format version written into every snapshot. Bump it whenever a change to
+/// `Snapshot` or anything it contains would make older files resume wrongly.
+pub const SNAPSHOT_VERSION: u32 = 6;
+
+/// A simulator between two slots: everything `Simulator::from_snapshot`
+/// needs to carry on exactly as the original would have.
+///
+/// Behaviours and the beacon are stored as the specs they are built from;
+/// the built-in ones keep no state outside `ChainState` and their RNG stream.
+/// Sinks are not part of a snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub clock: SimClock,
+    pub state: ChainState,
+    pub blocks: Vec<Block>,
+    pub epoch_len_slots: u64,
+    pub epoch_start_slot: u64,
+    pub sub_epoch_len_slots: u64,
+    pub behavior_seed: u64,
+    pub behaviors: BTreeMap<u64, BehaviorSpec>,
+    pub behavior_rngs: BTreeMap<u64, SimRng>,
+    pub beacon: BeaconSpec,
+    /// Scheduled actions not yet applied.
+    pub timeline: Timeline,
+    pub applied_events: Vec<AppliedEvent>,
+    pub invariants: Option<Invariants>,
+    pub auto_snapshot: Option<AutoSnapshot>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(String, std::io::Error),
+    Parse(String),
+    UnsupportedVersion(u32),
+    /// The validator's behaviour was not built from a `BehaviorSpec`.
+    UnsupportedBehavior(u64),
+    /// The beacon was not built from a `BeaconSpec`.
+    UnsupportedBeacon,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(path, e) => write!(f, "{}: {}", path, e),
+            SnapshotError::Parse(msg) => write!(f, "parse error: {}", msg),
+            SnapshotError::UnsupportedVersion(v) => {
+                write!(f, "snapshot version {} is not supported (expected {})", v, SNAPSHOT_VERSION)
+            }
+            SnapshotError::UnsupportedBehavior(id) => {
+                write!(f, "behaviour of validator {} has no spec and cannot be snapshotted", id)
+            }
+            SnapshotError::UnsupportedBeacon => write!(f, "beacon has no spec and cannot be snapshotted"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Only the version, read before the rest so that an old file fails with
+/// `UnsupportedVersion` rather than a parse error.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+impl Snapshot {
+    /// Write as JSON. The file is written next to `path` and renamed into
+    /// place, so a crash mid-write never leaves a truncated snapshot.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        let io_error = |e| SnapshotError::Io(path.display().to_string(), e);
+
+        let tmp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(fs::File::create(&tmp).map_err(io_error)?);
+        serde_json::to_writer(&mut writer, self).map_err(|e| SnapshotError::Parse(e.to_string()))?;
+        writer.flush().map_err(io_error)?;
+        drop(writer);
+        fs::rename(&tmp, path).map_err(io_error)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Snapshot, SnapshotError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| SnapshotError::Io(path.display().to_string(), e))?;
+        Snapshot::from_json(&text)
+    }
+
+    pub fn from_json(text: &str) -> Result<Snapshot, SnapshotError> {
+        let header: Header = serde_json::from_str(text).map_err(|e| SnapshotError::Parse(e.to_string()))?;
+        if header.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(header.version));
+        }
+        let mut snapshot: Snapshot = serde_json::from_str(text).map_err(|e| SnapshotError::Parse(e.to_string()))?;
+        snapshot.state.rebuild_ticket_index();
+        Ok(snapshot)
+    }
+}
+
+/// The `[snapshots]` table of a scenario: write a snapshot at the start of
+/// every `every_epochs`-th epoch and keep the latest `keep` of them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AutoSnapshot {
+    pub dir: PathBuf,
+    pub every_epochs: u64,
+    /// 0 keeps every snapshot.
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+}
+
+fn default_keep() -> usize {
+    3
+}
+
+const FILE_PREFIX: &str = "epoch-";
+const FILE_SUFFIX: &str = ".snapshot.json";
+
+impl AutoSnapshot {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.every_epochs == 0 {
+            return Err("every_epochs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn is_due(&self, epoch: u64) -> bool {
+        epoch > 0 && self.every_epochs > 0 && epoch.is_multiple_of(self.every_epochs)
+    }
+
+    /// File the snapshot taken at the start of `epoch` goes to.
+    pub fn path(&self, epoch: u64) -> PathBuf {
+        self.dir.join(format!("{}{:08}{}", FILE_PREFIX, epoch, FILE_SUFFIX))
+    }
+
+    /// Existing snapshots in `dir`, oldest first.
+    pub fn existing(&self) -> Result<Vec<(u64, PathBuf)>, SnapshotError> {
+        let io_error = |e| SnapshotError::Io(self.dir.display().to_string(), e);
+        let mut found = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            let epoch = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX)?.parse().ok());
+            if let Some(epoch) = epoch {
+                found.push((epoch, path));
+            }
+        }
+        found.sort();
+        Ok(found)
+    }
+
+    /// Save `snapshot` for `epoch`, then delete all but the latest `keep`.
+    pub fn write(&self, epoch: u64, snapshot: &Snapshot) -> Result<PathBuf, SnapshotError> {
+        fs::create_dir_all(&self.dir).map_err(|e| SnapshotError::Io(self.dir.display().to_string(), e))?;
+        let path = self.path(epoch);
+        snapshot.save(&path)?;
+
+        if self.keep > 0 {
+            let existing = self.existing()?;
+            let excess = existing.len().saturating_sub(self.keep);
+            for (_, old) in &existing[..excess] {
+                fs::remove_file(old).map_err(|e| SnapshotError::Io(old.display().to_string(), e))?;
+            }
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::sim::simulator::Simulator;
+    use crate::sim::sink::MemorySink;
+
+    fn run(sim: &mut Simulator, slots: u64) {
+        for _ in 0..slots {
+            sim.run_one_slot().unwrap();
+        }
+    }
+
+    #[test]
+    fn resumed_run_matches_uninterrupted_run() {
+        let scenario = Scenario::load("scenarios/interventions.toml").unwrap();
+        let mut straight = scenario.build_simulator().unwrap();
+        let straight_events = MemorySink::default();
+        straight.add_sink(Box::new(straight_events.clone()));
+        run(&mut straight, 37);
+        straight_events.clear();
+        run(&mut straight, 163);
+
+        let mut first = scenario.build_simulator().unwrap();
+        run(&mut first, 37);
+        let json = serde_json::to_string(&first.snapshot().unwrap()).unwrap();
+        let mut resumed = Simulator::from_snapshot(Snapshot::from_json(&json).unwrap()).unwrap();
+        let resumed_events = MemorySink::default();
+        resumed.add_sink(Box::new(resumed_events.clone()));
+        run(&mut resumed, 163);
+
+        assert_eq!(resumed.blocks, straight.blocks);
+        assert_eq!(resumed.state, straight.state);
+        assert_eq!(resumed.clock, straight.clock);
+        assert_eq!(resumed.applied_events, straight.applied_events);
+        assert_eq!(resumed_events.records(), straight_events.records());
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        let mut snapshot = sim.snapshot().unwrap();
+        snapshot.version = SNAPSHOT_VERSION + 1;
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(matches!(Snapshot::from_json(&json), Err(SnapshotError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    fn auto_snapshots_keep_the_latest() {
+        let dir = std::env::temp_dir().join(format!("eternix-snapshots-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        sim.auto_snapshot = Some(AutoSnapshot { dir: dir.clone(), every_epochs: 2, keep: 2 });
+        let slots = 10 * sim.epoch_len_slots + 1;
+        run(&mut sim, slots);
+
+        let auto = sim.auto_snapshot.clone().unwrap();
+        let epochs: Vec<u64> = auto.existing().unwrap().into_iter().map(|(epoch, _)| epoch).collect();
+        assert_eq!(epochs, vec![8, 10]);
+        let latest = Snapshot::load(auto.path(10)).unwrap();
+        assert_eq!(latest.state.epoch_index, 10);
+        assert_eq!(latest.clock.slot_index, latest.epoch_start_slot);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::amount;
+use crate::sim::behavior::BehaviorSpec;
+
+/// When a scheduled action fires.
+///
+/// Epoch-triggered actions run at the start of the first slot of that epoch,
+/// before slot-triggered actions for the same slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    Slot(u64),
+    Epoch(u64),
+}
+
+/// A mid-run intervention.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Action {
+    VaultRefill {
+        validator: u64,
+        #[serde(with = "amount")]
+        amount: u128,
+    },
+    /// Refill applied at the next sub-epoch boundary rather than immediately.
+    QueueVaultRefill {
+        validator: u64,
+        #[serde(with = "amount")]
+        amount: u128,
+    },
+    RetireTickets {
+        validator: u64,
+        ticket_ids: Vec<u64>,
+    },
+    JoinValidator {
+        validator: u64,
+        #[serde(with = "amount")]
+        vault_balance: u128,
+        #[serde(with = "amount")]
+        initial_bond: u128,
+        #[serde(default)]
+        ticket_ids: Vec<u64>,
+        #[serde(default)]
+        behavior: Option<BehaviorSpec>,
+    },
+    IssueTickets {
+        owner: u64,
+        ticket_ids: Vec<u64>,
+    },
+    ForceJail {
+        validator: u64,
+    },
+    SetParam {
+        name: String,
+        value: u64,
+    },
+    SetBehavior {
+        validator: u64,
+        behavior: BehaviorSpec,
+    },
+    /// Submit the evidence of `validator` double-signing at `slot`.
+    SubmitEvidence {
+        validator: u64,
+        slot: u64,
+        #[serde(default)]
+        reporter: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimelineEvent {
+    pub at: Trigger,
+    pub action: Action,
+}
+
+/// An action as it was actually applied during a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedEvent {
+    pub slot: u64,
+    pub epoch: u64,
+    pub action: Action,
+    /// Set when the action was rejected (e.g. unknown validator); state is unchanged.
+    pub error: Option<String>,
+}
+
+/// Pending scheduled actions, kept in firing order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    by_epoch: BTreeMap<u64, Vec<Action>>,
+    by_slot: BTreeMap<u64, Vec<Action>>,
+}
+
+impl Timeline {
+    pub fn push(&mut self, event: TimelineEvent) {
+        match event.at {
+            Trigger::Slot(slot) => self.by_slot.entry(slot).or_default().push(event.action),
+            Trigger::Epoch(epoch) => self.by_epoch.entry(epoch).or_default().push(event.action),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_epoch.is_empty() && self.by_slot.is_empty()
+    }
+
+    /// Remove and return every action due at or before `(slot, epoch)`,
+    /// epoch-triggered ones first, each group in declaration order.
+    pub fn take_due(&mut self, slot: u64, epoch: u64) -> Vec<Action> {
+        let mut due = Vec::new();
+
+        let later_epochs = self.by_epoch.split_off(&(epoch + 1));
+        for (_, actions) in std::mem::replace(&mut self.by_epoch, later_epochs) {
+            due.extend(actions);
+        }
+
+        let later_slots = self.by_slot.split_off(&(slot + 1));
+        for (_, actions) in std::mem::replace(&mut self.by_slot, later_slots) {
+            due.extend(actions);
+        }
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jail(validator: u64) -> Action {
+        Action::ForceJail { validator }
+    }
+
+    #[test]
+    fn due_actions_fire_once_epoch_first() {
+        let mut timeline = Timeline::default();
+        timeline.push(TimelineEvent { at: Trigger::Slot(10), action: jail(1) });
+        timeline.push(TimelineEvent { at: Trigger::Epoch(1), action: jail(2) });
+        timeline.push(TimelineEvent { at: Trigger::Slot(11), action: jail(3) });
+
+        assert!(timeline.take_due(9, 0).is_empty());
+        assert_eq!(timeline.take_due(10, 1), vec![jail(2), jail(1)]);
+        assert!(timeline.take_due(10, 1).is_empty());
+        assert_eq!(timeline.take_due(11, 1), vec![jail(3)]);
+        assert!(timeline.is_empty());
+    }
+}
@@ -1,88 +1,249 @@
+use sha2::{Digest, Sha256};
+
+use crate::error::SimError;
 use crate::state::chain_state::ChainState;
-use crate::types::ticket::TicketState;
+use crate::types::event::SimEvent;
+use crate::types::ticket::{Ticket, TicketState};
+use crate::types::validator::ValidatorState;
 
-/// Move a ticket between buckets.
+/// Move a ticket between buckets, keeping `ticket_index` in step.
 /// This is the ONLY place tickets are allowed to change buckets.
+/// All checks run before anything is changed.
 pub fn move_ticket(
     state: &mut ChainState,
     ticket_id: u64,
     from_bucket: u64,
     to_bucket: u64,
-) {
-    {
-        let from = state.buckets.get_mut(&from_bucket).unwrap();
-        let removed = from.ticket_ids.remove(&ticket_id);
-        assert!(removed, "Ticket {} not found in source bucket {}", ticket_id, from_bucket);
+) -> Result<(), SimError> {
+    if !state.tickets.contains_key(&ticket_id) {
+        return Err(SimError::UnknownTicket(ticket_id));
+    }
+
+    let from = state.buckets.get(&from_bucket).ok_or(SimError::UnknownBucket(from_bucket))?;
+    if !from.ticket_ids.contains(&ticket_id) {
+        return Err(SimError::TicketNotInBucket { ticket: ticket_id, bucket: from_bucket });
     }
 
-    {
-        let to = state.buckets.get_mut(&to_bucket).unwrap();
-        let inserted = to.ticket_ids.insert(ticket_id);
-        assert!(inserted, "Ticket {} already in target bucket {}", ticket_id, to_bucket);
+    let to = state.buckets.get(&to_bucket).ok_or(SimError::UnknownBucket(to_bucket))?;
+    if to_bucket != from_bucket && to.ticket_ids.contains(&ticket_id) {
+        return Err(SimError::TicketAlreadyInBucket { ticket: ticket_id, bucket: to_bucket });
     }
 
-    state.tickets.get_mut(&ticket_id).unwrap().bucket = to_bucket;
+    state.buckets.get_mut(&from_bucket).unwrap().ticket_ids.remove(&ticket_id);
+    state.buckets.get_mut(&to_bucket).unwrap().ticket_ids.insert(ticket_id);
+    let ticket = state.tickets.get_mut(&ticket_id).unwrap();
+    let before = ticket.clone();
+    ticket.bucket = to_bucket;
+    state.ticket_index.update(&before, ticket)
+}
+
+/// Change a ticket's lifecycle state, keeping `ticket_index` in step.
+/// This is the ONLY place tickets are allowed to change state.
+/// Returns the previous state.
+pub fn set_ticket_state(state: &mut ChainState, ticket_id: u64, to: TicketState) -> Result<TicketState, SimError> {
+    let ticket = state.tickets.get_mut(&ticket_id).ok_or(SimError::UnknownTicket(ticket_id))?;
+    let before = ticket.clone();
+    ticket.state = to;
+    state.ticket_index.update(&before, ticket)?;
+    Ok(before.state)
+}
+
+/// The lowest-id MUTED bucket.
+pub fn any_muted_bucket(state: &ChainState) -> Result<u64, SimError> {
+    state.muted_bucket_ids.iter().next().copied().ok_or(SimError::NoMutedBucket)
 }
 
-pub fn any_muted_bucket(state: &ChainState) -> u64 {
-    *state
-        .muted_bucket_ids
-        .iter()
-        .next()
-        .expect("No MUTED bucket defined")
+/// The lowest-id ACTIVE bucket.
+pub fn any_active_bucket(state: &ChainState) -> Result<u64, SimError> {
+    state.active_bucket_ids.iter().next().copied().ok_or(SimError::NoActiveBucket)
 }
 
-pub fn any_active_bucket(state: &ChainState) -> u64 {
-    *state
-        .active_bucket_ids
-        .iter()
-        .next()
-        .expect("No ACTIVE bucket defined")
+/// ACTIVE bucket ids in ascending order; the index space for `home_bucket_for`.
+pub fn sorted_active_bucket_ids(state: &ChainState) -> Vec<u64> {
+    state.active_bucket_ids.iter().copied().collect()
 }
 
+/// Deterministic home bucket of a ticket: `hash(seed || ticket_id)` modulo the
+/// number of ACTIVE buckets, indexed into the sorted ACTIVE bucket ids.
+pub fn home_bucket_for(ticket_id: u64, seed: [u8; 32], sorted_active_bucket_ids: &[u64]) -> Result<u64, SimError> {
+    if sorted_active_bucket_ids.is_empty() {
+        return Err(SimError::NoActiveBucket);
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(seed);
+    hasher.update(ticket_id.to_be_bytes());
+    let hash: [u8; 32] = hasher.finalize().into();
+
+    let raw = u64::from_be_bytes(hash[0..8].try_into().unwrap());
+    Ok(sorted_active_bucket_ids[(raw % sorted_active_bucket_ids.len() as u64) as usize])
+}
+
+/// Move every non-Dead ticket of a validator into `to_bucket`.
+/// Dead tickets stay in the DEAD bucket.
 pub fn move_all_validator_tickets_to_bucket(
     state: &mut ChainState,
     validator_id: u64,
     to_bucket: u64,
-) {
+) -> Result<(), SimError> {
     let ticket_ids: Vec<u64> = state
-        .tickets
-        .values()
-        .filter(|t| t.owner == validator_id)
-        .map(|t| t.id)
+        .ticket_index
+        .owned_by(validator_id)
+        .filter(|tid| state.tickets[tid].state != TicketState::Dead)
         .collect();
 
     for tid in ticket_ids {
-        let from = state.tickets.get(&tid).unwrap().bucket;
+        let from = state.tickets[&tid].bucket;
         if from != to_bucket {
-            move_ticket(state, tid, from, to_bucket);
+            move_ticket(state, tid, from, to_bucket)?;
         }
     }
+    Ok(())
+}
+
+/// Send a validator's Active tickets back to their home buckets, e.g. on rejoin
+/// or cooldown expiry. Retiring tickets stay MUTED and Dead tickets stay DEAD.
+pub fn restore_validator_tickets(state: &mut ChainState, validator_id: u64) -> Result<(), SimError> {
+    let homes: Vec<(u64, u64, u64)> = state
+        .ticket_index
+        .owned_by(validator_id)
+        .map(|tid| &state.tickets[&tid])
+        .filter(|t| t.state == TicketState::Active && t.bucket != t.home_bucket)
+        .map(|t| (t.id, t.bucket, t.home_bucket))
+        .collect();
+
+    for (tid, from, home) in homes {
+        move_ticket(state, tid, from, home)?;
+    }
+    Ok(())
 }
 
-pub fn force_dead_all_validator_tickets(state: &mut ChainState, validator_id: u64) {
+pub fn force_dead_all_validator_tickets(
+    state: &mut ChainState,
+    validator_id: u64,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
     let dead_bucket = state.dead_bucket_id;
 
     // collect first to avoid borrow issues
-    let ticket_ids: Vec<u64> = state
-        .tickets
-        .values()
-        .filter(|t| t.owner == validator_id)
-        .map(|t| t.id)
-        .collect();
+    let ticket_ids: Vec<u64> = state.ticket_index.owned_by(validator_id).collect();
 
     for tid in ticket_ids {
-        let t = state.tickets.get_mut(&tid).unwrap();
-
         // mark dead
-        t.state = TicketState::Dead;
+        if set_ticket_state(state, tid, TicketState::Dead)? != TicketState::Dead {
+            events.push(SimEvent::TicketDead { ticket: tid });
+        }
+        let t = state.tickets.get_mut(&tid).unwrap();
         t.retire_requested_epoch = t.retire_requested_epoch.or(Some(state.epoch_index));
         t.retire_effective_epoch = Some(state.epoch_index);
 
         // move bucket
         let from = t.bucket;
         if from != dead_bucket {
-            move_ticket(state, tid, from, dead_bucket);
+            move_ticket(state, tid, from, dead_bucket)?;
+        }
+    }
+    Ok(())
+}
+
+/// Create a new ticket for `owner`, with its home bucket derived from the current epoch seed.
+/// It starts in its home bucket if the owner is Active, otherwise MUTED until the owner rejoins.
+/// Jailed validators cannot receive tickets.
+pub fn issue_ticket(
+    state: &mut ChainState,
+    ticket_id: u64,
+    owner: u64,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    let owner_state = state.validators.get(&owner).ok_or(SimError::UnknownValidator(owner))?.state;
+    if owner_state == ValidatorState::Jailed {
+        return Err(SimError::ValidatorJailed(owner));
+    }
+    if state.tickets.contains_key(&ticket_id) {
+        return Err(SimError::DuplicateTicket(ticket_id));
+    }
+
+    let home_bucket = home_bucket_for(ticket_id, state.epoch_seed, &sorted_active_bucket_ids(state))?;
+    let bucket = if owner_state == ValidatorState::Active {
+        home_bucket
+    } else {
+        any_muted_bucket(state)?
+    };
+    let target = state.buckets.get_mut(&bucket).ok_or(SimError::UnknownBucket(bucket))?;
+    if !target.ticket_ids.insert(ticket_id) {
+        return Err(SimError::TicketAlreadyInBucket { ticket: ticket_id, bucket });
+    }
+
+    let ticket = Ticket {
+        id: ticket_id,
+        owner,
+        bucket,
+        home_bucket,
+        creation_epoch: state.epoch_index,
+        state: TicketState::Active,
+        retire_requested_epoch: None,
+        retire_effective_epoch: None,
+    };
+    state.ticket_index.insert(&ticket);
+    state.tickets.insert(ticket_id, ticket);
+
+    events.push(SimEvent::TicketIssued { ticket: ticket_id, owner, bucket });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::types::event::SimEvent;
+
+    #[test]
+    fn home_bucket_is_deterministic_and_in_range() {
+        let active: Vec<u64> = (10..20).collect();
+        for tid in 0..100 {
+            let home = home_bucket_for(tid, [9u8; 32], &active).unwrap();
+            assert!(active.contains(&home));
+            assert_eq!(home, home_bucket_for(tid, [9u8; 32], &active).unwrap());
         }
+        assert_eq!(home_bucket_for(1, [9u8; 32], &[]), Err(SimError::NoActiveBucket));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn tickets_return_to_their_home_buckets() {
+        let scenario = Scenario::load("scenarios/full_layout.toml").unwrap();
+        let mut state = scenario.build_state().unwrap();
+        let mut events: Vec<SimEvent> = Vec::new();
+
+        for t in state.tickets.values() {
+            assert_eq!(t.bucket, t.home_bucket);
+        }
+
+        let muted = any_muted_bucket(&state).unwrap();
+        move_all_validator_tickets_to_bucket(&mut state, 2, muted).unwrap();
+        assert!(state.tickets.values().filter(|t| t.owner == 2).all(|t| t.bucket == muted));
+
+        state.validators.get_mut(&2).unwrap().state = ValidatorState::PausedLowVault;
+        crate::state::validator_ops::on_vault_refill(&mut state, 2, 0, &mut events).unwrap();
+
+        for t in state.tickets.values() {
+            assert_eq!(t.bucket, t.home_bucket);
+            assert!(state.buckets[&t.home_bucket].ticket_ids.contains(&t.id));
+        }
+    }
+
+    #[test]
+    fn invalid_move_is_rejected_without_side_effects() {
+        let mut state = Scenario::load("scenarios/default.toml").unwrap().build_state().unwrap();
+        let before = state.clone();
+        let home = state.tickets[&1].bucket;
+        let muted = any_muted_bucket(&state).unwrap();
+
+        assert_eq!(
+            move_ticket(&mut state, 1, muted, home),
+            Err(SimError::TicketNotInBucket { ticket: 1, bucket: muted })
+        );
+        assert_eq!(move_ticket(&mut state, 1, home, 99), Err(SimError::UnknownBucket(99)));
+        assert_eq!(move_ticket(&mut state, 42, home, muted), Err(SimError::UnknownTicket(42)));
+        assert_eq!(state, before);
+    }
+}
@@ -1,21 +1,42 @@
-use std::collections::{HashMap, HashSet, BTreeMap};
+use std::collections::{BTreeMap, BTreeSet};
 
-use crate::types::{validator::Validator, ticket::Ticket, bucket::Bucket};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::consensus::evidence::{EvidenceParams, EvidencePool};
+use crate::consensus::leader_selection::BucketSelection;
+use crate::consensus::params::ProtocolParams;
+use crate::economics::inflation::InflationParams;
+use crate::economics::rewards::{RewardParams, RewardTally};
+use crate::economics::slashing::SlashPolicy;
+use crate::economics::supply::SupplyLedger;
+use crate::state::journal::JournaledMap;
+use crate::state::ticket_index::TicketIndex;
+use crate::types::{validator::{LivenessStats, Validator}, ticket::Ticket, bucket::Bucket};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChainState {
-    pub validators: HashMap<u64, Validator>,
-    pub tickets: HashMap<u64, Ticket>,
+    pub validators: JournaledMap<Validator>,
+    pub tickets: JournaledMap<Ticket>,
+    /// Owner and state lookups over `tickets`. Not serialised; see
+    /// `rebuild_ticket_index`.
+    #[serde(skip)]
+    pub ticket_index: TicketIndex,
 
-    pub buckets: HashMap<u64, Bucket>,
+    pub buckets: JournaledMap<Bucket>,
 
-    pub active_bucket_ids: HashSet<u64>,
-    pub muted_bucket_ids: HashSet<u64>,
+    pub active_bucket_ids: BTreeSet<u64>,
+    pub muted_bucket_ids: BTreeSet<u64>,
     pub dead_bucket_id: u64,
+    /// First-stage leader selection rule.
+    pub bucket_selection: BucketSelection,
 
     pub epoch_index: u64,
     pub sub_epoch_index: u64,
     pub epoch_seed: [u8; 32],
+    /// Seed leaders are drawn from; re-derived at every sub-epoch boundary.
+    pub sub_epoch_seed: [u8; 32],
+    /// Randomness accumulator folded with every block's contribution; see `consensus::beacon`.
+    pub randao_mix: [u8; 32],
 
     pub retire_per_epoch_limit: u64,
 
@@ -24,4 +45,171 @@ pub struct ChainState {
 
     // epoch -> list of ticket ids that become DEAD this epoch
     pub retire_finalize: BTreeMap<u64, Vec<u64>>,
+
+    // validator -> refill amount applied at the next sub-epoch boundary
+    pub pending_refills: JournaledMap<u128>,
+
+    // validator -> leader outcomes since the last sub-epoch boundary
+    pub liveness: JournaledMap<LivenessStats>,
+
+    /// Liveness and double-sign punishment rules.
+    pub protocol: ProtocolParams,
+    pub rewards: RewardParams,
+    pub inflation: InflationParams,
+    pub slashing: SlashPolicy,
+    pub evidence: EvidenceParams,
+    /// Double-sign evidence not yet included, and recently punished offences.
+    pub evidence_pool: EvidencePool,
+    /// Cumulative supply accounting, including the treasury balance.
+    pub ledger: SupplyLedger,
+    /// Rewards of the current epoch, reported and reset at the epoch transition.
+    pub reward_tally: RewardTally,
+    /// Rewards paid to each validator over the current epoch; reported and
+    /// reset with `reward_tally`.
+    pub rewards_paid: JournaledMap<u128>,
+}
+
+/// Calls `$m!` with every `ChainState` field except the validator, ticket
+/// and bucket records, the derived `ticket_index`, the epoch counters and
+/// the `for_each_keyed_field` maps. `state::root::body_hash` hashes them in
+/// this order, so reordering the list changes every state root.
+macro_rules! for_each_field {
+    ($m:ident) => {
+        $m!(
+            active_bucket_ids,
+            muted_bucket_ids,
+            dead_bucket_id,
+            bucket_selection,
+            epoch_seed,
+            sub_epoch_seed,
+            randao_mix,
+            retire_per_epoch_limit,
+            retire_schedule,
+            retire_finalize,
+            protocol,
+            rewards,
+            inflation,
+            slashing,
+            evidence,
+            evidence_pool,
+            ledger,
+            reward_tally
+        )
+    };
+}
+pub(crate) use for_each_field;
+
+/// Calls `$m!` with every `ChainState` map keyed by validator id outside the
+/// records. They are journaled like the records, so checkpoints, diffs and
+/// the state root cost what changed in them rather than what they hold;
+/// `state::root::body_hash` commits to each through its own Merkle tree, in
+/// this order.
+macro_rules! for_each_keyed_field {
+    ($m:ident) => {
+        $m!(pending_refills, liveness, rewards_paid)
+    };
+}
+pub(crate) use for_each_keyed_field;
+
+/// Where the journal of each `for_each_keyed_field` map stood at a checkpoint.
+#[derive(Debug)]
+pub struct KeyedMarks {
+    pub(crate) pending_refills: usize,
+    pub(crate) liveness: usize,
+    pub(crate) rewards_paid: usize,
+}
+
+/// A point `ChainState::rollback` can return to: where each journal stood,
+/// and a copy of every other field.
+#[derive(Debug)]
+pub struct StateCheckpoint {
+    pub(crate) validators: usize,
+    pub(crate) tickets: usize,
+    pub(crate) buckets: usize,
+    pub(crate) keyed: KeyedMarks,
+    /// The state's other fields; its records, keyed maps and index are empty.
+    pub(crate) fields: ChainState,
+}
+
+impl ChainState {
+    /// Recompute `ticket_index` from `tickets`, e.g. after deserialising.
+    pub fn rebuild_ticket_index(&mut self) {
+        self.ticket_index = TicketIndex::build(self.tickets.values(), &self.active_bucket_ids);
+    }
+
+    /// Mark the journals and copy the rest of the state. Costs what the
+    /// `for_each_field` fields cost to clone; journaled maps are not copied.
+    pub fn checkpoint(&mut self) -> StateCheckpoint {
+        macro_rules! copy {
+            ($($field:ident),*) => {
+                ChainState {
+                    validators: JournaledMap::default(),
+                    tickets: JournaledMap::default(),
+                    ticket_index: TicketIndex::default(),
+                    buckets: JournaledMap::default(),
+                    epoch_index: self.epoch_index,
+                    sub_epoch_index: self.sub_epoch_index,
+                    pending_refills: JournaledMap::default(),
+                    liveness: JournaledMap::default(),
+                    rewards_paid: JournaledMap::default(),
+                    $($field: self.$field.clone(),)*
+                }
+            };
+        }
+        macro_rules! mark {
+            ($($field:ident),*) => { KeyedMarks { $($field: self.$field.mark(),)* } };
+        }
+        StateCheckpoint {
+            validators: self.validators.mark(),
+            tickets: self.tickets.mark(),
+            buckets: self.buckets.mark(),
+            keyed: for_each_keyed_field!(mark),
+            fields: for_each_field!(copy),
+        }
+    }
+
+    /// Undo everything done since `checkpoint` was taken, `ticket_index`
+    /// included. An index that does not count a ticket being rolled back is
+    /// rebuilt from the restored tickets.
+    pub fn rollback(&mut self, checkpoint: StateCheckpoint) {
+        let StateCheckpoint { validators, tickets, buckets, keyed, fields } = checkpoint;
+        self.validators.rollback(validators, |_, _| {});
+        let index = &mut self.ticket_index;
+        let mut lost = false;
+        self.tickets.rollback(tickets, |now, then| {
+            if let Some(ticket) = now {
+                lost |= index.remove(ticket).is_err();
+            }
+            if let Some(ticket) = then {
+                index.insert(ticket);
+            }
+        });
+        self.buckets.rollback(buckets, |_, _| {});
+        macro_rules! roll_back {
+            ($($field:ident),*) => { $(self.$field.rollback(keyed.$field, |_, _| {});)* };
+        }
+        for_each_keyed_field!(roll_back);
+
+        self.epoch_index = fields.epoch_index;
+        self.sub_epoch_index = fields.sub_epoch_index;
+        macro_rules! restore {
+            ($($field:ident),*) => { $(self.$field = fields.$field;)* };
+        }
+        for_each_field!(restore);
+        if lost {
+            self.ticket_index = TicketIndex::build(self.tickets.values(), &self.active_bucket_ids);
+        }
+    }
+
+    /// Drop the journals; no earlier checkpoint can be rolled back to after
+    /// this.
+    pub fn commit(&mut self) {
+        self.validators.commit();
+        self.tickets.commit();
+        self.buckets.commit();
+        macro_rules! commit {
+            ($($field:ident),*) => { $(self.$field.commit();)* };
+        }
+        for_each_keyed_field!(commit);
+    }
 }
@@ -0,0 +1,381 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::SimError;
+use crate::state::chain_state::{for_each_field, for_each_keyed_field, ChainState, StateCheckpoint};
+use crate::state::journal::JournaledMap;
+use crate::types::bucket::Bucket;
+use crate::types::ticket::Ticket;
+use crate::types::validator::Validator;
+
+/// A changed entry; `None` on either side means added / removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change<T> {
+    pub id: u64,
+    pub before: Option<T>,
+    pub after: Option<T>,
+}
+
+/// Ticket ids that entered / left a bucket, and whether the bucket itself
+/// was created or dropped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketChange {
+    pub id: u64,
+    pub added: Vec<u64>,
+    pub removed: Vec<u64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub created: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dropped: bool,
+}
+
+/// Any other top-level `ChainState` field that changed, as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+impl FieldChange {
+    fn new<T: Serialize>(field: &str, before: &T, after: &T) -> FieldChange {
+        // Only maps with non-string keys fail to serialise; say so rather than panic.
+        let value = |v: &T| serde_json::to_value(v).unwrap_or_else(|e| Value::String(format!("<unserialisable: {}>", e)));
+        FieldChange { field: field.to_string(), before: value(before), after: value(after) }
+    }
+}
+
+/// A changed entry of a `chain_state::for_each_keyed_field` map, as JSON;
+/// `None` on either side means added / removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryChange {
+    pub field: String,
+    pub id: u64,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl EntryChange {
+    fn new<T: Serialize + PartialEq>(field: &str, id: u64, before: Option<&T>, after: Option<&T>) -> Option<EntryChange> {
+        let value = |v: &T| serde_json::to_value(v).unwrap_or_else(|e| Value::String(format!("<unserialisable: {}>", e)));
+        (before != after).then(|| EntryChange { field: field.to_string(), id, before: before.map(value), after: after.map(value) })
+    }
+}
+
+/// What changed between two chain states: every field of `ChainState`, with
+/// records in id order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDiff {
+    pub epoch: Option<(u64, u64)>,
+    pub sub_epoch: Option<(u64, u64)>,
+    pub validators: Vec<Change<Validator>>,
+    pub tickets: Vec<Change<Ticket>>,
+    pub buckets: Vec<BucketChange>,
+    /// Every other field that differs, in declaration order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+    /// Changed entries of the keyed maps, by map then id.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<EntryChange>,
+}
+
+impl StateDiff {
+    pub fn between(before: &ChainState, after: &ChainState) -> Self {
+        let validator_ids: BTreeSet<u64> = before.validators.keys().chain(after.validators.keys()).copied().collect();
+        let validators = validator_ids
+            .into_iter()
+            .filter_map(|id| change(id, before.validators.get(&id), after.validators.get(&id)))
+            .collect();
+
+        let ticket_ids: BTreeSet<u64> = before.tickets.keys().chain(after.tickets.keys()).copied().collect();
+        let tickets = ticket_ids
+            .into_iter()
+            .filter_map(|id| change(id, before.tickets.get(&id), after.tickets.get(&id)))
+            .collect();
+
+        let bucket_ids: BTreeSet<u64> = before.buckets.keys().chain(after.buckets.keys()).copied().collect();
+        let buckets = bucket_ids
+            .into_iter()
+            .filter_map(|id| bucket_change(id, before.buckets.get(&id), after.buckets.get(&id)))
+            .collect();
+
+        StateDiff {
+            epoch: changed(before.epoch_index, after.epoch_index),
+            sub_epoch: changed(before.sub_epoch_index, after.sub_epoch_index),
+            validators,
+            tickets,
+            buckets,
+            fields: field_changes(before, after),
+            entries: entry_changes(before, after),
+        }
+    }
+
+    /// What changed in `state` since `start` was taken. Records and keyed
+    /// maps are read from their journals, so this costs what changed rather
+    /// than what they hold.
+    pub fn since(start: &StateCheckpoint, state: &ChainState) -> Self {
+        let validators = state.validators.changes_since(start.validators);
+        let tickets = state.tickets.changes_since(start.tickets);
+        let buckets = state.buckets.changes_since(start.buckets);
+        StateDiff {
+            epoch: changed(start.fields.epoch_index, state.epoch_index),
+            sub_epoch: changed(start.fields.sub_epoch_index, state.sub_epoch_index),
+            validators: validators.into_iter().filter_map(|(id, b, a)| change(id, b, a)).collect(),
+            tickets: tickets.into_iter().filter_map(|(id, b, a)| change(id, b, a)).collect(),
+            buckets: buckets.into_iter().filter_map(|(id, b, a)| bucket_change(id, b, a)).collect(),
+            fields: field_changes(&start.fields, state),
+            entries: entry_changes_since(start, state),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == StateDiff::default()
+    }
+
+    /// Make the changes on `state`, taking it from the diff's `before` side to
+    /// its `after` side. Only what the diff covers is touched. Fails if a
+    /// field change names no field or does not decode into it, if a
+    /// replaced ticket is missing from `ticket_index`, or if a changed bucket
+    /// is neither in `state` nor created by the diff.
+    pub fn apply(&self, state: &mut ChainState) -> Result<(), SimError> {
+        self.set(state, true)
+    }
+
+    /// Undo the changes on `state`, taking it from the `after` side back to
+    /// the `before` side. Fails as `apply` does.
+    pub fn revert(&self, state: &mut ChainState) -> Result<(), SimError> {
+        self.set(state, false)
+    }
+
+    /// Set what the diff covers to its `after` side if `forward`, else to
+    /// its `before` side.
+    fn set(&self, state: &mut ChainState, forward: bool) -> Result<(), SimError> {
+        if let Some((b, a)) = self.epoch {
+            state.epoch_index = pick(forward, b, a);
+        }
+        if let Some((b, a)) = self.sub_epoch {
+            state.sub_epoch_index = pick(forward, b, a);
+        }
+        for change in &self.validators {
+            match pick(forward, &change.before, &change.after) {
+                Some(validator) => state.validators.insert(change.id, validator.clone()),
+                None => state.validators.remove(&change.id),
+            };
+        }
+        for change in &self.tickets {
+            if let Some(old) = state.tickets.remove(&change.id) {
+                state.ticket_index.remove(&old)?;
+            }
+            if let Some(ticket) = pick(forward, &change.before, &change.after) {
+                state.ticket_index.insert(ticket);
+                state.tickets.insert(change.id, ticket.clone());
+            }
+        }
+        for change in &self.buckets {
+            let (added, removed) = pick(forward, (&change.removed, &change.added), (&change.added, &change.removed));
+            let (created, dropped) = pick(forward, (change.dropped, change.created), (change.created, change.dropped));
+            if dropped {
+                state.buckets.remove(&change.id);
+                continue;
+            }
+            if created {
+                state.buckets.insert(change.id, Bucket { id: change.id, ticket_ids: BTreeSet::new() });
+            }
+            let bucket = state.buckets.get_mut(&change.id).ok_or(SimError::UnknownBucket(change.id))?;
+            bucket.ticket_ids.extend(added);
+            for id in removed {
+                bucket.ticket_ids.remove(id);
+            }
+        }
+        for change in &self.fields {
+            let value = pick(forward, &change.before, &change.after);
+            macro_rules! assign {
+                ($($field:ident),*) => {
+                    match change.field.as_str() {
+                        $(stringify!($field) => {
+                            state.$field = serde_json::from_value(value.clone()).map_err(|e| SimError::Encoding(e.to_string()))?
+                        })*
+                        other => return Err(SimError::Encoding(format!("unknown chain state field {:?}", other))),
+                    }
+                };
+            }
+            for_each_field!(assign);
+        }
+        for change in &self.entries {
+            let value = pick(forward, &change.before, &change.after).as_ref();
+            macro_rules! assign {
+                ($($field:ident),*) => {
+                    match change.field.as_str() {
+                        $(stringify!($field) => set_entry(&mut state.$field, change.id, value)?,)*
+                        other => return Err(SimError::Encoding(format!("unknown chain state map {:?}", other))),
+                    }
+                };
+            }
+            for_each_keyed_field!(assign);
+        }
+        Ok(())
+    }
+}
+
+fn pick<T>(forward: bool, before: T, after: T) -> T {
+    if forward { after } else { before }
+}
+
+fn changed(before: u64, after: u64) -> Option<(u64, u64)> {
+    (before != after).then_some((before, after))
+}
+
+fn change<T: Clone + PartialEq>(id: u64, before: Option<&T>, after: Option<&T>) -> Option<Change<T>> {
+    (before != after).then(|| Change { id, before: before.cloned(), after: after.cloned() })
+}
+
+fn bucket_change(id: u64, before: Option<&Bucket>, after: Option<&Bucket>) -> Option<BucketChange> {
+    let empty = BTreeSet::new();
+    let b = before.map_or(&empty, |b| &b.ticket_ids);
+    let a = after.map_or(&empty, |b| &b.ticket_ids);
+
+    let added: Vec<u64> = a.difference(b).copied().collect();
+    let removed: Vec<u64> = b.difference(a).copied().collect();
+    let (created, dropped) = (before.is_none() && after.is_some(), before.is_some() && after.is_none());
+    (!added.is_empty() || !removed.is_empty() || created || dropped)
+        .then_some(BucketChange { id, added, removed, created, dropped })
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn field_changes(before: &ChainState, after: &ChainState) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    macro_rules! compare {
+        ($($field:ident),*) => {
+            // Fails to compile when `ChainState` gains a field not listed here.
+            let ChainState {
+                validators: _,
+                tickets: _,
+                ticket_index: _,
+                buckets: _,
+                epoch_index: _,
+                sub_epoch_index: _,
+                pending_refills: _,
+                liveness: _,
+                rewards_paid: _,
+                $($field: _,)*
+            } = after;
+            $(
+                if before.$field != after.$field {
+                    changes.push(FieldChange::new(stringify!($field), &before.$field, &after.$field));
+                }
+            )*
+        };
+    }
+    for_each_field!(compare);
+    changes
+}
+
+fn entry_changes(before: &ChainState, after: &ChainState) -> Vec<EntryChange> {
+    let mut changes = Vec::new();
+    macro_rules! compare {
+        ($($field:ident),*) => {
+            $(
+                let ids: BTreeSet<u64> = before.$field.keys().chain(after.$field.keys()).copied().collect();
+                for id in ids {
+                    changes.extend(EntryChange::new(stringify!($field), id, before.$field.get(&id), after.$field.get(&id)));
+                }
+            )*
+        };
+    }
+    for_each_keyed_field!(compare);
+    changes
+}
+
+fn entry_changes_since(start: &StateCheckpoint, state: &ChainState) -> Vec<EntryChange> {
+    let mut changes = Vec::new();
+    macro_rules! journaled {
+        ($($field:ident),*) => {
+            $(
+                for (id, before, after) in state.$field.changes_since(start.keyed.$field) {
+                    changes.extend(EntryChange::new(stringify!($field), id, before, after));
+                }
+            )*
+        };
+    }
+    for_each_keyed_field!(journaled);
+    changes
+}
+
+/// Set `id` in `map` to `value`, decoded, or remove it for `None`.
+fn set_entry<T: DeserializeOwned + Clone>(map: &mut JournaledMap<T>, id: u64, value: Option<&Value>) -> Result<(), SimError> {
+    match value {
+        Some(value) => {
+            map.insert(id, serde_json::from_value(value.clone()).map_err(|e| SimError::Encoding(e.to_string()))?);
+        }
+        None => {
+            map.remove(&id);
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((b, a)) = self.epoch {
+            writeln!(f, "  epoch: {} -> {}", b, a)?;
+        }
+        if let Some((b, a)) = self.sub_epoch {
+            writeln!(f, "  sub_epoch: {} -> {}", b, a)?;
+        }
+        for c in &self.validators {
+            writeln!(f, "  validator {}: {:?} -> {:?}", c.id, c.before, c.after)?;
+        }
+        for c in &self.tickets {
+            writeln!(f, "  ticket {}: {:?} -> {:?}", c.id, c.before, c.after)?;
+        }
+        for c in &self.buckets {
+            let status = if c.created { " (created)" } else if c.dropped { " (dropped)" } else { "" };
+            writeln!(f, "  bucket {}{}: +{:?} -{:?}", c.id, status, c.added, c.removed)?;
+        }
+        for c in &self.fields {
+            writeln!(f, "  {}: {} -> {}", c.field, c.before, c.after)?;
+        }
+        let show = |v: &Option<Value>| v.as_ref().map_or("none".to_string(), Value::to_string);
+        for c in &self.entries {
+            writeln!(f, "  {} {}: {} -> {}", c.field, c.id, show(&c.before), show(&c.after))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+
+    #[test]
+    fn created_and_dropped_buckets_apply_and_revert() {
+        let before = Scenario::load("scenarios/default.toml").unwrap().build_state().unwrap();
+        let dropped = *before.buckets.keys().next().unwrap();
+        let mut after = before.clone();
+        after.buckets.remove(&dropped);
+        after.buckets.insert(900, Bucket { id: 900, ticket_ids: BTreeSet::from([7]) });
+        after.buckets.insert(901, Bucket { id: 901, ticket_ids: BTreeSet::new() });
+
+        let diff = StateDiff::between(&before, &after);
+        let mut applied = before.clone();
+        diff.apply(&mut applied).unwrap();
+        assert_eq!(applied, after);
+        let mut reverted = after.clone();
+        diff.revert(&mut reverted).unwrap();
+        assert_eq!(reverted, before);
+
+        let mut missing = before.clone();
+        missing.buckets.remove(&dropped);
+        let change = BucketChange { id: dropped, added: vec![7], removed: Vec::new(), created: false, dropped: false };
+        let diff = StateDiff { buckets: vec![change], ..StateDiff::default() };
+        assert_eq!(diff.apply(&mut missing), Err(SimError::UnknownBucket(dropped)));
+    }
+}
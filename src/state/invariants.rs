@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::economics::rewards::RewardDestination;
+use crate::economics::supply::check_conservation;
+use crate::state::chain_state::ChainState;
+use crate::state::diff::StateDiff;
+use crate::state::ticket_index::TicketCounts;
+use crate::types::event::SimEvent;
+use crate::types::ticket::TicketState;
+use crate::types::validator::ValidatorState;
+
+/// A consensus rule that must hold after every slot and epoch transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Invariant {
+    /// Every ticket is in exactly one bucket, and its `bucket` field agrees.
+    TicketInOneBucket,
+    /// Dead tickets sit in `dead_bucket_id`.
+    DeadTicketsInDeadBucket,
+    /// Retiring tickets sit in a MUTED bucket.
+    RetiringTicketsMuted,
+    /// Jailed validators own no non-Dead ticket.
+    JailedOwnNoLiveTickets,
+    /// PunishedCooldown validators know when their cooldown ends.
+    CooldownHasEnd,
+    /// Vault balances only grow through refills or rewards.
+    VaultGrowsOnlyByCredit,
+    /// Every `retire_schedule` epoch is still ahead.
+    RetireScheduleInFuture,
+    /// Issued + deposited supply equals what is held plus what was burned.
+    SupplyConserved,
+    /// `ticket_index` agrees with `tickets`.
+    TicketIndexConsistent,
+}
+
+impl Invariant {
+    pub const ALL: [Invariant; 9] = [
+        Invariant::TicketInOneBucket,
+        Invariant::DeadTicketsInDeadBucket,
+        Invariant::RetiringTicketsMuted,
+        Invariant::JailedOwnNoLiveTickets,
+        Invariant::CooldownHasEnd,
+        Invariant::VaultGrowsOnlyByCredit,
+        Invariant::RetireScheduleInFuture,
+        Invariant::SupplyConserved,
+        Invariant::TicketIndexConsistent,
+    ];
+}
+
+/// A broken invariant, with the state change that broke it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub invariant: Invariant,
+    pub slot: u64,
+    pub epoch: u64,
+    pub detail: String,
+    /// Change from the start of the slot to the point of the check.
+    pub diff: StateDiff,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invariant {:?} violated at slot {} (epoch {}): {}\nstate diff:\n{}",
+            self.invariant, self.slot, self.epoch, self.detail, self.diff
+        )
+    }
+}
+
+/// Checks a set of invariants; all of them by default.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invariants {
+    pub enabled: Vec<Invariant>,
+}
+
+impl Default for Invariants {
+    fn default() -> Self {
+        Invariants { enabled: Invariant::ALL.to_vec() }
+    }
+}
+
+impl Invariants {
+    /// Check `after`, reached through `changes` and `events`. Returns the
+    /// first violated invariant (in `Invariant::ALL` order) and what broke it.
+    pub fn check(&self, after: &ChainState, changes: &StateDiff, events: &[SimEvent]) -> Result<(), (Invariant, String)> {
+        for &invariant in &Invariant::ALL {
+            if !self.enabled.contains(&invariant) {
+                continue;
+            }
+            let result = match invariant {
+                Invariant::TicketInOneBucket => check_ticket_in_one_bucket(after),
+                Invariant::DeadTicketsInDeadBucket => check_dead_tickets(after),
+                Invariant::RetiringTicketsMuted => check_retiring_tickets(after),
+                Invariant::JailedOwnNoLiveTickets => check_jailed_tickets(after),
+                Invariant::CooldownHasEnd => check_cooldowns(after),
+                Invariant::VaultGrowsOnlyByCredit => check_vault_growth(changes, events),
+                Invariant::RetireScheduleInFuture => check_retire_schedule(after),
+                Invariant::SupplyConserved => check_conservation(after),
+                Invariant::TicketIndexConsistent => check_ticket_index(after, changes),
+            };
+            result.map_err(|detail| (invariant, detail))?;
+        }
+        Ok(())
+    }
+}
+
+fn check_ticket_in_one_bucket(state: &ChainState) -> Result<(), String> {
+    let mut location: BTreeMap<u64, u64> = BTreeMap::new();
+    for (&bucket_id, bucket) in &state.buckets {
+        for &tid in &bucket.ticket_ids {
+            if !state.tickets.contains_key(&tid) {
+                return Err(format!("bucket {} holds unknown ticket {}", bucket_id, tid));
+            }
+            if let Some(first) = location.insert(tid, bucket_id) {
+                return Err(format!("ticket {} is in buckets {} and {}", tid, first, bucket_id));
+            }
+        }
+    }
+
+    for (&tid, t) in &state.tickets {
+        match location.get(&tid) {
+            None => return Err(format!("ticket {} is in no bucket", tid)),
+            Some(&actual) if actual != t.bucket => {
+                return Err(format!("ticket {} says bucket {} but is in bucket {}", tid, t.bucket, actual));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_dead_tickets(state: &ChainState) -> Result<(), String> {
+    for (&tid, t) in &state.tickets {
+        if t.state == TicketState::Dead && t.bucket != state.dead_bucket_id {
+            return Err(format!("Dead ticket {} is in bucket {}", tid, t.bucket));
+        }
+    }
+    Ok(())
+}
+
+fn check_retiring_tickets(state: &ChainState) -> Result<(), String> {
+    for (&tid, t) in &state.tickets {
+        if t.state == TicketState::Retiring && !state.muted_bucket_ids.contains(&t.bucket) {
+            return Err(format!("Retiring ticket {} is in non-MUTED bucket {}", tid, t.bucket));
+        }
+    }
+    Ok(())
+}
+
+fn check_jailed_tickets(state: &ChainState) -> Result<(), String> {
+    for (&tid, t) in &state.tickets {
+        let jailed = state.validators.get(&t.owner).is_some_and(|v| v.state == ValidatorState::Jailed);
+        if jailed && t.state != TicketState::Dead {
+            return Err(format!("jailed validator {} owns {:?} ticket {}", t.owner, t.state, tid));
+        }
+    }
+    Ok(())
+}
+
+fn check_cooldowns(state: &ChainState) -> Result<(), String> {
+    for (&vid, v) in &state.validators {
+        if v.state == ValidatorState::PunishedCooldown && v.cooldown_until_epoch.is_none() {
+            return Err(format!("validator {} is in cooldown with no end epoch", vid));
+        }
+    }
+    Ok(())
+}
+
+fn check_vault_growth(changes: &StateDiff, events: &[SimEvent]) -> Result<(), String> {
+    let mut credited: BTreeMap<u64, u128> = BTreeMap::new();
+    for event in events {
+        match event {
+            SimEvent::VaultRefilled { validator, amount, .. }
+            | SimEvent::RewardPaid { validator, amount, destination: RewardDestination::Vault } => {
+                let total = credited.entry(*validator).or_default();
+                *total = total.saturating_add(*amount);
+            }
+            _ => {}
+        }
+    }
+
+    for change in &changes.validators {
+        let (Some(prev), Some(v)) = (&change.before, &change.after) else { continue; };
+        let vid = change.id;
+        let credit = credited.get(&vid).copied().unwrap_or(0);
+        if v.vault_balance > prev.vault_balance.saturating_add(credit) {
+            return Err(format!(
+                "validator {} vault grew from {} to {} with {} credited",
+                vid, prev.vault_balance, v.vault_balance, credit
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn check_retire_schedule(state: &ChainState) -> Result<(), String> {
+    match state.retire_schedule.keys().next() {
+        Some(&epoch) if epoch <= state.epoch_index => Err(format!(
+            "retirement scheduled for epoch {} at epoch {}",
+            epoch, state.epoch_index
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Check the index against the tickets, where `changes` touched it: the
+/// buckets and owners of every changed ticket, before and after. Entries no
+/// ticket change touched were checked when they last changed.
+fn check_ticket_index(state: &ChainState, changes: &StateDiff) -> Result<(), String> {
+    let index = &state.ticket_index;
+    let mut in_buckets = TicketCounts::default();
+    for &bucket_id in state.buckets.keys() {
+        in_buckets += index.bucket_counts(bucket_id);
+    }
+    if index.counts() != in_buckets || in_buckets.total() != state.tickets.len() as u64 {
+        return Err(format!(
+            "ticket counts are {:?} over buckets counting {:?}, for {} tickets",
+            index.counts(),
+            in_buckets,
+            state.tickets.len()
+        ));
+    }
+
+    let touched = changes.tickets.iter().flat_map(|c| c.before.iter().chain(&c.after));
+    let (buckets, owners): (BTreeSet<u64>, BTreeSet<u64>) = touched.map(|t| (t.bucket, t.owner)).unzip();
+    for bucket_id in buckets {
+        let held = state.buckets.get(&bucket_id).map(|b| &b.ticket_ids);
+        let actual: TicketCounts = held.into_iter().flatten().filter_map(|tid| state.tickets.get(tid)).collect();
+        let indexed = index.bucket_counts(bucket_id);
+        if indexed != actual {
+            return Err(format!("index counts {:?} in bucket {}, expected {:?}", indexed, bucket_id, actual));
+        }
+    }
+
+    for change in &changes.tickets {
+        if let Some(t) = &change.after
+            && !index.owns(t.owner, t.id)
+        {
+            return Err(format!("index does not list ticket {} of validator {}", t.id, t.owner));
+        }
+    }
+    for owner in owners {
+        let mut owned = Vec::new();
+        for tid in index.owned_by(owner) {
+            match state.tickets.get(&tid) {
+                Some(t) if t.owner == owner => owned.push(t),
+                _ => return Err(format!("index lists ticket {} for validator {}, which does not own it", tid, owner)),
+            }
+        }
+        let active: Vec<_> = owned.iter().filter(|t| t.state == TicketState::Active).collect();
+        if index.active_count(owner) != active.len() as u64 {
+            return Err(format!(
+                "index counts {} Active tickets for validator {}, expected {}",
+                index.active_count(owner),
+                owner,
+                active.len()
+            ));
+        }
+        let eligible = active.iter().filter(|t| state.active_bucket_ids.contains(&t.bucket)).count() as u64;
+        if index.eligible_count(owner) != eligible {
+            return Err(format!(
+                "index counts {} eligible tickets for validator {}, expected {}",
+                index.eligible_count(owner),
+                owner,
+                eligible
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SimError;
+    use crate::scenario::config::Scenario;
+    use crate::sim::timeline::{Action, TimelineEvent, Trigger};
+
+    fn state() -> ChainState {
+        Scenario::load("scenarios/default.toml").unwrap().build_state().unwrap()
+    }
+
+    #[test]
+    fn vault_may_only_grow_by_refills() {
+        let before = state();
+        let mut after = before.clone();
+        after.validators.get_mut(&2).unwrap().vault_balance += 100;
+
+        let invariants = Invariants { enabled: vec![Invariant::VaultGrowsOnlyByCredit] };
+        let err = invariants.check(&after, &StateDiff::between(&before, &after), &[]).unwrap_err();
+        assert_eq!(err.0, Invariant::VaultGrowsOnlyByCredit);
+
+        let refill = SimEvent::VaultRefilled { validator: 2, amount: 100, new_vault: 0 };
+        assert!(invariants.check(&after, &StateDiff::between(&before, &after), &[refill]).is_ok());
+    }
+
+    #[test]
+    fn refills_near_the_vault_limit_keep_the_invariant() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        sim.invariants = Some(Invariants { enabled: vec![Invariant::VaultGrowsOnlyByCredit] });
+        sim.state.rewards.block_reward = 0;
+        sim.state.validators.get_mut(&2).unwrap().vault_balance = u128::MAX - 1_000;
+        for amount in [900, 200] {
+            sim.timeline.push(TimelineEvent { at: Trigger::Slot(0), action: Action::VaultRefill { validator: 2, amount } });
+        }
+
+        sim.run_one_slot().unwrap();
+
+        assert_eq!(sim.applied_events[0].error, None);
+        assert_eq!(sim.applied_events[1].error, Some(SimError::BalanceOverflow { validator: 2, amount: 200 }.to_string()));
+        assert_eq!(sim.state.validators[&2].vault_balance, u128::MAX - 100);
+    }
+
+    #[test]
+    fn misplaced_ticket_is_reported() {
+        let before = state();
+        let mut after = before.clone();
+        after.tickets.get_mut(&1).unwrap().state = TicketState::Dead;
+
+        let err = Invariants::default().check(&after, &StateDiff::between(&before, &after), &[]).unwrap_err();
+        assert_eq!(err.0, Invariant::DeadTicketsInDeadBucket);
+    }
+
+    #[test]
+    fn stale_ticket_index_is_reported() {
+        let before = state();
+        let mut after = before.clone();
+        // Move ticket 1 to a MUTED bucket behind the index's back.
+        let from = after.tickets[&1].bucket;
+        let to = *after.muted_bucket_ids.iter().next().unwrap();
+        after.buckets.get_mut(&from).unwrap().ticket_ids.remove(&1);
+        after.buckets.get_mut(&to).unwrap().ticket_ids.insert(1);
+        after.tickets.get_mut(&1).unwrap().bucket = to;
+
+        let err = Invariants::default().check(&after, &StateDiff::between(&before, &after), &[]).unwrap_err();
+        assert_eq!(err.0, Invariant::TicketIndexConsistent);
+        assert!(err.1.starts_with("index counts") && err.1.contains(&format!("in bucket {}", from.min(to))), "{}", err.1);
+
+        // A ticket the index lost is caught whether or not it changed.
+        let mut after = before.clone();
+        let ticket = after.tickets[&2].clone();
+        after.ticket_index.remove(&ticket).unwrap();
+        let index = after.ticket_index.clone();
+        assert!(matches!(after.ticket_index.remove(&ticket), Err(SimError::TicketNotIndexed(2))));
+        assert_eq!(after.ticket_index, index);
+        let err = Invariants::default().check(&after, &StateDiff::default(), &[]).unwrap_err();
+        assert_eq!(err.0, Invariant::TicketIndexConsistent);
+        assert!(err.1.starts_with("ticket counts"), "{}", err.1);
+    }
+}
@@ -0,0 +1,295 @@
+//! Id-keyed maps that can be rolled back and diffed without a copy.
+use std::collections::{btree_map, BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Index;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A key's value before a change; `None` if the key was absent.
+#[derive(Clone)]
+struct Undo<V> {
+    key: u64,
+    before: Option<V>,
+    /// Index of the key's previous entry in the log, if any.
+    previous: Option<usize>,
+}
+
+/// A `BTreeMap<u64, V>` that logs each key's value before the first change
+/// after every `mark`. Changes since a mark can be listed with `changes_since`
+/// and undone with `rollback`, at a cost proportional to what changed.
+///
+/// Serialises as the plain map. Equality compares contents only. A clone or
+/// a deserialised map starts with an empty log and no known dirty keys.
+pub struct JournaledMap<V> {
+    map: BTreeMap<u64, V>,
+    log: Vec<Undo<V>>,
+    /// Index in `log` of each key's latest entry.
+    latest: BTreeMap<u64, usize>,
+    /// Length of `log` at the latest mark; keys logged at or after it are
+    /// not logged again.
+    mark: usize,
+    /// Keys changed or rolled back since the last `clear_dirty`; `None`
+    /// when not known.
+    dirty: Option<BTreeSet<u64>>,
+}
+
+impl<V: Clone> JournaledMap<V> {
+    pub fn get(&self, key: &u64) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    pub fn contains_key(&self, key: &u64) -> bool {
+        self.map.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn keys(&self) -> btree_map::Keys<'_, u64, V> {
+        self.map.keys()
+    }
+
+    pub fn values(&self) -> btree_map::Values<'_, u64, V> {
+        self.map.values()
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, u64, V> {
+        self.map.iter()
+    }
+
+    /// Mutable access, logging the value first. Only call it to change
+    /// the value: every call marks the key dirty.
+    pub fn get_mut(&mut self, key: &u64) -> Option<&mut V> {
+        if !self.map.contains_key(key) {
+            return None;
+        }
+        self.log(*key);
+        self.map.get_mut(key)
+    }
+
+    pub fn insert(&mut self, key: u64, value: V) -> Option<V> {
+        self.log(key);
+        self.map.insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &u64) -> Option<V> {
+        if !self.map.contains_key(key) {
+            return None;
+        }
+        self.log(*key);
+        self.map.remove(key)
+    }
+
+    /// Mutable access to `key`'s value, inserting the default first if it
+    /// has none. Logs as `get_mut` does.
+    pub fn get_or_default(&mut self, key: u64) -> &mut V
+    where
+        V: Default,
+    {
+        self.log(key);
+        self.map.entry(key).or_default()
+    }
+
+    /// Remove every entry, logging each, and return them.
+    pub fn take(&mut self) -> BTreeMap<u64, V> {
+        let keys: Vec<u64> = self.map.keys().copied().collect();
+        for key in keys {
+            self.log(key);
+        }
+        std::mem::take(&mut self.map)
+    }
+
+    fn log(&mut self, key: u64) {
+        if let Some(dirty) = &mut self.dirty {
+            dirty.insert(key);
+        }
+        if self.latest.get(&key).is_some_and(|&i| i >= self.mark) {
+            return;
+        }
+        let previous = self.latest.insert(key, self.log.len());
+        self.log.push(Undo { key, before: self.map.get(&key).cloned(), previous });
+    }
+
+    /// Start a new span of changes; pass the result to `changes_since` or
+    /// `rollback`.
+    pub fn mark(&mut self) -> usize {
+        self.mark = self.log.len();
+        self.mark
+    }
+
+    /// Undo every change made since `mark`. `undone` sees each undone
+    /// key's current value and the value it goes back to.
+    pub fn rollback(&mut self, mark: usize, mut undone: impl FnMut(Option<&V>, Option<&V>)) {
+        while self.log.len() > mark {
+            let Some(Undo { key, before, previous }) = self.log.pop() else { break };
+            if let Some(dirty) = &mut self.dirty {
+                dirty.insert(key);
+            }
+            undone(self.map.get(&key), before.as_ref());
+            match before {
+                Some(value) => self.map.insert(key, value),
+                None => self.map.remove(&key),
+            };
+            match previous {
+                Some(i) => self.latest.insert(key, i),
+                None => self.latest.remove(&key),
+            };
+        }
+        self.mark = mark;
+    }
+
+    /// Each key changed since `mark`, ascending, with its value at `mark`
+    /// and now. A key set back to its old value is still listed.
+    pub fn changes_since(&self, mark: usize) -> Vec<(u64, Option<&V>, Option<&V>)> {
+        let mut first: BTreeMap<u64, Option<&V>> = BTreeMap::new();
+        for undo in &self.log[mark.min(self.log.len())..] {
+            first.entry(undo.key).or_insert(undo.before.as_ref());
+        }
+        first.into_iter().map(|(key, before)| (key, before, self.map.get(&key))).collect()
+    }
+
+    /// Drop the log: nothing before this point can be rolled back any more.
+    pub fn commit(&mut self) {
+        self.log.clear();
+        self.latest.clear();
+        self.mark = 0;
+    }
+
+    /// Keys changed or rolled back since the last `clear_dirty`, or `None`
+    /// if they are not known: on a new, cloned or deserialised map that was
+    /// never cleared.
+    pub fn dirty(&self) -> Option<&BTreeSet<u64>> {
+        self.dirty.as_ref()
+    }
+
+    /// Forget the dirty keys and track changes from here on.
+    pub fn clear_dirty(&mut self) {
+        self.dirty = Some(BTreeSet::new());
+    }
+}
+
+impl<V> Default for JournaledMap<V> {
+    fn default() -> Self {
+        BTreeMap::new().into()
+    }
+}
+
+impl<V> From<BTreeMap<u64, V>> for JournaledMap<V> {
+    fn from(map: BTreeMap<u64, V>) -> Self {
+        JournaledMap { map, log: Vec::new(), latest: BTreeMap::new(), mark: 0, dirty: None }
+    }
+}
+
+impl<V: Clone> Clone for JournaledMap<V> {
+    fn clone(&self) -> Self {
+        self.map.clone().into()
+    }
+}
+
+impl<V: PartialEq> PartialEq for JournaledMap<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for JournaledMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<V> Index<&u64> for JournaledMap<V> {
+    type Output = V;
+
+    fn index(&self, key: &u64) -> &V {
+        &self.map[key]
+    }
+}
+
+impl<'a, V> IntoIterator for &'a JournaledMap<V> {
+    type Item = (&'a u64, &'a V);
+    type IntoIter = btree_map::Iter<'a, u64, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
+
+impl<V: Serialize> Serialize for JournaledMap<V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.map.serialize(serializer)
+    }
+}
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for JournaledMap<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        BTreeMap::deserialize(deserializer).map(JournaledMap::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollback_restores_values_from_a_mark() {
+        let mut map: JournaledMap<u32> = BTreeMap::from([(1, 10), (2, 20)]).into();
+        let start = map.mark();
+        *map.get_mut(&1).unwrap() += 1;
+        map.insert(3, 30);
+
+        let inner = map.mark();
+        *map.get_mut(&1).unwrap() += 1;
+        map.remove(&2);
+        assert_eq!(map.changes_since(inner), vec![(1, Some(&11), Some(&12)), (2, Some(&20), None)]);
+
+        let mut undone = Vec::new();
+        map.rollback(inner, |now, then| undone.push((now.copied(), then.copied())));
+        assert_eq!(undone, vec![(None, Some(20)), (Some(12), Some(11))]);
+        assert_eq!(map.changes_since(start), vec![(1, Some(&10), Some(&11)), (3, None, Some(&30))]);
+
+        map.rollback(start, |_, _| {});
+        assert_eq!(map, BTreeMap::from([(1, 10), (2, 20)]).into());
+        assert!(map.changes_since(start).is_empty());
+    }
+
+    #[test]
+    fn dirty_keys_are_known_once_taken() {
+        let mut map: JournaledMap<u32> = BTreeMap::from([(1, 10)]).into();
+        map.insert(2, 20);
+        assert_eq!(map.dirty(), None);
+
+        map.clear_dirty();
+        *map.get_mut(&1).unwrap() += 1;
+        map.commit();
+        map.remove(&5);
+        assert_eq!(map.dirty(), Some(&BTreeSet::from([1])));
+
+        map.clear_dirty();
+        let start = map.mark();
+        map.insert(3, 30);
+        map.clear_dirty();
+        map.rollback(start, |_, _| {});
+        assert_eq!(map.dirty(), Some(&BTreeSet::from([3])));
+        assert_eq!(map.clone().dirty(), None);
+    }
+
+    #[test]
+    fn take_and_default_entries_roll_back() {
+        let mut map: JournaledMap<u32> = BTreeMap::from([(1, 10)]).into();
+        let start = map.mark();
+        *map.get_or_default(2) += 5;
+        *map.get_or_default(1) += 1;
+        assert_eq!(map.take(), BTreeMap::from([(1, 11), (2, 5)]));
+        assert!(map.is_empty());
+        assert_eq!(map.changes_since(start), vec![(1, Some(&10), None), (2, None, None)]);
+
+        map.rollback(start, |_, _| {});
+        assert_eq!(map, BTreeMap::from([(1, 10)]).into());
+    }
+}
@@ -0,0 +1,662 @@
+//! Merkle commitment to the chain state, with inclusion and exclusion proofs
+//! for single validator and ticket records.
+//!
+//! The state root commits to three parts: a Merkle tree over validator
+//! records, one over ticket records (leaves sorted by id), and `body`, the
+//! canonical hash of the rest of the state from `state::root`, plus the
+//! number of leaves in each tree. `body` in turn commits to a tree over the
+//! entries of each keyed map, such as `liveness`. A record is proven present by its path to
+//! its tree's root, and absent by the paths of the two adjacent leaves whose
+//! ids enclose it. Paths are checked against the committed leaf counts.
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::SimError;
+use crate::state::chain_state::{for_each_keyed_field, ChainState};
+use crate::state::journal::JournaledMap;
+use crate::state::root::{body_hasher, finish_body};
+use crate::types::hex;
+use crate::types::ticket::Ticket;
+use crate::types::validator::Validator;
+
+/// A record that has its own tree in the state commitment.
+pub trait Record: Serialize {
+    /// Domain tag of the record's leaves.
+    const TREE: &'static [u8];
+
+    fn id(&self) -> u64;
+
+    /// Root of the record's tree among the parts of a state root.
+    fn tree_root(parts: &RootParts) -> [u8; 32];
+
+    /// Number of leaves in the record's tree.
+    fn leaf_count(parts: &RootParts) -> u64;
+}
+
+impl Record for Validator {
+    const TREE: &'static [u8] = b"eternix/validator";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn tree_root(parts: &RootParts) -> [u8; 32] {
+        parts.validators
+    }
+
+    fn leaf_count(parts: &RootParts) -> u64 {
+        parts.validator_count
+    }
+}
+
+impl Record for Ticket {
+    const TREE: &'static [u8] = b"eternix/ticket";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn tree_root(parts: &RootParts) -> [u8; 32] {
+        parts.tickets
+    }
+
+    fn leaf_count(parts: &RootParts) -> u64 {
+        parts.ticket_count
+    }
+}
+
+/// Hash of a record's canonical (JSON) encoding.
+pub fn record_hash<T: Record>(record: &T) -> Result<[u8; 32], SimError> {
+    let mut hasher = Sha256::new();
+    serde_json::to_writer(&mut hasher, record).map_err(|e| SimError::Encoding(format!("record {}: {}", record.id(), e)))?;
+    Ok(hasher.finalize().into())
+}
+
+/// Hash of the canonical (JSON) encoding of entry `id` of keyed map `field`.
+fn entry_hash<V: Serialize>(field: &str, id: u64, value: &V) -> Result<[u8; 32], SimError> {
+    let mut hasher = Sha256::new();
+    serde_json::to_writer(&mut hasher, value).map_err(|e| SimError::Encoding(format!("{} {}: {}", field, id, e)))?;
+    Ok(hasher.finalize().into())
+}
+
+fn leaf_hash(tree: &[u8], id: u64, record_hash: [u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0]);
+    hasher.update(tree);
+    hasher.update(id.to_be_bytes());
+    hasher.update(record_hash);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([1]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Binary Merkle tree over leaves sorted by id. A node without a sibling is
+/// carried up unchanged; the root of an empty tree is all zero.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleTree {
+    /// `(id, record hash)` of every leaf, in id order.
+    leaves: Vec<(u64, [u8; 32])>,
+    /// `levels[0]` are the leaf hashes, the last level is the root.
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub fn build<'a, T: Record + 'a>(records: impl IntoIterator<Item = &'a T>) -> Result<MerkleTree, SimError> {
+        let mut leaves: Vec<(u64, [u8; 32])> =
+            records.into_iter().map(|r| Ok((r.id(), record_hash(r)?))).collect::<Result<_, SimError>>()?;
+        leaves.sort_unstable_by_key(|&(id, _)| id);
+        Ok(MerkleTree::from_leaves(T::TREE, leaves))
+    }
+
+    /// Tree over `leaves`, which must be in id order, tagged `tree`.
+    fn from_leaves(tree: &[u8], leaves: Vec<(u64, [u8; 32])>) -> MerkleTree {
+        let mut merkle = MerkleTree { leaves, levels: Vec::new() };
+        merkle.rehash(tree, BTreeSet::new(), 0);
+        merkle
+    }
+
+    /// Set the record hash of each `(id, hash)` in `changes`, in id order,
+    /// adding leaves for new ids and removing those whose hash is `None`.
+    /// Rehashes the paths above changed leaves, and every path right of the
+    /// first leaf added or removed.
+    fn update(&mut self, tree: &[u8], changes: impl IntoIterator<Item = (u64, Option<[u8; 32]>)>) {
+        let mut touched = BTreeSet::new();
+        // Leaves from here on may have moved.
+        let mut shifted = self.leaves.len();
+        for (id, hash) in changes {
+            match (self.leaves.binary_search_by_key(&id, |&(leaf_id, _)| leaf_id), hash) {
+                (Ok(index), Some(hash)) => {
+                    self.leaves[index].1 = hash;
+                    touched.insert(index);
+                }
+                (Ok(index), None) => {
+                    self.leaves.remove(index);
+                    shifted = shifted.min(index);
+                }
+                (Err(index), Some(hash)) => {
+                    self.leaves.insert(index, (id, hash));
+                    shifted = shifted.min(index);
+                }
+                (Err(_), None) => {}
+            }
+        }
+        self.rehash(tree, touched, shifted);
+    }
+
+    /// Recompute the nodes above the leaves in `touched` and above every leaf
+    /// from `from` on; the rest of `levels` must still fit the leaves.
+    fn rehash(&mut self, tree: &[u8], mut touched: BTreeSet<usize>, mut from: usize) {
+        touched.retain(|&i| i < from);
+        if self.levels.is_empty() {
+            self.levels.push(Vec::new());
+        }
+        let leaves = &self.leaves;
+        let level = &mut self.levels[0];
+        level.resize(leaves.len(), [0; 32]);
+        for i in touched.iter().copied().chain(from..leaves.len()) {
+            let (id, hash) = leaves[i];
+            level[i] = leaf_hash(tree, id, hash);
+        }
+
+        let mut depth = 0;
+        while self.levels[depth].len() > 1 {
+            touched = touched.iter().map(|i| i / 2).collect();
+            from /= 2;
+            if self.levels.len() == depth + 1 {
+                self.levels.push(Vec::new());
+            }
+            let (lower, upper) = self.levels.split_at_mut(depth + 1);
+            let (below, level) = (&lower[depth], &mut upper[0]);
+            level.resize(below.len().div_ceil(2), [0; 32]);
+            for i in touched.iter().copied().chain(from..level.len()) {
+                level[i] = match below.get(2 * i + 1) {
+                    Some(right) => node_hash(&below[2 * i], right),
+                    None => below[2 * i],
+                };
+            }
+            depth += 1;
+        }
+        self.levels.truncate(depth + 1);
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().and_then(|level| level.first()).copied().unwrap_or_default()
+    }
+
+    fn path(&self, index: usize) -> LeafPath {
+        let mut siblings = Vec::new();
+        let mut i = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(i ^ 1) {
+                siblings.push(*sibling);
+            }
+            i /= 2;
+        }
+        LeafPath { index: index as u64, leaf_count: self.leaves.len() as u64, siblings }
+    }
+
+    fn neighbour(&self, index: usize) -> Neighbour {
+        let (id, record_hash) = self.leaves[index];
+        Neighbour { id, record_hash, path: self.path(index) }
+    }
+
+    /// Inclusion proof for `id` if it has a leaf, exclusion proof otherwise.
+    pub fn membership(&self, id: u64) -> Membership {
+        match self.leaves.binary_search_by_key(&id, |&(leaf_id, _)| leaf_id) {
+            Ok(index) => Membership::Included(self.path(index)),
+            Err(index) => Membership::Excluded {
+                lower: index.checked_sub(1).map(|i| self.neighbour(i)),
+                upper: (index < self.leaves.len()).then(|| self.neighbour(index)),
+                leaf_count: self.leaves.len() as u64,
+            },
+        }
+    }
+}
+
+/// Siblings from a leaf up to the root, skipping levels where the node has none.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeafPath {
+    pub index: u64,
+    pub leaf_count: u64,
+    #[serde(with = "hex::list")]
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl LeafPath {
+    /// Root reached from `leaf`, or `None` if the path does not fit the tree shape.
+    fn root(&self, leaf: [u8; 32]) -> Option<[u8; 32]> {
+        if self.index >= self.leaf_count {
+            return None;
+        }
+        let mut siblings = self.siblings.iter();
+        let (mut hash, mut index, mut width) = (leaf, self.index, self.leaf_count);
+        while width > 1 {
+            if index % 2 == 1 {
+                hash = node_hash(siblings.next()?, &hash);
+            } else if index + 1 < width {
+                hash = node_hash(&hash, siblings.next()?);
+            }
+            index /= 2;
+            width = width.div_ceil(2);
+        }
+        siblings.next().is_none().then_some(hash)
+    }
+}
+
+/// A leaf next to where an absent id would sit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Neighbour {
+    pub id: u64,
+    #[serde(with = "hex")]
+    pub record_hash: [u8; 32],
+    pub path: LeafPath,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Membership {
+    Included(LeafPath),
+    /// The leaves just below and above the id; `None` past either end of the tree.
+    Excluded { lower: Option<Neighbour>, upper: Option<Neighbour>, leaf_count: u64 },
+}
+
+/// The three hashes a state root is made of, and the size of each tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootParts {
+    #[serde(with = "hex")]
+    pub validators: [u8; 32],
+    pub validator_count: u64,
+    #[serde(with = "hex")]
+    pub tickets: [u8; 32],
+    pub ticket_count: u64,
+    /// `state::root::body_hash` of the state outside the records.
+    #[serde(with = "hex")]
+    pub body: [u8; 32],
+}
+
+impl RootParts {
+    pub fn root(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"eternix/state");
+        hasher.update(self.validators);
+        hasher.update(self.validator_count.to_be_bytes());
+        hasher.update(self.tickets);
+        hasher.update(self.ticket_count.to_be_bytes());
+        hasher.update(self.body);
+        hasher.finalize().into()
+    }
+}
+
+/// A tree over the entries of each `chain_state::for_each_keyed_field` map,
+/// committed to by the body hash.
+#[derive(Debug, Clone, Default)]
+pub struct KeyedTrees {
+    pub pending_refills: MerkleTree,
+    pub liveness: MerkleTree,
+    pub rewards_paid: MerkleTree,
+}
+
+/// Leaf tag of keyed map `$field`'s tree.
+macro_rules! keyed_tag {
+    ($field:ident) => {
+        concat!("eternix/", stringify!($field)).as_bytes()
+    };
+}
+
+impl KeyedTrees {
+    pub fn build(state: &ChainState) -> Result<KeyedTrees, SimError> {
+        macro_rules! build {
+            ($($field:ident),*) => {
+                KeyedTrees { $($field: LeafUpdate::rebuild(&state.$field, |id, v| entry_hash(stringify!($field), id, v))?.into_tree(keyed_tag!($field)),)* }
+            };
+        }
+        Ok(for_each_keyed_field!(build))
+    }
+
+    /// New leaves of each tree, in `for_each_keyed_field` order.
+    fn updates(state: &ChainState) -> Result<Vec<LeafUpdate>, SimError> {
+        macro_rules! updates {
+            ($($field:ident),*) => {
+                vec![$(LeafUpdate::of(&state.$field, |id, v| entry_hash(stringify!($field), id, v))?,)*]
+            };
+        }
+        Ok(for_each_keyed_field!(updates))
+    }
+
+    fn apply(&mut self, updates: Vec<LeafUpdate>) {
+        macro_rules! apply {
+            ($($field:ident),*) => {
+                let mut updates = updates.into_iter();
+                $(
+                    if let Some(update) = updates.next() {
+                        update.apply(keyed_tag!($field), &mut self.$field);
+                    }
+                )*
+            };
+        }
+        for_each_keyed_field!(apply);
+    }
+
+    /// `(name, root, leaf count)` of each tree, in `for_each_keyed_field` order.
+    pub fn roots(&self) -> Vec<(&'static str, [u8; 32], u64)> {
+        macro_rules! roots {
+            ($($field:ident),*) => {
+                vec![$((stringify!($field), self.$field.root(), self.$field.leaves.len() as u64),)*]
+            };
+        }
+        for_each_keyed_field!(roots)
+    }
+}
+
+/// The validator and ticket trees of one chain state, and the trees of its
+/// keyed maps.
+#[derive(Debug, Clone, Default)]
+pub struct StateTree {
+    pub validators: MerkleTree,
+    pub tickets: MerkleTree,
+    pub keyed: KeyedTrees,
+    pub body: [u8; 32],
+}
+
+impl StateTree {
+    pub fn build(state: &ChainState) -> Result<StateTree, SimError> {
+        let keyed = KeyedTrees::build(state)?;
+        Ok(StateTree {
+            validators: MerkleTree::build(state.validators.values())?,
+            tickets: MerkleTree::build(state.tickets.values())?,
+            body: finish_body(body_hasher(state)?, &keyed),
+            keyed,
+        })
+    }
+
+    /// Bring the tree up to date with `state`, the state it was last built
+    /// or updated from: rehash only the records and keyed-map entries their
+    /// maps list as dirty, then start the dirty lists afresh. A tree whose
+    /// map does not know its dirty keys, e.g. after a snapshot resume, is
+    /// rebuilt. On error neither the tree nor the dirty lists change.
+    pub fn update(&mut self, state: &mut ChainState) -> Result<(), SimError> {
+        let validators = LeafUpdate::of(&state.validators, |_, v| record_hash(v))?;
+        let tickets = LeafUpdate::of(&state.tickets, |_, t| record_hash(t))?;
+        let keyed = KeyedTrees::updates(state)?;
+        let body = body_hasher(state)?;
+
+        validators.apply(Validator::TREE, &mut self.validators);
+        tickets.apply(Ticket::TREE, &mut self.tickets);
+        self.keyed.apply(keyed);
+        self.body = finish_body(body, &self.keyed);
+        state.validators.clear_dirty();
+        state.tickets.clear_dirty();
+        macro_rules! clear_dirty {
+            ($($field:ident),*) => { $(state.$field.clear_dirty();)* };
+        }
+        for_each_keyed_field!(clear_dirty);
+        Ok(())
+    }
+
+    pub fn parts(&self) -> RootParts {
+        RootParts {
+            validators: self.validators.root(),
+            validator_count: self.validators.leaves.len() as u64,
+            tickets: self.tickets.root(),
+            ticket_count: self.tickets.leaves.len() as u64,
+            body: self.body,
+        }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.parts().root()
+    }
+
+    /// Proof of validator `id`'s record in `state`, or of its absence.
+    /// `state` must be the one the tree was built from.
+    pub fn prove_validator(&self, state: &ChainState, id: u64) -> Proof<Validator> {
+        Proof { id, record: state.validators.get(&id).cloned(), membership: self.validators.membership(id), parts: self.parts() }
+    }
+
+    /// Proof of ticket `id`'s record in `state`, or of its absence.
+    pub fn prove_ticket(&self, state: &ChainState, id: u64) -> Proof<Ticket> {
+        Proof { id, record: state.tickets.get(&id).cloned(), membership: self.tickets.membership(id), parts: self.parts() }
+    }
+}
+
+/// New leaf hashes for one tree of a `StateTree`.
+enum LeafUpdate {
+    /// Every leaf, in id order.
+    Rebuild(Vec<(u64, [u8; 32])>),
+    /// The dirty ids, in order; `None` for a removed entry.
+    Changed(Vec<(u64, Option<[u8; 32]>)>),
+}
+
+impl LeafUpdate {
+    /// Leaves for the dirty keys of `map`, or for all of them if those are
+    /// not known, hashed by `hash`.
+    fn of<T: Clone>(
+        map: &JournaledMap<T>,
+        hash: impl Fn(u64, &T) -> Result<[u8; 32], SimError>,
+    ) -> Result<LeafUpdate, SimError> {
+        match map.dirty() {
+            None => LeafUpdate::rebuild(map, hash),
+            Some(dirty) => dirty
+                .iter()
+                .map(|&id| Ok((id, map.get(&id).map(|v| hash(id, v)).transpose()?)))
+                .collect::<Result<_, _>>()
+                .map(LeafUpdate::Changed),
+        }
+    }
+
+    fn rebuild<T: Clone>(
+        map: &JournaledMap<T>,
+        hash: impl Fn(u64, &T) -> Result<[u8; 32], SimError>,
+    ) -> Result<LeafUpdate, SimError> {
+        map.iter().map(|(&id, v)| Ok((id, hash(id, v)?))).collect::<Result<_, _>>().map(LeafUpdate::Rebuild)
+    }
+
+    fn into_tree(self, tree: &[u8]) -> MerkleTree {
+        let mut merkle = MerkleTree::default();
+        self.apply(tree, &mut merkle);
+        merkle
+    }
+
+    fn apply(self, tree: &[u8], merkle: &mut MerkleTree) {
+        match self {
+            LeafUpdate::Rebuild(leaves) => *merkle = MerkleTree::from_leaves(tree, leaves),
+            LeafUpdate::Changed(changes) => merkle.update(tree, changes),
+        }
+    }
+}
+
+/// That record `id` is `record`, or that there is none (`record: None`),
+/// in the state with a given root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proof<T> {
+    pub id: u64,
+    pub record: Option<T>,
+    pub membership: Membership,
+    pub parts: RootParts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofError {
+    /// The record's id differs from the proven id.
+    WrongRecord,
+    /// A record with an exclusion proof, or no record with an inclusion proof.
+    WrongKind,
+    /// The path does not fit the tree, or the neighbours do not enclose the id.
+    InvalidPath,
+    /// The proof leads to a different root.
+    RootMismatch,
+    /// The record could not be encoded for its hash.
+    Unencodable,
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::WrongRecord => write!(f, "record does not have the proven id"),
+            ProofError::WrongKind => write!(f, "proof kind does not match the record"),
+            ProofError::InvalidPath => write!(f, "invalid Merkle path"),
+            ProofError::RootMismatch => write!(f, "proof does not lead to the expected state root"),
+            ProofError::Unencodable => write!(f, "record cannot be encoded"),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+impl<T: Record> Proof<T> {
+    /// Check the proof against `state_root`, e.g. a block's.
+    pub fn verify(&self, state_root: [u8; 32]) -> Result<(), ProofError> {
+        if self.parts.root() != state_root {
+            return Err(ProofError::RootMismatch);
+        }
+        let tree_root = T::tree_root(&self.parts);
+        let leaf_count = T::leaf_count(&self.parts);
+        let leads_to_root = |id: u64, record_hash: [u8; 32], path: &LeafPath| {
+            if path.leaf_count != leaf_count {
+                return Err(ProofError::InvalidPath);
+            }
+            path.root(leaf_hash(T::TREE, id, record_hash)).ok_or(ProofError::InvalidPath).and_then(|root| {
+                if root == tree_root { Ok(()) } else { Err(ProofError::RootMismatch) }
+            })
+        };
+
+        match (&self.record, &self.membership) {
+            (Some(record), Membership::Included(path)) => {
+                if record.id() != self.id {
+                    return Err(ProofError::WrongRecord);
+                }
+                leads_to_root(self.id, record_hash(record).map_err(|_| ProofError::Unencodable)?, path)
+            }
+            (None, Membership::Excluded { lower, upper, leaf_count: claimed }) => {
+                if *claimed != leaf_count {
+                    return Err(ProofError::InvalidPath);
+                }
+                for neighbour in lower.iter().chain(upper) {
+                    leads_to_root(neighbour.id, neighbour.record_hash, &neighbour.path)?;
+                }
+                let encloses = match (lower, upper) {
+                    (Some(l), Some(u)) => l.id < self.id && self.id < u.id && l.path.index + 1 == u.path.index,
+                    (Some(l), None) => l.id < self.id && l.path.index + 1 == leaf_count,
+                    (None, Some(u)) => self.id < u.id && u.path.index == 0,
+                    (None, None) => leaf_count == 0 && tree_root == [0; 32],
+                };
+                if encloses { Ok(()) } else { Err(ProofError::InvalidPath) }
+            }
+            _ => Err(ProofError::WrongKind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::types::ticket::TicketState;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn proofs_verify_against_the_block_root() {
+        let mut sim = Scenario::load("scenarios/default.toml").unwrap().build_simulator().unwrap();
+        for _ in 0..150 {
+            sim.run_one_slot().unwrap();
+        }
+        let root = sim.blocks.last().unwrap().state_root;
+        let tree = StateTree::build(&sim.state).unwrap();
+        assert_eq!(tree.root(), root);
+
+        // Validator 1 double-signed its way into jail; its tickets are dead.
+        let ticket = tree.prove_ticket(&sim.state, 1);
+        assert_eq!(ticket.record.as_ref().unwrap().state, TicketState::Dead);
+        assert_eq!(ticket.verify(root), Ok(()));
+
+        let validator = tree.prove_validator(&sim.state, 2);
+        let json = serde_json::to_string(&validator).unwrap();
+        assert_eq!(serde_json::from_str::<Proof<Validator>>(&json).unwrap(), validator);
+        assert_eq!(validator.verify(root), Ok(()));
+        let mut forged = validator.clone();
+        forged.record.as_mut().unwrap().vault_balance += 1;
+        assert_eq!(forged.verify(root), Err(ProofError::RootMismatch));
+
+        for absent in [0, 3, 1_000] {
+            assert_eq!(tree.prove_validator(&sim.state, absent).verify(root), Ok(()));
+        }
+        let mut hidden = tree.prove_validator(&sim.state, 3);
+        hidden.id = 2;
+        assert_eq!(hidden.verify(root), Err(ProofError::InvalidPath));
+
+        // Leaf counts are part of the root, not taken from the path.
+        let mut resized = validator.clone();
+        let Membership::Included(path) = &mut resized.membership else { panic!("validator 2 is present") };
+        path.leaf_count += 1;
+        assert_eq!(resized.verify(root), Err(ProofError::InvalidPath));
+        resized.parts.validator_count += 1;
+        assert_eq!(resized.verify(root), Err(ProofError::RootMismatch));
+    }
+
+    #[test]
+    fn updates_match_a_rebuild() {
+        let validator = |id: u64, vault_balance: u128| Validator {
+            id,
+            state: crate::types::validator::ValidatorState::Active,
+            vault_balance,
+            initial_bond: 0,
+            withdrawable_balance: 0,
+            miss_counter: 0,
+            double_sign_offenses: 0,
+            cooldown_until_epoch: None,
+        };
+        for count in 0..12u64 {
+            let before: BTreeMap<u64, Validator> = (0..count).map(|i| (i * 2 + 1, validator(i * 2 + 1, 0))).collect();
+            let mut after = before.clone();
+            // Change one leaf, remove another and add one in the middle and one past the end.
+            if let Some(v) = after.get_mut(&(count | 1)) {
+                v.vault_balance = 7;
+            }
+            after.remove(&3);
+            after.insert(count, validator(count, 1));
+            after.insert(count * 2 + 5, validator(count * 2 + 5, 2));
+
+            let mut tree = MerkleTree::build(before.values()).unwrap();
+            let ids: BTreeSet<u64> = before.keys().chain(after.keys()).copied().collect();
+            tree.update(Validator::TREE, ids.into_iter().map(|id| (id, after.get(&id).map(|v| record_hash(v).unwrap()))));
+            let rebuilt = MerkleTree::build(after.values()).unwrap();
+            assert_eq!(tree.leaves, rebuilt.leaves, "{} leaves", count);
+            assert_eq!(tree.levels, rebuilt.levels, "{} leaves", count);
+        }
+    }
+
+    #[test]
+    fn paths_fit_every_tree_shape() {
+        for count in 0..12u64 {
+            let validators: Vec<Validator> = (0..count)
+                .map(|i| Validator {
+                    id: i * 2 + 1,
+                    state: crate::types::validator::ValidatorState::Active,
+                    vault_balance: i as u128,
+                    initial_bond: 0,
+                    withdrawable_balance: 0,
+                    miss_counter: 0,
+                    double_sign_offenses: 0,
+                    cooldown_until_epoch: None,
+                })
+                .collect();
+            let tree = MerkleTree::build(&validators).unwrap();
+            for v in &validators {
+                let Membership::Included(path) = tree.membership(v.id) else { panic!("{} is present", v.id) };
+                assert_eq!(path.root(leaf_hash(Validator::TREE, v.id, record_hash(v).unwrap())), Some(tree.root()));
+            }
+            for absent in (0..=count * 2).step_by(2) {
+                assert!(matches!(tree.membership(absent), Membership::Excluded { .. }));
+            }
+        }
+    }
+}
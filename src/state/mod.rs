@@ -1,4 +1,10 @@
 pub mod chain_state;
+pub mod journal;
 pub mod bucket_ops;
 pub mod validator_ops;
-pub mod retirement_ops;
\ No newline at end of file
+pub mod retirement_ops;
+pub mod diff;
+pub mod invariants;
+pub mod root;
+pub mod merkle;
+pub mod ticket_index;
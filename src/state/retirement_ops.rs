@@ -1,8 +1,25 @@
+use crate::error::SimError;
 use crate::state::chain_state::ChainState;
-use crate::state::bucket_ops::{any_muted_bucket, move_ticket};
+use crate::state::bucket_ops::{any_muted_bucket, move_ticket, set_ticket_state};
+use crate::types::event::SimEvent;
 use crate::types::ticket::TicketState;
 
-pub fn request_ticket_retire(state: &mut ChainState, validator_id: u64, ticket_ids: Vec<u64>) {
+pub fn request_ticket_retire(
+    state: &mut ChainState,
+    validator_id: u64,
+    ticket_ids: Vec<u64>,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    if !state.validators.contains_key(&validator_id) {
+        return Err(SimError::UnknownValidator(validator_id));
+    }
+    if state.retire_per_epoch_limit == 0 {
+        return Err(SimError::InvalidParam {
+            name: "retire_per_epoch_limit".to_string(),
+            reason: "must be greater than zero".to_string(),
+        });
+    }
+
     // Filter: must be owned by validator and currently Active
     let mut eligible: Vec<u64> = ticket_ids
         .into_iter()
@@ -24,7 +41,7 @@ pub fn request_ticket_retire(state: &mut ChainState, validator_id: u64, ticket_i
         // Count how many already scheduled for this validator in this epoch
         let already_scheduled_for_validator = entry
             .iter()
-            .filter(|tid| state.tickets.get(tid).unwrap().owner == validator_id)
+            .filter(|tid| state.tickets.get(tid).is_some_and(|t| t.owner == validator_id))
             .count() as u64;
 
         let room = state.retire_per_epoch_limit.saturating_sub(already_scheduled_for_validator);
@@ -36,20 +53,22 @@ pub fn request_ticket_retire(state: &mut ChainState, validator_id: u64, ticket_i
         let take = std::cmp::min(room as usize, eligible.len() - idx);
         for _ in 0..take {
             entry.push(eligible[idx]);
+            events.push(SimEvent::TicketRetireScheduled { ticket: eligible[idx], validator: validator_id, epoch });
             idx += 1;
         }
     }
+    Ok(())
 }
 
-pub fn begin_retire_for_epoch(state: &mut ChainState, epoch: u64) {
-    let Some(ticket_ids) = state.retire_schedule.remove(&epoch) else { return; };
+pub fn begin_retire_for_epoch(state: &mut ChainState, epoch: u64, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    let Some(ticket_ids) = state.retire_schedule.remove(&epoch) else { return Ok(()); };
 
-    println!("Tickets scheduled this epoch: {:?}", ticket_ids);
+    events.push(SimEvent::RetireBegin { ticket_ids: ticket_ids.clone() });
 
-    let muted_bucket = any_muted_bucket(state);
+    let muted_bucket = any_muted_bucket(state)?;
 
     for tid in ticket_ids {
-        let t = state.tickets.get_mut(&tid).unwrap();
+        let t = state.tickets.get(&tid).ok_or(SimError::UnknownTicket(tid))?;
 
         // If ticket already dead (e.g., validator jailed), skip
         if t.state != TicketState::Active {
@@ -57,45 +76,50 @@ pub fn begin_retire_for_epoch(state: &mut ChainState, epoch: u64) {
         }
 
         // Immediate ineligibility on request start
-        t.state = TicketState::Retiring;
+        set_ticket_state(state, tid, TicketState::Retiring)?;
+        let t = state.tickets.get_mut(&tid).unwrap();
         t.retire_requested_epoch = Some(epoch);
 
         // retirement delay: choose your constant (example: 2 epochs)
         let finalize_epoch = epoch + 2;
         t.retire_effective_epoch = Some(finalize_epoch);
+        events.push(SimEvent::TicketRetiring { ticket: tid, effective_epoch: finalize_epoch });
 
         // move to MUTED so it can't be selected (cleaner than keeping in ACTIVE)
         let from = t.bucket;
         if from != muted_bucket {
-            move_ticket(state, tid, from, muted_bucket);
+            move_ticket(state, tid, from, muted_bucket)?;
         }
 
         // enqueue finalization
         state.retire_finalize.entry(finalize_epoch).or_default().push(tid);
     }
+    Ok(())
 }
 
-pub fn finalize_retire_for_epoch(state: &mut ChainState, epoch: u64) {
-    let Some(ticket_ids) = state.retire_finalize.remove(&epoch) else { return; };
+pub fn finalize_retire_for_epoch(state: &mut ChainState, epoch: u64, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    let Some(ticket_ids) = state.retire_finalize.remove(&epoch) else { return Ok(()); };
 
-    println!("Finalizing tickets this epoch: {:?}", ticket_ids);
+    events.push(SimEvent::RetireFinalize { ticket_ids: ticket_ids.clone() });
 
     let dead_bucket = state.dead_bucket_id;
 
     for tid in ticket_ids {
-        let t = state.tickets.get_mut(&tid).unwrap();
+        let t = state.tickets.get(&tid).ok_or(SimError::UnknownTicket(tid))?;
 
         // If already dead, skip
         if t.state == TicketState::Dead {
             continue;
         }
 
-        t.state = TicketState::Dead;
+        set_ticket_state(state, tid, TicketState::Dead)?;
+        events.push(SimEvent::TicketDead { ticket: tid });
 
         // Move to DEAD bucket (unselectable forever)
-        let from = t.bucket;
+        let from = state.tickets[&tid].bucket;
         if from != dead_bucket {
-            move_ticket(state, tid, from, dead_bucket);
+            move_ticket(state, tid, from, dead_bucket)?;
         }
     }
+    Ok(())
 }
\ No newline at end of file
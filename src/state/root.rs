@@ -0,0 +1,116 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::SimError;
+use crate::state::chain_state::{for_each_field, ChainState};
+use crate::state::merkle::{KeyedTrees, StateTree};
+
+/// Root committed to by every block: the validator and ticket Merkle trees
+/// plus `body_hash`, combined as in `state::merkle::RootParts::root`.
+pub fn state_root(state: &ChainState) -> Result<[u8; 32], SimError> {
+    Ok(StateTree::build(state)?.root())
+}
+
+/// Canonical hash of the state outside its validator and ticket records,
+/// which have their own trees.
+///
+/// Hashes the epoch and sub-epoch indices, each bucket's id and size, then
+/// every `chain_state::for_each_field` field by name, in order, as its
+/// length-prefixed JSON encoding, then the name, leaf count and Merkle root
+/// of each `for_each_keyed_field` map. Which bucket holds a ticket is part of
+/// the ticket's record. Every map and set is ordered, so the hash depends only
+/// on what the state holds. Builds the keyed maps' trees from scratch;
+/// `StateTree::update` keeps them instead and rehashes only changed entries.
+pub fn body_hash(state: &ChainState) -> Result<[u8; 32], SimError> {
+    Ok(finish_body(body_hasher(state)?, &KeyedTrees::build(state)?))
+}
+
+/// The body hash up to the keyed maps. Costs what the `for_each_field`
+/// fields cost to encode; records and keyed maps are not read.
+pub(crate) fn body_hasher(state: &ChainState) -> Result<Sha256, SimError> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"eternix/body");
+    hasher.update(state.epoch_index.to_be_bytes());
+    hasher.update(state.sub_epoch_index.to_be_bytes());
+    hasher.update((state.buckets.len() as u64).to_be_bytes());
+    for (id, bucket) in &state.buckets {
+        hasher.update(id.to_be_bytes());
+        hasher.update((bucket.ticket_ids.len() as u64).to_be_bytes());
+    }
+
+    macro_rules! hash_fields {
+        ($($field:ident),*) => {
+            $( hash_field(&mut hasher, stringify!($field), &state.$field)?; )*
+        };
+    }
+    for_each_field!(hash_fields);
+    Ok(hasher)
+}
+
+/// Complete a `body_hasher` with the roots of `keyed`.
+pub(crate) fn finish_body(mut hasher: Sha256, keyed: &KeyedTrees) -> [u8; 32] {
+    for (name, root, count) in keyed.roots() {
+        hasher.update((name.len() as u64).to_be_bytes());
+        hasher.update(name);
+        hasher.update(count.to_be_bytes());
+        hasher.update(root);
+    }
+    hasher.finalize().into()
+}
+
+fn hash_field(hasher: &mut Sha256, name: &str, value: &impl Serialize) -> Result<(), SimError> {
+    let json = serde_json::to_vec(value).map_err(|e| SimError::Encoding(format!("{}: {}", name, e)))?;
+    hasher.update((name.len() as u64).to_be_bytes());
+    hasher.update(name);
+    hasher.update((json.len() as u64).to_be_bytes());
+    hasher.update(json);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::types::block::first_divergence;
+
+    #[test]
+    fn root_depends_only_on_content() {
+        let state = Scenario::load("scenarios/full_layout.toml").unwrap().build_state().unwrap();
+
+        let json = serde_json::to_string(&state).unwrap();
+        let mut decoded: ChainState = serde_json::from_str(&json).unwrap();
+        let root = state_root(&state).unwrap();
+        assert_eq!(state_root(&decoded).unwrap(), root);
+
+        decoded.validators.get_mut(&1).unwrap().vault_balance += 1;
+        assert_ne!(state_root(&decoded).unwrap(), root);
+
+        // Fields outside the records are covered by the body hash.
+        let mut changed = state.clone();
+        changed.randao_mix[0] ^= 1;
+        assert_ne!(body_hash(&changed).unwrap(), body_hash(&state).unwrap());
+        let mut changed = state.clone();
+        changed.pending_refills.insert(1, 5);
+        assert_ne!(state_root(&changed).unwrap(), root);
+    }
+
+    #[test]
+    fn blocks_chain_and_runs_diverge_where_they_differ() {
+        let mut scenario = Scenario::load("scenarios/interventions.toml").unwrap();
+        let run = |scenario: &Scenario| {
+            let mut sim = scenario.build_simulator().unwrap();
+            for _ in 0..100 {
+                sim.run_one_slot().unwrap();
+            }
+            sim.blocks
+        };
+
+        let blocks = run(&scenario);
+        assert!(blocks.windows(2).all(|pair| pair[1].parent_hash == pair[0].hash()));
+        assert_eq!(first_divergence(&blocks, &run(&scenario)), None);
+
+        // Move validator 1's refill from slot 25 to slot 60.
+        scenario.events[1].at = crate::sim::timeline::Trigger::Slot(60);
+        assert_eq!(first_divergence(&blocks, &run(&scenario)), Some(25));
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::AddAssign;
+
+use serde::Serialize;
+
+use crate::error::SimError;
+use crate::types::ticket::{Ticket, TicketState};
+
+/// Number of tickets in each lifecycle state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TicketCounts {
+    pub active: u64,
+    pub retiring: u64,
+    pub dead: u64,
+}
+
+impl TicketCounts {
+    fn get_mut(&mut self, state: TicketState) -> &mut u64 {
+        match state {
+            TicketState::Active => &mut self.active,
+            TicketState::Retiring => &mut self.retiring,
+            TicketState::Dead => &mut self.dead,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.active + self.retiring + self.dead
+    }
+}
+
+impl AddAssign for TicketCounts {
+    fn add_assign(&mut self, other: TicketCounts) {
+        self.active += other.active;
+        self.retiring += other.retiring;
+        self.dead += other.dead;
+    }
+}
+
+impl<'a> FromIterator<&'a Ticket> for TicketCounts {
+    fn from_iter<I: IntoIterator<Item = &'a Ticket>>(tickets: I) -> Self {
+        let mut counts = TicketCounts::default();
+        for ticket in tickets {
+            *counts.get_mut(ticket.state) += 1;
+        }
+        counts
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct OwnerTickets {
+    /// Every ticket the owner has, Dead ones included.
+    ids: BTreeSet<u64>,
+    active: u64,
+    /// Active tickets sitting in an ACTIVE bucket.
+    eligible: u64,
+}
+
+/// Lookups over `ChainState::tickets` that would otherwise need a scan of
+/// every ticket. Derived data: not serialised, rebuilt with `build`, and
+/// kept up to date by `bucket_ops::issue_ticket`, `bucket_ops::move_ticket`
+/// and `bucket_ops::set_ticket_state`, the only places tickets are added,
+/// change bucket or change state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TicketIndex {
+    /// The ACTIVE buckets at build time; the bucket layout is fixed at genesis.
+    active_buckets: BTreeSet<u64>,
+    by_owner: BTreeMap<u64, OwnerTickets>,
+    by_bucket: BTreeMap<u64, TicketCounts>,
+    counts: TicketCounts,
+}
+
+impl TicketIndex {
+    pub fn build<'a>(tickets: impl IntoIterator<Item = &'a Ticket>, active_bucket_ids: &BTreeSet<u64>) -> TicketIndex {
+        let mut index = TicketIndex { active_buckets: active_bucket_ids.clone(), ..TicketIndex::default() };
+        for ticket in tickets {
+            index.insert(ticket);
+        }
+        index
+    }
+
+    pub fn insert(&mut self, ticket: &Ticket) {
+        self.by_owner.entry(ticket.owner).or_default().ids.insert(ticket.id);
+        self.add(ticket);
+    }
+
+    /// Fails, leaving the index untouched, if it does not count `ticket`.
+    pub fn remove(&mut self, ticket: &Ticket) -> Result<(), SimError> {
+        self.subtract(ticket)?;
+        if let Some(owned) = self.by_owner.get_mut(&ticket.owner) {
+            owned.ids.remove(&ticket.id);
+            if owned.ids.is_empty() {
+                self.by_owner.remove(&ticket.owner);
+            }
+        }
+        Ok(())
+    }
+
+    /// Record that a ticket changed from `before` to `after`, in bucket,
+    /// state or both. The owner never changes. Fails, leaving the index
+    /// untouched, if it does not count `before`.
+    pub fn update(&mut self, before: &Ticket, after: &Ticket) -> Result<(), SimError> {
+        debug_assert_eq!(before.owner, after.owner);
+        self.subtract(before)?;
+        self.add(after);
+        Ok(())
+    }
+
+    /// Add the counters `ticket` contributes to.
+    fn add(&mut self, ticket: &Ticket) {
+        *self.counts.get_mut(ticket.state) += 1;
+        *self.by_bucket.entry(ticket.bucket).or_default().get_mut(ticket.state) += 1;
+        if ticket.state == TicketState::Active {
+            let owned = self.by_owner.entry(ticket.owner).or_default();
+            owned.active += 1;
+            if self.active_buckets.contains(&ticket.bucket) {
+                owned.eligible += 1;
+            }
+        }
+    }
+
+    /// Take away the counters `ticket` contributes to, after checking that
+    /// none of them would go below zero.
+    fn subtract(&mut self, ticket: &Ticket) -> Result<(), SimError> {
+        let eligible = ticket.state == TicketState::Active && self.active_buckets.contains(&ticket.bucket);
+        let mut bucket = self.by_bucket.get(&ticket.bucket).copied().unwrap_or_default();
+        let mut owned = self.by_owner.get(&ticket.owner).map(|o| (o.active, o.eligible)).unwrap_or_default();
+        let mut counts = self.counts;
+        let counted = [
+            Some(counts.get_mut(ticket.state)),
+            Some(bucket.get_mut(ticket.state)),
+            (ticket.state == TicketState::Active).then_some(&mut owned.0),
+            eligible.then_some(&mut owned.1),
+        ];
+        for count in counted.into_iter().flatten() {
+            *count = count.checked_sub(1).ok_or(SimError::TicketNotIndexed(ticket.id))?;
+        }
+
+        self.counts = counts;
+        if bucket.total() == 0 {
+            self.by_bucket.remove(&ticket.bucket);
+        } else {
+            self.by_bucket.insert(ticket.bucket, bucket);
+        }
+        if let Some(o) = self.by_owner.get_mut(&ticket.owner) {
+            (o.active, o.eligible) = owned;
+        }
+        Ok(())
+    }
+
+    /// Ids of `owner`'s tickets, ascending.
+    pub fn owned_by(&self, owner: u64) -> impl Iterator<Item = u64> + '_ {
+        self.by_owner.get(&owner).into_iter().flat_map(|o| o.ids.iter().copied())
+    }
+
+    pub fn owns(&self, owner: u64, ticket_id: u64) -> bool {
+        self.by_owner.get(&owner).is_some_and(|o| o.ids.contains(&ticket_id))
+    }
+
+    pub fn active_count(&self, owner: u64) -> u64 {
+        self.by_owner.get(&owner).map_or(0, |o| o.active)
+    }
+
+    /// `owner`'s Active tickets in an ACTIVE bucket.
+    pub fn eligible_count(&self, owner: u64) -> u64 {
+        self.by_owner.get(&owner).map_or(0, |o| o.eligible)
+    }
+
+    /// Owners with at least one Active ticket in an ACTIVE bucket, with how
+    /// many they have, ascending by owner.
+    pub fn eligible_by_owner(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.by_owner.iter().filter(|(_, o)| o.eligible > 0).map(|(&owner, o)| (owner, o.eligible))
+    }
+
+    pub fn bucket_counts(&self, bucket: u64) -> TicketCounts {
+        self.by_bucket.get(&bucket).copied().unwrap_or_default()
+    }
+
+    pub fn counts(&self) -> TicketCounts {
+        self.counts
+    }
+}
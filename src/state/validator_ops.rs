@@ -1,27 +1,98 @@
-use crate::state::bucket_ops::{any_active_bucket, move_all_validator_tickets_to_bucket};
-use crate::types::validator::ValidatorState;
+use crate::error::SimError;
+use crate::state::bucket_ops::restore_validator_tickets;
+use crate::types::validator::{Validator, ValidatorState};
 use crate::state::chain_state::ChainState;
 use crate::state::bucket_ops::force_dead_all_validator_tickets;
+use crate::types::event::SimEvent;
 
-pub fn on_vault_refill(state: &mut ChainState, validator_id: u64, amount: u128) {
-    let v = state.validators.get_mut(&validator_id).unwrap();
-    v.vault_balance += amount;
+pub fn on_vault_refill(
+    state: &mut ChainState,
+    validator_id: u64,
+    amount: u128,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    let overflow = SimError::BalanceOverflow { validator: validator_id, amount };
+    let deposited = state.ledger.deposited.checked_add(amount).ok_or(overflow.clone())?;
+    let v = state.validators.get_mut(&validator_id).ok_or(SimError::UnknownValidator(validator_id))?;
+    v.vault_balance = v.vault_balance.checked_add(amount).ok_or(overflow)?;
+    state.ledger.deposited = deposited;
+    events.push(SimEvent::VaultRefilled { validator: validator_id, amount, new_vault: v.vault_balance });
 
     // Instant rejoin only from PausedLowVault
     if v.state == ValidatorState::PausedLowVault && v.vault_balance >= v.initial_bond {
         v.state = ValidatorState::Active;
+        events.push(SimEvent::Rejoined { validator: validator_id });
 
-        let active_bucket = any_active_bucket(state);
-        move_all_validator_tickets_to_bucket(state, validator_id, active_bucket);
+        restore_validator_tickets(state, validator_id)?;
     }
+    Ok(())
 }
 
-pub fn jail_validator(state: &mut ChainState, validator_id: u64) {
-    let val = state.validators.get_mut(&validator_id).unwrap();
+/// Queue a refill to be applied at the next sub-epoch boundary.
+pub fn queue_vault_refill(
+    state: &mut ChainState,
+    validator_id: u64,
+    amount: u128,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    let v = state.validators.get(&validator_id).ok_or(SimError::UnknownValidator(validator_id))?;
+
+    // Refuse now a refill that could not be credited at the boundary.
+    let queued = state.pending_refills.get(&validator_id).copied().unwrap_or(0);
+    let pending = queued
+        .checked_add(amount)
+        .filter(|pending| v.vault_balance.checked_add(*pending).is_some())
+        .ok_or(SimError::BalanceOverflow { validator: validator_id, amount })?;
+    state.pending_refills.insert(validator_id, pending);
+    events.push(SimEvent::RefillQueued { validator: validator_id, amount });
+    Ok(())
+}
+
+pub fn jail_validator(state: &mut ChainState, validator_id: u64, events: &mut Vec<SimEvent>) -> Result<(), SimError> {
+    let val = state.validators.get_mut(&validator_id).ok_or(SimError::UnknownValidator(validator_id))?;
 
     val.state = ValidatorState::Jailed;
     val.cooldown_until_epoch = None;
+    events.push(SimEvent::Jailed { validator: validator_id });
 
     // Tickets become dead immediately
-    force_dead_all_validator_tickets(state, validator_id);
+    force_dead_all_validator_tickets(state, validator_id, events)
+}
+
+
+/// Register a new validator mid-run. It starts Active if its vault covers the
+/// bond, otherwise PausedLowVault.
+pub fn add_validator(
+    state: &mut ChainState,
+    validator_id: u64,
+    vault_balance: u128,
+    initial_bond: u128,
+    events: &mut Vec<SimEvent>,
+) -> Result<(), SimError> {
+    if state.validators.contains_key(&validator_id) {
+        return Err(SimError::DuplicateValidator(validator_id));
+    }
+
+    let st = if vault_balance >= initial_bond {
+        ValidatorState::Active
+    } else {
+        ValidatorState::PausedLowVault
+    };
+
+    state.validators.insert(
+        validator_id,
+        Validator {
+            id: validator_id,
+            state: st,
+            vault_balance,
+            initial_bond,
+            withdrawable_balance: 0,
+            miss_counter: 0,
+            double_sign_offenses: 0,
+            cooldown_until_epoch: None,
+        },
+    );
+    state.ledger.deposited += vault_balance;
+    events.push(SimEvent::ValidatorJoined { validator: validator_id, state: st });
+    Ok(())
 }
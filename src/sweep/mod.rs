@@ -0,0 +1,55 @@
+//! Parameter sweeps: run many independent simulators over a grid or random
+//! sample of scenario parameters and tabulate their summary metrics, or many
+//! seeded replicas of one scenario and aggregate their distributions.
+
+pub mod monte_carlo;
+pub mod runner;
+pub mod spec;
+pub mod table;
+
+#[cfg(test)]
+mod tests {
+    use super::runner::run_sweep;
+    use super::spec::SweepSpec;
+    use crate::scenario::config::Scenario;
+
+    const SPEC: &str = r#"
+        scenario = "scenarios/full_layout.toml"
+        seeds = [1, 2]
+
+        [[grid]]
+        param = "run.epochs"
+        values = [10]
+
+        [[grid]]
+        param = "protocol.liveness_miss_threshold"
+        values = [3, 10]
+
+        [random]
+        samples = 2
+        seed = 9
+        params = [{ param = "validators.3.behavior.p", min = 0.0, max = 0.5 }]
+    "#;
+
+    #[test]
+    fn results_do_not_depend_on_thread_count() {
+        let spec: SweepSpec = toml::from_str(SPEC).unwrap();
+        let base = Scenario::load(&spec.scenario).unwrap();
+        let runs = spec.plan(&base).unwrap();
+        assert_eq!(runs.len(), 2 * 2 * 2);
+        assert_eq!(runs[2].scenario.protocol.liveness_miss_threshold, 3);
+        assert_eq!(runs[4].scenario.protocol.liveness_miss_threshold, 10);
+
+        let serial = run_sweep(&runs, 1);
+        assert!(serial.iter().all(|r| r.summary.is_ok()));
+        assert_eq!(serial, run_sweep(&runs, 4));
+    }
+
+    #[test]
+    fn unknown_parameters_are_rejected() {
+        let mut spec: SweepSpec = toml::from_str(SPEC).unwrap();
+        spec.grid[1].param = "protocol.no_such_field".to_string();
+        let base = Scenario::load(&spec.scenario).unwrap();
+        assert!(spec.plan(&base).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::scenario::config::Scenario;
+use crate::sim::rng::SimRng;
+use crate::sweep::runner::{run_sweep, RunResult, RunSummary};
+use crate::sweep::spec::SweepRun;
+
+/// `run.seed` of replica `replica`. Each replica depends only on its own seed,
+/// so it can be rerun alone by setting `[run] seed` to this value.
+pub fn replica_seed(base_seed: u64, replica: u64) -> u64 {
+    SimRng::derive(base_seed, replica).next_u64()
+}
+
+/// Replicas of `scenario`, identical except for their derived seeds.
+pub fn plan_replicas(scenario: &Scenario, replicas: usize, base_seed: u64) -> Vec<SweepRun> {
+    (0..replicas)
+        .map(|index| {
+            let seed = replica_seed(base_seed, index as u64);
+            let mut scenario = scenario.clone();
+            scenario.run.seed = seed;
+            SweepRun { index, seed, params: Vec::new(), scenario }
+        })
+        .collect()
+}
+
+/// Summary statistics of one metric across replicas.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Distribution {
+    pub samples: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+    /// 95% confidence interval of the mean (normal approximation).
+    pub ci95: (f64, f64),
+}
+
+impl Distribution {
+    /// `None` for an empty sample.
+    pub fn from_samples(samples: &[f64]) -> Option<Distribution> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let variance = if sorted.len() > 1 {
+            sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+        let std_dev = variance.sqrt();
+        let half_width = 1.96 * std_dev / n.sqrt();
+
+        Some(Distribution {
+            samples: sorted.len(),
+            mean,
+            std_dev,
+            p5: percentile(&sorted, 5.0),
+            p50: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            ci95: (mean - half_width, mean + half_width),
+        })
+    }
+}
+
+/// Nearest-rank percentile of sorted data.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mean {:.4} ± {:.4} (95% CI {:.4}..{:.4}) | p5 {:.4} p50 {:.4} p95 {:.4} | n={}",
+            self.mean,
+            self.ci95.1 - self.mean,
+            self.ci95.0,
+            self.ci95.1,
+            self.p5,
+            self.p50,
+            self.p95,
+            self.samples
+        )
+    }
+}
+
+/// Aggregated Monte Carlo results, with the per-replica results they came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct MonteCarloReport {
+    pub base_seed: u64,
+    pub replicas: usize,
+    /// Replicas whose scenario or a slot failed; excluded from the distributions.
+    pub failed: usize,
+    /// Slot of the first jailing, over the replicas where one happened.
+    pub time_to_jail: Option<Distribution>,
+    /// Replicas in which nobody was jailed.
+    pub never_jailed: usize,
+    /// Share of blocks produced by the protocol.
+    pub protocol_block_fraction: Option<Distribution>,
+    /// Largest fall of total bonded funds from their peak, as a fraction.
+    pub vault_drawdown: Option<Distribution>,
+    /// Share of validators not jailed at the end.
+    pub survival: Option<Distribution>,
+    #[serde(skip)]
+    pub results: Vec<RunResult>,
+}
+
+impl MonteCarloReport {
+    pub fn from_results(base_seed: u64, results: Vec<RunResult>) -> MonteCarloReport {
+        let summaries: Vec<_> = results.iter().filter_map(|r| r.summary.as_ref().ok()).collect();
+        let metric = |f: &dyn Fn(&RunSummary) -> Option<f64>| {
+            Distribution::from_samples(&summaries.iter().filter_map(|s| f(s)).collect::<Vec<_>>())
+        };
+
+        MonteCarloReport {
+            base_seed,
+            replicas: results.len(),
+            failed: results.len() - summaries.len(),
+            time_to_jail: metric(&|s| s.first_jail_slot.map(|slot| slot as f64)),
+            never_jailed: summaries.iter().filter(|s| s.first_jail_slot.is_none()).count(),
+            protocol_block_fraction: metric(&|s| {
+                (s.slots > 0).then(|| s.protocol_blocks as f64 / s.slots as f64)
+            }),
+            vault_drawdown: metric(&|s| Some(s.max_drawdown_bps as f64 / 10_000.0)),
+            survival: metric(&|s| {
+                (s.validators > 0).then(|| (s.validators - s.jailed) as f64 / s.validators as f64)
+            }),
+            results,
+        }
+    }
+}
+
+impl fmt::Display for MonteCarloReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Monte Carlo: {} replicas from base seed {} ({} failed)",
+            self.replicas, self.base_seed, self.failed
+        )?;
+        let rows = [
+            ("time to jail (slot)", &self.time_to_jail),
+            ("protocol block fraction", &self.protocol_block_fraction),
+            ("vault drawdown", &self.vault_drawdown),
+            ("validator survival", &self.survival),
+        ];
+        for (name, dist) in rows {
+            match dist {
+                Some(d) => writeln!(f, "  {:<24} {}", name, d)?,
+                None => writeln!(f, "  {:<24} no samples", name)?,
+            }
+        }
+        write!(f, "  never jailed in {} of {} replicas", self.never_jailed, self.replicas - self.failed)
+    }
+}
+
+/// Run `replicas` replicas of `scenario` on `threads` threads (0 = one per core).
+pub fn run_monte_carlo(scenario: &Scenario, replicas: usize, base_seed: u64, threads: usize) -> MonteCarloReport {
+    let runs = plan_replicas(scenario, replicas, base_seed);
+    MonteCarloReport::from_results(base_seed, run_sweep(&runs, threads))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sweep::runner::run_scenario;
+
+    #[test]
+    fn percentiles_and_interval() {
+        let samples: Vec<f64> = (1..=100).map(f64::from).collect();
+        let d = Distribution::from_samples(&samples).unwrap();
+        assert_eq!((d.p5, d.p50, d.p95), (5.0, 50.0, 95.0));
+        assert!((d.mean - 50.5).abs() < 1e-9);
+        assert!(d.ci95.0 < d.mean && d.mean < d.ci95.1);
+        assert!(Distribution::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn a_replica_is_reproducible_from_its_seed_alone() {
+        let mut scenario = Scenario::load("scenarios/full_layout.toml").unwrap();
+        scenario.run.epochs = Some(10);
+        let report = run_monte_carlo(&scenario, 3, 42, 2);
+        assert_eq!(report.failed, 0);
+
+        let replica = &report.results[2];
+        assert_eq!(replica.seed, replica_seed(42, 2));
+        scenario.run.seed = replica.seed;
+        assert_eq!(replica.summary, run_scenario(&scenario));
+    }
+}
@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::economics::supply::SupplyReport;
+use crate::scenario::config::Scenario;
+use crate::sim::simulator::Simulator;
+use crate::sim::sink::EventSink;
+use crate::sweep::spec::SweepRun;
+use crate::types::amount;
+use crate::types::event::{EventRecord, SimEvent};
+use crate::types::validator::ValidatorState;
+
+/// Summary metrics of one finished run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunSummary {
+    pub slots: u64,
+    pub protocol_blocks: u64,
+    pub liveness_slashes: u64,
+    pub double_signs: u64,
+    /// Slot of the first jailing, if any validator was jailed.
+    pub first_jail_slot: Option<u64>,
+    pub validators: u64,
+    /// Validators Active at the end of the run.
+    pub active: u64,
+    pub jailed: u64,
+    /// Largest fall of total bonded funds from their running peak, in basis points.
+    pub max_drawdown_bps: u64,
+    #[serde(with = "amount")]
+    pub genesis_bonded: u128,
+    #[serde(with = "amount")]
+    pub bonded: u128,
+    #[serde(with = "amount")]
+    pub slashed: u128,
+    #[serde(with = "amount")]
+    pub burned: u128,
+    #[serde(with = "amount")]
+    pub total_supply: u128,
+}
+
+/// Outcome of one sweep run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunResult {
+    pub index: usize,
+    pub seed: u64,
+    pub params: Vec<(String, Value)>,
+    /// The error message if the scenario was invalid or a slot failed.
+    pub summary: Result<RunSummary, String>,
+}
+
+/// Event counts gathered during a run.
+#[derive(Debug, Default)]
+struct Counts {
+    liveness_slashes: u64,
+    double_signs: u64,
+    first_jail_slot: Option<u64>,
+}
+
+#[derive(Clone, Default)]
+struct CountingSink(Rc<RefCell<Counts>>);
+
+impl EventSink for CountingSink {
+    fn emit(&mut self, record: &EventRecord) {
+        let mut counts = self.0.borrow_mut();
+        match record.event {
+            SimEvent::LivenessSlash { .. } => counts.liveness_slashes += 1,
+            SimEvent::DoubleSign { .. } => counts.double_signs += 1,
+            SimEvent::Jailed { .. } => {
+                counts.first_jail_slot.get_or_insert(record.slot);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Run `scenario` for its configured length and summarise it.
+pub fn run_scenario(scenario: &Scenario) -> Result<RunSummary, String> {
+    let mut sim = scenario.build_simulator().map_err(|e| e.to_string())?;
+    let slots = scenario.run_slots().map_err(|e| e.to_string())?;
+    let sink = CountingSink::default();
+    sim.add_sink(Box::new(sink.clone()));
+
+    let bonded = |sim: &Simulator| -> u128 { sim.state.validators.values().map(|v| v.vault_balance).sum() };
+    let genesis_bonded = bonded(&sim);
+    let (mut peak, mut max_drawdown_bps) = (genesis_bonded, 0);
+    let mut protocol_blocks = 0;
+    for _ in 0..slots {
+        let block = sim.run_one_slot().map_err(|e| format!("slot {}: {}", sim.clock.slot_index, e))?;
+        if block.proposer.is_none() {
+            protocol_blocks += 1;
+        }
+
+        let now = bonded(&sim);
+        peak = peak.max(now);
+        if let Some(drawdown) = ((peak - now) * 10_000).checked_div(peak) {
+            max_drawdown_bps = max_drawdown_bps.max(drawdown as u64);
+        }
+    }
+
+    let counts = sink.0.borrow();
+    let supply = SupplyReport::from_state(&sim.state);
+    let count = |state: ValidatorState| sim.state.validators.values().filter(|v| v.state == state).count() as u64;
+    Ok(RunSummary {
+        slots,
+        protocol_blocks,
+        liveness_slashes: counts.liveness_slashes,
+        double_signs: counts.double_signs,
+        first_jail_slot: counts.first_jail_slot,
+        validators: sim.state.validators.len() as u64,
+        active: count(ValidatorState::Active),
+        jailed: count(ValidatorState::Jailed),
+        max_drawdown_bps,
+        genesis_bonded,
+        bonded: supply.bonded,
+        slashed: supply.slashed,
+        burned: supply.burned,
+        total_supply: supply.total_supply,
+    })
+}
+
+/// Number of worker threads to use when the caller asks for 0 (automatic).
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Run every sweep run on up to `threads` threads (0 = one per core).
+///
+/// Runs share nothing, each simulator is built inside its worker, and
+/// results are returned in run order, so the output does not depend on the
+/// thread count or on scheduling.
+pub fn run_sweep(runs: &[SweepRun], threads: usize) -> Vec<RunResult> {
+    let threads = if threads == 0 { default_threads() } else { threads }.clamp(1, runs.len().max(1));
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(runs.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(run) = runs.get(i) else { break; };
+                    let result = RunResult {
+                        index: run.index,
+                        seed: run.seed,
+                        params: run.params.clone(),
+                        summary: run_scenario(&run.scenario),
+                    };
+                    results.lock().unwrap_or_else(|e| e.into_inner()).push(result);
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by_key(|r| r.index);
+    results
+}
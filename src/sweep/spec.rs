@@ -0,0 +1,229 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::scenario::config::Scenario;
+use crate::scenario::loader::ScenarioError;
+use crate::sim::rng::SimRng;
+
+/// A sweep file: a base scenario, the parameters to vary and the seeds to run
+/// every combination with.
+///
+/// Parameters are dotted paths into the scenario as it is written in a
+/// scenario file, with list positions as numbers, e.g.
+/// `protocol.liveness_miss_threshold` or `validators.0.behavior.p`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SweepSpec {
+    /// Path of the base scenario.
+    pub scenario: String,
+    /// `run.seed` values; every combination runs once per seed.
+    #[serde(default = "default_seeds")]
+    pub seeds: Vec<u64>,
+    /// Every combination of these values is run.
+    #[serde(default)]
+    pub grid: Vec<GridParam>,
+    /// Random points, each combined with every grid combination.
+    #[serde(default)]
+    pub random: Option<RandomSamples>,
+}
+
+fn default_seeds() -> Vec<u64> {
+    vec![0]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GridParam {
+    pub param: String,
+    pub values: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RandomSamples {
+    pub samples: usize,
+    /// Seed of the sampler, independent of the run seeds.
+    #[serde(default)]
+    pub seed: u64,
+    pub params: Vec<RangeParam>,
+}
+
+/// Uniform in `[min, max]`. Integer if the base scenario's value is an integer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RangeParam {
+    pub param: String,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Debug)]
+pub enum SweepError {
+    Io(String, std::io::Error),
+    Parse(String),
+    Scenario(ScenarioError),
+    /// The path does not name a field of the scenario.
+    UnknownParam(String),
+    InvalidValue { param: String, reason: String },
+    EmptySweep,
+}
+
+impl fmt::Display for SweepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SweepError::Io(path, e) => write!(f, "cannot read {}: {}", path, e),
+            SweepError::Parse(msg) => write!(f, "parse error: {}", msg),
+            SweepError::Scenario(e) => write!(f, "{}", e),
+            SweepError::UnknownParam(param) => write!(f, "unknown scenario parameter {:?}", param),
+            SweepError::InvalidValue { param, reason } => write!(f, "invalid value for {}: {}", param, reason),
+            SweepError::EmptySweep => write!(f, "sweep has no runs (empty seeds, values or samples)"),
+        }
+    }
+}
+
+impl std::error::Error for SweepError {}
+
+impl From<ScenarioError> for SweepError {
+    fn from(e: ScenarioError) -> Self {
+        SweepError::Scenario(e)
+    }
+}
+
+/// One run of a sweep: a parameter combination and a seed.
+#[derive(Debug, Clone)]
+pub struct SweepRun {
+    pub index: usize,
+    pub seed: u64,
+    pub params: Vec<(String, Value)>,
+    pub scenario: Scenario,
+}
+
+impl SweepSpec {
+    pub fn load(path: impl AsRef<Path>) -> Result<SweepSpec, SweepError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| SweepError::Io(path.display().to_string(), e))?;
+        toml::from_str(&text).map_err(|e| SweepError::Parse(e.to_string()))
+    }
+
+    /// Every run of the sweep, in a fixed order: parameter combinations in
+    /// grid order (last parameter fastest), random samples within each, seeds
+    /// innermost.
+    pub fn plan(&self, base: &Scenario) -> Result<Vec<SweepRun>, SweepError> {
+        let base_value = serde_json::to_value(base).map_err(|e| SweepError::Parse(e.to_string()))?;
+
+        let mut points: Vec<Vec<(String, Value)>> = vec![Vec::new()];
+        for grid in &self.grid {
+            lookup(&base_value, &grid.param)?;
+            points = points
+                .into_iter()
+                .flat_map(|point| {
+                    grid.values.iter().map(move |value| {
+                        let mut point = point.clone();
+                        point.push((grid.param.clone(), value.clone()));
+                        point
+                    })
+                })
+                .collect();
+        }
+
+        if let Some(random) = &self.random {
+            let samples = random.sample(&base_value)?;
+            points = points
+                .into_iter()
+                .flat_map(|point| {
+                    samples.iter().map(move |sample| point.iter().cloned().chain(sample.iter().cloned()).collect())
+                })
+                .collect();
+        }
+
+        let mut runs = Vec::new();
+        for params in points {
+            let mut value = base_value.clone();
+            for (param, v) in &params {
+                *lookup_mut(&mut value, param)? = v.clone();
+            }
+            let scenario: Scenario = serde_json::from_value(value).map_err(|e| SweepError::InvalidValue {
+                param: params.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>().join(", "),
+                reason: e.to_string(),
+            })?;
+
+            for &seed in &self.seeds {
+                let mut scenario = scenario.clone();
+                scenario.run.seed = seed;
+                runs.push(SweepRun { index: runs.len(), seed, params: params.clone(), scenario });
+            }
+        }
+
+        if runs.is_empty() {
+            return Err(SweepError::EmptySweep);
+        }
+        Ok(runs)
+    }
+}
+
+impl RandomSamples {
+    fn sample(&self, base: &Value) -> Result<Vec<Vec<(String, Value)>>, SweepError> {
+        for range in &self.params {
+            lookup(base, &range.param)?;
+            if range.min.is_nan() || range.max.is_nan() || range.min > range.max {
+                return Err(SweepError::InvalidValue {
+                    param: range.param.clone(),
+                    reason: format!("min {} exceeds max {}", range.min, range.max),
+                });
+            }
+        }
+
+        let mut rng = SimRng::new(self.seed);
+        let mut samples = Vec::with_capacity(self.samples);
+        for _ in 0..self.samples {
+            let mut point = Vec::new();
+            for range in &self.params {
+                let integer = lookup(base, &range.param)?.is_u64();
+                let value = if integer {
+                    let (min, max) = (range.min.max(0.0).ceil() as u64, range.max.floor() as u64);
+                    if min > max {
+                        return Err(SweepError::InvalidValue {
+                            param: range.param.clone(),
+                            reason: format!("no integer in [{}, {}]", range.min, range.max),
+                        });
+                    }
+                    Value::from(min + rng.next_u64() % (max - min + 1))
+                } else {
+                    Value::from(range.min + rng.next_f64() * (range.max - range.min))
+                };
+                point.push((range.param.clone(), value));
+            }
+            samples.push(point);
+        }
+        Ok(samples)
+    }
+}
+
+fn lookup<'a>(value: &'a Value, param: &str) -> Result<&'a Value, SweepError> {
+    let mut current = value;
+    for key in param.split('.') {
+        current = match current {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        }
+        .ok_or_else(|| SweepError::UnknownParam(param.to_string()))?;
+    }
+    Ok(current)
+}
+
+fn lookup_mut<'a>(value: &'a mut Value, param: &str) -> Result<&'a mut Value, SweepError> {
+    let mut current = value;
+    for key in param.split('.') {
+        current = match current {
+            Value::Object(map) => map.get_mut(key),
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+            _ => None,
+        }
+        .ok_or_else(|| SweepError::UnknownParam(param.to_string()))?;
+    }
+    Ok(current)
+}
@@ -0,0 +1,131 @@
+use std::io::{self, Write};
+
+use serde_json::{json, Map, Value};
+
+use crate::sweep::runner::{RunResult, RunSummary};
+
+/// Metric columns, in table order. Each names a field of `RunSummary`.
+const METRICS: &[&str] = &[
+    "slots",
+    "protocol_blocks",
+    "liveness_slashes",
+    "double_signs",
+    "first_jail_slot",
+    "validators",
+    "active",
+    "jailed",
+    "max_drawdown_bps",
+    "genesis_bonded",
+    "bonded",
+    "slashed",
+    "burned",
+    "total_supply",
+];
+
+/// `summary`'s fields as serialised, so the table writes amounts as the
+/// JSON output does.
+fn summary_fields(summary: &RunSummary) -> io::Result<Map<String, Value>> {
+    match serde_json::to_value(summary)? {
+        Value::Object(fields) => Ok(fields),
+        other => Err(io::Error::new(io::ErrorKind::InvalidData, format!("run summary serialised as {}", other))),
+    }
+}
+
+/// The text of metric `name`; empty for a missing optional value.
+fn metric(fields: &Map<String, Value>, name: &str) -> io::Result<String> {
+    match fields.get(name) {
+        Some(Value::Null) => Ok(String::new()),
+        Some(value) => Ok(value_text(value)),
+        None => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown metric {:?}", name))),
+    }
+}
+
+/// Quote a CSV field if it needs it.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// One row per run: `run`, `seed`, each swept parameter, every metric, `error`.
+pub fn write_csv<W: Write>(results: &[RunResult], mut out: W) -> io::Result<()> {
+    let params: Vec<&str> = results
+        .first()
+        .map(|r| r.params.iter().map(|(p, _)| p.as_str()).collect())
+        .unwrap_or_default();
+
+    let header: Vec<String> = ["run", "seed"]
+        .iter()
+        .chain(params.iter())
+        .chain(METRICS.iter())
+        .chain(["error"].iter())
+        .map(|h| csv_field(h))
+        .collect();
+    writeln!(out, "{}", header.join(","))?;
+
+    for result in results {
+        let mut row = vec![result.index.to_string(), result.seed.to_string()];
+        row.extend(result.params.iter().map(|(_, v)| csv_field(&value_text(v))));
+        match &result.summary {
+            Ok(summary) => {
+                let fields = summary_fields(summary)?;
+                for name in METRICS {
+                    row.push(csv_field(&metric(&fields, name)?));
+                }
+                row.push(String::new());
+            }
+            Err(e) => {
+                row.extend(METRICS.iter().map(|_| String::new()));
+                row.push(csv_field(e));
+            }
+        }
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// A JSON array with one object per run.
+pub fn write_json<W: Write>(results: &[RunResult], mut out: W) -> io::Result<()> {
+    let rows: Vec<Value> = results
+        .iter()
+        .map(|r| {
+            let params: Map<String, Value> = r.params.iter().cloned().collect();
+            let (summary, error) = match &r.summary {
+                Ok(summary) => (serde_json::to_value(summary).unwrap_or(Value::Null), Value::Null),
+                Err(e) => (Value::Null, Value::from(e.as_str())),
+            };
+            json!({ "run": r.index, "seed": r.seed, "params": params, "summary": summary, "error": error })
+        })
+        .collect();
+    serde_json::to_writer_pretty(&mut out, &rows)?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::config::Scenario;
+    use crate::sweep::runner::run_scenario;
+
+    #[test]
+    fn metrics_are_the_summary_fields() {
+        let scenario = Scenario::load("scenarios/default.toml").unwrap();
+        let summary = run_scenario(&scenario).unwrap();
+        let fields = summary_fields(&summary).unwrap();
+
+        let mut names: Vec<&str> = METRICS.to_vec();
+        names.sort_unstable();
+        assert_eq!(names, fields.keys().map(String::as_str).collect::<Vec<_>>());
+        assert_eq!(metric(&fields, "slots").unwrap(), summary.slots.to_string());
+        assert_eq!(metric(&fields, "slot").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,24 @@
+//! Token amounts are `u128`, which TOML cannot represent natively. They are
+//! accepted either as integers or as decimal strings.
+use serde::{Deserialize, Deserializer, Serializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Raw {
+    Int(u64),
+    Str(String),
+}
+
+pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    match u64::try_from(*value) {
+        Ok(v) => serializer.serialize_u64(v),
+        Err(_) => serializer.serialize_str(&value.to_string()),
+    }
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    match Raw::deserialize(deserializer)? {
+        Raw::Int(v) => Ok(v as u128),
+        Raw::Str(s) => s.replace('_', "").parse().map_err(serde::de::Error::custom),
+    }
+}
@@ -1,6 +1,53 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::types::hex;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Block {
     pub slot_index: u64,
+    pub sub_epoch_index: u64,
     pub timestamp_ms: u64,
     pub proposer: Option<u64>, // None = protocol block
+    /// `state::root::state_root` once the slot is processed, including the
+    /// epoch or sub-epoch boundary it closes, i.e. the state the next slot starts from.
+    #[serde(with = "hex")]
+    pub state_root: [u8; 32],
+    /// `hash()` of the previous block; all zero for the first one.
+    #[serde(with = "hex")]
+    pub parent_hash: [u8; 32],
+}
+
+impl Block {
+    /// Commitment to every field, so it covers the whole chain up to here.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"eternix/block");
+        hasher.update(self.slot_index.to_be_bytes());
+        hasher.update(self.sub_epoch_index.to_be_bytes());
+        hasher.update(self.timestamp_ms.to_be_bytes());
+        match self.proposer {
+            Some(vid) => {
+                hasher.update([1]);
+                hasher.update(vid.to_be_bytes());
+            }
+            None => hasher.update([0]),
+        }
+        hasher.update(self.state_root);
+        hasher.update(self.parent_hash);
+        hasher.finalize().into()
+    }
+}
+
+/// Slot of the first block at which two chains disagree, including one
+/// ending before the other; `None` if they are identical.
+pub fn first_divergence(a: &[Block], b: &[Block]) -> Option<u64> {
+    match a.iter().zip(b).find(|(x, y)| x != y) {
+        Some((x, _)) => Some(x.slot_index),
+        None if a.len() != b.len() => {
+            let shorter = a.len().min(b.len());
+            a.get(shorter).or(b.get(shorter)).map(|block| block.slot_index)
+        }
+        None => None,
+    }
 }
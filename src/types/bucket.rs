@@ -1,7 +1,9 @@
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Bucket {
     pub id: u64,
-    pub ticket_ids: HashSet<u64>,
+    pub ticket_ids: BTreeSet<u64>,
 }
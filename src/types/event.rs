@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use crate::economics::rewards::RewardDestination;
+use crate::economics::slashing::SlashBasis;
+use crate::sim::timeline::Action;
+use crate::types::amount;
+use crate::types::validator::ValidatorState;
+
+/// Why the protocol produced a block instead of the leader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolBlockReason {
+    NoEligibleTickets,
+    LeaderMissed,
+    DoubleSign,
+}
+
+/// Everything observable that happens during a run.
+///
+/// State and consensus functions push these into an event buffer; the
+/// simulator stamps them with slot/epoch and hands them to its sinks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SimEvent {
+    LeaderSelected { leader: u64 },
+    BlockProposed { proposer: u64, block_id: u64 },
+    ProtocolBlock { reason: ProtocolBlockReason },
+    MissRecorded { validator: u64, miss_counter: u32 },
+    /// `bps` of `basis` was slashed; `amount` is what was actually taken.
+    LivenessSlash {
+        validator: u64,
+        bps: u32,
+        basis: SlashBasis,
+        #[serde(with = "amount")]
+        amount: u128,
+        #[serde(with = "amount")]
+        new_vault: u128,
+    },
+    DoubleSign {
+        validator: u64,
+        offense: u8,
+        bps: u32,
+        basis: SlashBasis,
+        #[serde(with = "amount")]
+        amount: u128,
+        #[serde(with = "amount")]
+        new_vault: u128,
+    },
+    /// An equivocation was seen; it is punished once its evidence is included.
+    EvidenceObserved { validator: u64, slot: u64 },
+    EvidenceSubmitted { validator: u64, slot: u64, reporter: Option<u64> },
+    EvidenceIncluded { validator: u64, slot: u64, reporter: Option<u64> },
+    EvidenceExpired { validator: u64, slot: u64 },
+    Jailed { validator: u64 },
+    CooldownStarted { validator: u64, until_epoch: u64 },
+    CooldownEnded { validator: u64, new_state: ValidatorState },
+    PausedLowVault { validator: u64 },
+    ValidatorInactive { validator: u64 },
+    VaultRefilled {
+        validator: u64,
+        #[serde(with = "amount")]
+        amount: u128,
+        #[serde(with = "amount")]
+        new_vault: u128,
+    },
+    Rejoined { validator: u64 },
+    ValidatorJoined { validator: u64, state: ValidatorState },
+    TicketIssued { ticket: u64, owner: u64, bucket: u64 },
+    TicketRetireScheduled { ticket: u64, validator: u64, epoch: u64 },
+    RetireBegin { ticket_ids: Vec<u64> },
+    TicketRetiring { ticket: u64, effective_epoch: u64 },
+    RetireFinalize { ticket_ids: Vec<u64> },
+    TicketDead { ticket: u64 },
+    EpochTransition { epoch: u64 },
+    EpochSeed { seed: [u8; 32] },
+    SubEpochTransition { epoch: u64, sub_epoch: u64 },
+    LivenessSnapshot { validator: u64, proposed: u64, missed: u64 },
+    RefillQueued {
+        validator: u64,
+        #[serde(with = "amount")]
+        amount: u128,
+    },
+    ScheduledAction { action: Action, error: Option<String> },
+    RewardPaid {
+        validator: u64,
+        #[serde(with = "amount")]
+        amount: u128,
+        destination: RewardDestination,
+    },
+    RewardBurned {
+        #[serde(with = "amount")]
+        amount: u128,
+    },
+    RewardToTreasury {
+        #[serde(with = "amount")]
+        amount: u128,
+    },
+    /// Total rewards paid to `validator` over `epoch`.
+    EpochRewards {
+        epoch: u64,
+        validator: u64,
+        #[serde(with = "amount")]
+        amount: u128,
+    },
+    InflationMinted {
+        epoch: u64,
+        #[serde(with = "amount")]
+        amount: u128,
+        annual_rate_bps: u64,
+    },
+    /// Where a slash went; `amount` of the matching slash event = sum of the parts.
+    SlashSettled {
+        offender: u64,
+        #[serde(with = "amount")]
+        burned: u128,
+        #[serde(with = "amount")]
+        to_treasury: u128,
+        #[serde(with = "amount")]
+        redistributed: u128,
+        reporter: Option<u64>,
+        #[serde(with = "amount")]
+        whistleblower_reward: u128,
+    },
+    EpochRewardSummary {
+        epoch: u64,
+        #[serde(with = "amount")]
+        paid: u128,
+        #[serde(with = "amount")]
+        burned: u128,
+        #[serde(with = "amount")]
+        to_treasury: u128,
+    },
+}
+
+/// A `SimEvent` stamped with when it happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub slot: u64,
+    pub epoch: u64,
+    pub sub_epoch: u64,
+    pub event: SimEvent,
+}
@@ -0,0 +1,40 @@
+//! 32-byte hashes written as lowercase hex strings rather than arrays of numbers.
+use serde::{Deserialize, Deserializer, Serializer};
+
+fn encode(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode<E: serde::de::Error>(text: &str) -> Result<[u8; 32], E> {
+    let digits = text.as_bytes();
+    if digits.len() != 64 || !text.is_ascii() {
+        return Err(E::custom(format!("expected 64 hex digits, got {:?}", text)));
+    }
+    let mut bytes = [0; 32];
+    for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(E::custom)?;
+        *byte = u8::from_str_radix(pair, 16).map_err(E::custom)?;
+    }
+    Ok(bytes)
+}
+
+pub fn serialize<S: Serializer>(value: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(value))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+    decode(&String::deserialize(deserializer)?)
+}
+
+/// The same for a list of hashes.
+pub mod list {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(value.iter().map(super::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<[u8; 32]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?.iter().map(|text| super::decode(text)).collect()
+    }
+}
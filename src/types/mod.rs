@@ -2,4 +2,7 @@ pub mod validator;
 pub mod ticket;
 pub mod bucket;
 pub mod block;
-pub mod proposal;
\ No newline at end of file
+pub mod proposal;
+pub mod event;
+pub mod amount;
+pub mod hex;
@@ -1,8 +1,12 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Ticket {
     pub id: u64,
     pub owner: u64,
     pub bucket: u64,
+    /// ACTIVE bucket the ticket returns to whenever its owner becomes Active again.
+    pub home_bucket: u64,
     pub creation_epoch: u64,
 
     // retirement lifecycle
@@ -11,7 +15,8 @@ pub struct Ticket {
     pub retire_effective_epoch: Option<u64>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TicketState {
     Active,
     Retiring, // requested, not yet DEAD
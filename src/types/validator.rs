@@ -1,15 +1,25 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+use crate::types::amount;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Validator {
     pub id: u64,
     pub state: ValidatorState,
+    #[serde(with = "amount")]
     pub vault_balance: u128,
+    #[serde(with = "amount")]
     pub initial_bond: u128,
+    /// Rewards credited outside the vault; see `economics::rewards::RewardDestination`.
+    #[serde(with = "amount")]
+    pub withdrawable_balance: u128,
     pub miss_counter: u32,
     pub double_sign_offenses: u8,
     pub cooldown_until_epoch: Option<u64>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ValidatorState {
     Active,
     PausedLowVault,
@@ -17,3 +27,10 @@ pub enum ValidatorState {
     Inactive,
     Jailed,
 }
+
+/// Per-validator leader outcomes within the current sub-epoch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LivenessStats {
+    pub proposed: u64,
+    pub missed: u64,
+}